@@ -1,32 +1,110 @@
 use anyhow::Result;
 use eframe::egui;
 use portable_pty::{CommandBuilder, PtySize};
-use std::io::Write;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use unicode_width::UnicodeWidthChar;
-use vte::Parser;
 
+use crate::cli::Cli;
+use crate::config::bindings::{Action, Bindings};
+use crate::config::watcher::ConfigWatcher;
+use crate::config::{expand_home, CloseOnExit, Config};
 use crate::ime::korean::KoreanInputState;
-use crate::terminal::performer::TerminalPerformer;
-use crate::terminal::state::TerminalState;
+use crate::layout::{neighbor, Direction, Divider, SplitAxis, Tab, DIVIDER_WIDTH};
+use crate::pty::ChildExit;
+use crate::session::Session;
+use crate::terminal::hints::{HintAction, HintState};
+use crate::terminal::links::{Link, LinkDetector};
+use crate::terminal::search::SearchState;
+use crate::terminal::selection::{Selection, SelectionPoint, SelectionType};
+use crate::terminal::state::{TerminalCell, TerminalState};
+use crate::terminal::vi_mode::{ViAction, ViKey, ViMode, ViMotion};
+use crate::utils::clipboard::{paste_sequence, platform_primary_selection, PrimarySelection};
+use crate::utils::color::{Palette, TermColor};
+
+// Translucent overlay drawn over selected cells
+const SELECTION_COLOR: egui::Color32 = egui::Color32::from_rgba_premultiplied(40, 70, 120, 110);
+// Search hits, and the hit that next/previous navigation is on
+const SEARCH_MATCH_COLOR: egui::Color32 = egui::Color32::from_rgba_premultiplied(110, 100, 20, 120);
+const SEARCH_CURRENT_COLOR: egui::Color32 =
+    egui::Color32::from_rgba_premultiplied(170, 90, 10, 170);
+// Underline of the link under the pointer (Ctrl+hover)
+const LINK_COLOR: egui::Color32 = egui::Color32::from_rgb(100, 170, 255);
+// Hint mode: matched text and the label drawn over its first cells
+const HINT_MATCH_COLOR: egui::Color32 = egui::Color32::from_rgba_premultiplied(60, 90, 40, 90);
+const HINT_LABEL_COLOR: egui::Color32 = egui::Color32::from_rgb(250, 210, 80);
+// Copy mode cursor
+const VI_CURSOR_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 180, 60);
+// Notification text colors and how long a notification stays up
+const TOAST_INFO_COLOR: egui::Color32 = egui::Color32::from_rgb(150, 220, 150);
+const TOAST_WARNING_COLOR: egui::Color32 = egui::Color32::from_rgb(250, 200, 90);
+const TOAST_ERROR_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 120, 100);
+const TOAST_DURATION: Duration = Duration::from_secs(6);
+// Split panes: dividers (brighter while hovered / dragged) and the outline
+// of the focused pane
+const DIVIDER_COLOR: egui::Color32 = egui::Color32::from_rgb(80, 80, 80);
+const DIVIDER_ACTIVE_COLOR: egui::Color32 = egui::Color32::from_rgb(140, 140, 140);
+const FOCUS_BORDER_COLOR: egui::Color32 = egui::Color32::from_rgb(90, 130, 200);
+// Outline and badge of panes in the broadcast group
+const BROADCAST_COLOR: egui::Color32 = egui::Color32::from_rgb(240, 140, 40);
+
+// Short notification at the bottom of the window (config reloads, ...)
+struct Toast {
+    message: String,
+    color: egui::Color32,
+    shown_at: Instant,
+}
+
+// What the close confirmation is about
+enum CloseTarget {
+    Window,
+    Tab(u64),  // Tab id
+    Pane(u64), // Session id
+}
+
+// Native window title unless --title or the program sets one
+pub const DEFAULT_WINDOW_TITLE: &str = "WTerm - 터미널";
+// Centered in the custom title bar while there is a single tab
+const TITLE_BAR_TEXT: &str = "🖥️ WTerm: macOS 스타일 터미널";
 
 // Main terminal application
 pub struct TerminalApp {
-    terminal_state: Arc<Mutex<TerminalState>>,
-    pty_writer: Arc<Mutex<Box<dyn Write + Send>>>,
-    pty_master: Arc<Mutex<Box<dyn portable_pty::MasterPty + Send>>>,
+    tabs: Vec<Tab>,                                    // In tab order
+    sessions: BTreeMap<u64, Session>,                  // Every pane of every tab, by id
+    active: usize,                                     // Index of the tab being shown
+    next_id: u64,                                      // For new tabs and sessions
+    pane_area: egui::Rect,                             // Where the active tab's panes were laid out
+    renaming: Option<(u64, String)>,                   // Tab being renamed and the edited name
+    hold: bool,                                        // --hold: never close on exit
+    close_confirm: Option<(CloseTarget, Vec<String>)>, // Programs listed in the close confirmation
+    close_confirmed: bool,                             // Let the next close request through
+    config: Config,
+    title: Option<String>,                 // --title
+    window_title: String,                  // Last title sent to the native window
+    tab_strip_rect: Option<egui::Rect>,    // Clicks here don't drag the window
+    palette: Palette,                      // Resolved from config.colors
+    fonts_version: u64, // Bumped when reloaded fonts take effect, for the row caches
+    fonts_reloaded: bool, // set_fonts called; the new fonts are used from the next frame
+    bindings: Bindings, // Resolved from config.keybindings
+    config_watcher: Option<ConfigWatcher>, // Live reload of the config file
+    toast: Option<Toast>,
     korean_state: KoreanInputState,
+    primary_selection: Option<Box<dyn PrimarySelection>>, // X11/Wayland PRIMARY (Linux only)
+    search: SearchState,
+    vi_mode: ViMode,                // Copy mode over scrollback (Ctrl+Shift+Space)
+    links: LinkDetector,            // URL / file:line detection for Ctrl+click
+    hints: HintState,               // Keyboard hint labels (Ctrl+Shift+H)
     last_tab_time: Option<Instant>, // Tab key debouncing
-    initial_focus_set: bool,        // Flag to track if initial focus has been set
+    focus_set: bool,                // Terminal focused since start / the last focus change
 }
 
 impl TerminalApp {
     // Process text input with Korean composition support
     fn process_text_input(&mut self, text: &str) {
         // Reset arrow key state when text is being input
-        if let Ok(mut state) = self.terminal_state.lock() {
+        if let Ok(mut state) = self.terminal_state().lock() {
             state.clear_arrow_key_protection();
         }
 
@@ -159,38 +237,1084 @@ impl TerminalApp {
         //     text.as_bytes()
         // ); // Disabled for performance
 
-        if let Ok(mut writer) = self.pty_writer.lock() {
-            let _ = writer.write_all(text.as_bytes());
-            let _ = writer.flush();
+        self.send_to_group(|_| Some(text.to_string()));
+    }
+
+    // Write to the focused session and, if it is in the broadcast group, to
+    // every other member. `text_for` gives what each one gets, from its state.
+    fn send_to_group(&mut self, text_for: impl Fn(&TerminalState) -> Option<String>) {
+        self.snap_to_bottom();
+        let focus = self.tab().focus;
+        let session = self.session_mut();
+        let text = session
+            .terminal_state
+            .lock()
+            .ok()
+            .and_then(|state| text_for(&state));
+        if let Some(text) = text {
+            session.write(&text);
+        }
+        if !session.broadcast {
+            return;
+        }
+        // Typing into a member of the broadcast group types into all of them
+        for (id, member) in self.sessions.iter_mut() {
+            if *id == focus || !member.broadcast || member.exit_handled {
+                continue;
+            }
+            let text = match member.terminal_state.lock() {
+                Ok(mut state) => {
+                    state.scroll_display_to_bottom();
+                    text_for(&state)
+                }
+                Err(_) => continue,
+            };
+            if let Some(text) = text {
+                member.write(&text);
+            }
+        }
+    }
+
+    // Put `ids` into the broadcast group, or take them out if they are all
+    // in it already
+    fn toggle_broadcast(&mut self, ids: &[u64]) {
+        let join = ids
+            .iter()
+            .any(|id| self.sessions.get(id).is_some_and(|s| !s.broadcast));
+        for id in ids {
+            if let Some(session) = self.sessions.get_mut(id) {
+                session.broadcast = join;
+            }
+        }
+        let members = self.sessions.values().filter(|s| s.broadcast).count();
+        println!("📡 Broadcast group: {} session(s)", members);
+        let message = if members == 0 {
+            "입력 브로드캐스트 꺼짐".to_string()
+        } else {
+            format!("입력 브로드캐스트: {}개 패널", members)
+        };
+        self.show_toast(message, BROADCAST_COLOR);
+    }
+
+    // Typing returns a scrolled-back viewport to the live screen
+    fn snap_to_bottom(&self) {
+        if let Ok(mut state) = self.terminal_state().lock() {
+            state.scroll_display_to_bottom();
+        }
+    }
+
+    // Copy the current selection to the system clipboard
+    fn copy_selection(&self, ctx: &egui::Context) {
+        let text = self
+            .terminal_state()
+            .lock()
+            .ok()
+            .and_then(|state| state.selection_to_string());
+        if let Some(text) = text {
+            ctx.copy_text(text);
+        }
+    }
+
+    // Publish the current selection as the PRIMARY selection
+    fn update_primary_selection(&mut self) {
+        let text = self
+            .terminal_state()
+            .lock()
+            .ok()
+            .and_then(|state| state.selection_to_string());
+        if let (Some(primary), Some(text)) = (self.primary_selection.as_mut(), text) {
+            primary.set_text(&text);
+        }
+    }
+
+    // Middle-click paste from the PRIMARY selection
+    fn paste_primary_selection(&mut self) {
+        let text = self
+            .primary_selection
+            .as_mut()
+            .and_then(|primary| primary.get_text());
+        if let Some(text) = text {
+            self.paste_text(&text);
+        }
+    }
+
+    // Send pasted text to the PTY, bracketed if the application enabled mode
+    // 2004. Each member of a broadcast group gets the markers its own program
+    // asked for.
+    fn paste_text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        self.finalize_korean_composition();
+        self.send_to_group(|state| paste_sequence(text, state.bracketed_paste));
+    }
+
+    // Floating search bar in the top-right corner of the terminal area
+    fn show_search_bar(&mut self, ctx: &egui::Context, terminal_rect: egui::Rect) {
+        let input_id = egui::Id::new("terminal_search_input");
+        let mut query_changed = false;
+        let mut options_changed = false;
+        let mut close = false;
+
+        egui::Area::new(egui::Id::new("terminal_search_bar"))
+            .order(egui::Order::Foreground)
+            .pivot(egui::Align2::RIGHT_TOP)
+            .fixed_pos(terminal_rect.right_top() + egui::vec2(-16.0, 4.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        let response = ui.add(
+                            egui::TextEdit::singleline(&mut self.search.query)
+                                .id(input_id)
+                                .hint_text("검색 (Enter: 다음, Shift+Enter: 이전)")
+                                .desired_width(220.0),
+                        );
+                        if self.search.focus_requested {
+                            response.request_focus();
+                            self.search.focus_requested = false;
+                        }
+                        query_changed |= response.changed();
+
+                        // Single-line TextEdit gives up focus on Enter; keep typing in the bar
+                        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                            if ui.input(|i| i.modifiers.shift) {
+                                self.search.prev_match();
+                            } else {
+                                self.search.next_match();
+                            }
+                            response.request_focus();
+                        }
+
+                        options_changed |= ui
+                            .toggle_value(&mut self.search.use_regex, ".*")
+                            .on_hover_text("정규식")
+                            .changed();
+                        options_changed |= ui
+                            .toggle_value(&mut self.search.case_sensitive, "Aa")
+                            .on_hover_text("대소문자 구분")
+                            .changed();
+
+                        let status_color = if self.search.error.is_some() {
+                            egui::Color32::from_rgb(255, 120, 100)
+                        } else {
+                            ui.visuals().weak_text_color()
+                        };
+                        ui.colored_label(status_color, self.search.status());
+
+                        if ui.small_button("▲").clicked() {
+                            self.search.prev_match();
+                        }
+                        if ui.small_button("▼").clicked() {
+                            self.search.next_match();
+                        }
+                        if ui.small_button("✕").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            });
+
+        if close {
+            self.search.close();
+        } else if query_changed || options_changed {
+            if let Ok(state) = self.terminal_state().lock() {
+                self.search.query_changed(&state);
+            }
+        }
+    }
+
+    // Run a key binding action
+    fn perform_action(&mut self, ctx: &egui::Context, action: Action) {
+        match action {
+            Action::ToggleSearch => {
+                if self.search.active {
+                    self.search.close();
+                } else {
+                    self.search.open();
+                    if let Ok(state) = self.terminal_state().lock() {
+                        self.search.refresh(&state);
+                    }
+                }
+            }
+            Action::ToggleCopyMode => {
+                self.finalize_korean_composition();
+                if let Ok(mut state) = self.terminal_state().lock() {
+                    if self.vi_mode.active {
+                        self.vi_mode.exit(&mut state);
+                    } else {
+                        self.vi_mode.enter(&mut state);
+                    }
+                }
+            }
+            // Label the URLs, paths, hashes, ... on screen
+            Action::ToggleHints if !self.vi_mode.active => {
+                if self.hints.active {
+                    self.hints.cancel();
+                } else if let Ok(state) = self.terminal_state().lock() {
+                    let (first, last) = (state.visible_start_row, state.visible_end_row);
+                    if !self.hints.start(&state, first, last) {
+                        println!("🏷️ No hint targets on screen");
+                    }
+                }
+            }
+            // Scroll the viewport locally, nothing goes to the PTY
+            Action::ScrollPageUp
+            | Action::ScrollPageDown
+            | Action::ScrollToTop
+            | Action::ScrollToBottom => {
+                if let Ok(mut state) = self.terminal_state().lock() {
+                    let page = state.rows.saturating_sub(1).max(1);
+                    match action {
+                        Action::ScrollPageUp => state.scroll_display_up(page),
+                        Action::ScrollPageDown => state.scroll_display_down(page),
+                        Action::ScrollToTop => state.scroll_display_to_top(),
+                        _ => state.scroll_display_to_bottom(),
+                    }
+                }
+            }
+            Action::NewTab => self.new_tab(ctx),
+            Action::CloseTab => {
+                let id = self.tab().id;
+                self.request_close_tab(ctx, id);
+            }
+            Action::NextTab => self.activate((self.active + 1) % self.tabs.len()),
+            Action::PreviousTab => {
+                let count = self.tabs.len();
+                self.activate((self.active + count - 1) % count);
+            }
+            Action::MoveTabLeft => self.move_active_tab(-1),
+            Action::MoveTabRight => self.move_active_tab(1),
+            Action::SplitRight => self.split_pane(ctx, SplitAxis::LeftRight),
+            Action::SplitDown => self.split_pane(ctx, SplitAxis::TopBottom),
+            Action::ClosePane => {
+                let id = self.tab().focus;
+                self.request_close_pane(ctx, id);
+            }
+            Action::FocusPaneLeft => self.move_focus(Direction::Left),
+            Action::FocusPaneRight => self.move_focus(Direction::Right),
+            Action::FocusPaneUp => self.move_focus(Direction::Up),
+            Action::FocusPaneDown => self.move_focus(Direction::Down),
+            // Let the focused pane fill the tab, or bring the others back
+            Action::ToggleZoom => {
+                let tab = &mut self.tabs[self.active];
+                tab.zoomed = !tab.zoomed && tab.layout.panes().len() > 1;
+            }
+            // The focused pane joins / leaves the broadcast group
+            Action::ToggleBroadcast => {
+                let id = self.tab().focus;
+                self.toggle_broadcast(&[id]);
+            }
+            // Every pane of the active tab joins / leaves it
+            Action::BroadcastTab => {
+                let panes = self.tab().layout.panes();
+                self.toggle_broadcast(&panes);
+            }
+            Action::ToggleHints | Action::None => {}
+        }
+    }
+
+    // Translate this frame's key events for copy mode / hint mode and take them
+    // away from the PTY input handling further down
+    fn take_modal_keys(ctx: &egui::Context) -> Vec<ViKey> {
+        ctx.input_mut(|i| {
+            let mut keys = Vec::new();
+            let block_paste = i.modifiers.ctrl && !i.modifiers.shift;
+            i.events.retain(|event| {
+                match event {
+                    egui::Event::Text(text) | egui::Event::Ime(egui::ImeEvent::Commit(text)) => {
+                        keys.extend(text.chars().map(ViKey::Char));
+                    }
+                    // Ctrl+V arrives as a paste on Linux/Windows
+                    egui::Event::Paste(_) if block_paste => keys.push(ViKey::BlockSelection),
+                    egui::Event::Copy => keys.push(ViKey::Char('y')),
+                    egui::Event::Key {
+                        key,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } => match key {
+                        egui::Key::V | egui::Key::Q if modifiers.ctrl => {
+                            keys.push(ViKey::BlockSelection)
+                        }
+                        egui::Key::Enter => keys.push(ViKey::Enter),
+                        egui::Key::Backspace => keys.push(ViKey::Backspace),
+                        egui::Key::Escape => keys.push(ViKey::Escape),
+                        egui::Key::ArrowLeft => keys.push(ViKey::Motion(ViMotion::Left)),
+                        egui::Key::ArrowDown => keys.push(ViKey::Motion(ViMotion::Down)),
+                        egui::Key::ArrowUp => keys.push(ViKey::Motion(ViMotion::Up)),
+                        egui::Key::ArrowRight => keys.push(ViKey::Motion(ViMotion::Right)),
+                        _ => {}
+                    },
+                    egui::Event::Key { .. }
+                    | egui::Event::Cut
+                    | egui::Event::Paste(_)
+                    | egui::Event::Ime(_) => {}
+                    _ => return true,
+                }
+                false
+            });
+            keys
+        })
+    }
+
+    // Mode / search prompt indicator in the bottom-right corner while in copy mode
+    fn show_vi_status(&self, ctx: &egui::Context, terminal_rect: egui::Rect) {
+        let Some(status) = self
+            .terminal_state()
+            .lock()
+            .ok()
+            .map(|state| self.vi_mode.status(&state))
+        else {
+            return;
+        };
+        egui::Area::new(egui::Id::new("terminal_vi_status"))
+            .order(egui::Order::Foreground)
+            .pivot(egui::Align2::RIGHT_BOTTOM)
+            .fixed_pos(terminal_rect.right_bottom() + egui::vec2(-16.0, -4.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label(
+                        egui::RichText::new(status)
+                            .monospace()
+                            .color(VI_CURSOR_COLOR),
+                    );
+                });
+            });
+    }
+
+    // The active tab
+    fn tab(&self) -> &Tab {
+        &self.tabs[self.active]
+    }
+
+    // The focused pane of the active tab: where keys, search and copy mode go
+    fn session(&self) -> &Session {
+        &self.sessions[&self.tab().focus]
+    }
+
+    fn session_mut(&mut self) -> &mut Session {
+        let focus = self.tab().focus;
+        self.sessions
+            .get_mut(&focus)
+            .expect("every pane has a session")
+    }
+
+    // Grid of the focused pane. Returns the Arc so a lock can be held while
+    // other fields of `self` are borrowed.
+    fn terminal_state(&self) -> Arc<Mutex<TerminalState>> {
+        self.session().terminal_state.clone()
+    }
+
+    // The user's name for the tab, else the focused pane's title
+    fn tab_title(&self, tab: &Tab) -> String {
+        tab.name
+            .clone()
+            .unwrap_or_else(|| self.sessions[&tab.focus].title())
+    }
+
+    // Finish the per-pane modes (composition, search, copy mode, hints) on
+    // the focused pane before the focus moves away from it
+    fn leave_focus(&mut self) {
+        self.finalize_korean_composition();
+        self.search.close();
+        self.hints.cancel();
+        if self.vi_mode.active {
+            if let Ok(mut state) = self.terminal_state().lock() {
+                self.vi_mode.exit(&mut state);
+            }
+        }
+    }
+
+    // Switch tabs
+    fn activate(&mut self, index: usize) {
+        if index == self.active || index >= self.tabs.len() {
+            return;
+        }
+        self.leave_focus();
+        // Start the tab being left clean so later output shows as activity.
+        // A pane in the middle of a synchronized update stays as it is.
+        for id in self.tab().layout.panes() {
+            if let Ok(mut state) = self.sessions[&id].terminal_state.lock() {
+                state.update_render_buffer_if_dirty();
+            }
+        }
+        self.renaming = None;
+        self.active = index;
+        self.focus_set = false;
+        self.clear_tab_indicators();
+    }
+
+    // Give the keyboard to pane `id` of the active tab
+    fn focus_pane(&mut self, id: u64) {
+        if id == self.tab().focus || !self.tab().layout.contains(id) {
+            return;
+        }
+        self.leave_focus();
+        let tab = &mut self.tabs[self.active];
+        tab.focus = id;
+        tab.zoomed = false;
+        self.focus_set = false;
+    }
+
+    // Alt+arrows: the pane next to the focused one, as laid out last frame
+    fn move_focus(&mut self, direction: Direction) {
+        let tab = self.tab();
+        let mut panes = Vec::new();
+        tab.layout
+            .layout(self.pane_area, &mut panes, &mut Vec::new());
+        if let Some(id) = neighbor(&panes, tab.focus, direction) {
+            self.focus_pane(id);
         }
     }
 
-    pub fn new(cc: &eframe::CreationContext<'_>) -> anyhow::Result<Self> {
-        // Configure custom font with better fallback
-        let mut fonts = egui::FontDefinitions::default();
+    // Start a shell in the directory the focused pane's shell reported. It
+    // gets the focused pane's size until the next frame lays it out.
+    fn spawn_session(&mut self, ctx: &egui::Context) -> Result<u64> {
+        let cwd = self
+            .session()
+            .working_directory()
+            .or_else(|| self.config.shell.working_directory());
+        let size = self
+            .terminal_state()
+            .lock()
+            .map(|state| PtySize {
+                rows: state.rows as u16,
+                cols: state.cols as u16,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .unwrap_or_default();
+        let id = self.next_id;
+        let (session, program, fallback_reason) =
+            spawn_shell(id, &self.config, cwd, size, None, ctx)?;
+        if let Some(e) = fallback_reason {
+            self.show_shell_fallback(&program, &e);
+        }
+        self.next_id += 1;
+        self.sessions.insert(id, session);
+        Ok(id)
+    }
 
-        // Load D2Coding font from file
-        let d2coding_font_data = include_bytes!("../assets/fonts/D2Coding.ttf");
-        fonts.font_data.insert(
-            "D2Coding".to_owned(),
-            std::sync::Arc::new(egui::FontData::from_static(d2coding_font_data)),
+    // The configured shell didn't start and `program` runs instead
+    fn show_shell_fallback(&mut self, program: &str, error: &anyhow::Error) {
+        self.show_toast(
+            format!(
+                "셸을 시작할 수 없어 {} 로 대신 시작했습니다: {:#}",
+                program, error
+            ),
+            TOAST_ERROR_COLOR,
         );
+    }
+
+    // Open a tab next to the active one
+    fn new_tab(&mut self, ctx: &egui::Context) {
+        match self.spawn_session(ctx) {
+            Ok(session) => {
+                let id = self.next_id;
+                self.next_id += 1;
+                println!("🗂️ New tab {}", id);
+                self.tabs.insert(self.active + 1, Tab::new(id, session));
+                self.activate(self.active + 1);
+            }
+            Err(e) => {
+                eprintln!("❌ Failed to open a tab: {:#}", e);
+                self.show_toast(
+                    format!("새 탭을 열 수 없습니다: {:#}", e),
+                    TOAST_ERROR_COLOR,
+                );
+            }
+        }
+    }
+
+    // Split the focused pane; the new shell goes right of / below it and
+    // gets the focus
+    fn split_pane(&mut self, ctx: &egui::Context, axis: SplitAxis) {
+        match self.spawn_session(ctx) {
+            Ok(id) => {
+                println!("🗂️ New pane {} ({:?})", id, axis);
+                let tab = &mut self.tabs[self.active];
+                tab.layout.split(tab.focus, id, axis);
+                tab.zoomed = false;
+                self.focus_pane(id);
+            }
+            Err(e) => {
+                eprintln!("❌ Failed to split the pane: {:#}", e);
+                self.show_toast(format!("창을 나눌 수 없습니다: {:#}", e), TOAST_ERROR_COLOR);
+            }
+        }
+    }
+
+    // Close a pane right away; closing the last pane of a tab closes the tab
+    fn close_pane(&mut self, ctx: &egui::Context, id: u64) {
+        let Some(index) = self.tabs.iter().position(|tab| tab.layout.contains(id)) else {
+            return;
+        };
+        if self.tabs[index].layout.panes().len() == 1 {
+            let tab_id = self.tabs[index].id;
+            self.close_tab(ctx, tab_id);
+            return;
+        }
+        if index == self.active && self.tab().focus == id {
+            self.leave_focus();
+            self.focus_set = false;
+        }
+        let tab = &mut self.tabs[index];
+        tab.layout.remove(id);
+        tab.zoomed = false;
+        if tab.focus == id {
+            tab.focus = tab.layout.panes()[0];
+        }
+        // Dropping the session closes its PTY, which hangs up the child
+        self.sessions.remove(&id);
+    }
+
+    // Close a tab and all its panes right away; closing the last one closes
+    // the window
+    fn close_tab(&mut self, ctx: &egui::Context, id: u64) {
+        let Some(index) = self.tabs.iter().position(|tab| tab.id == id) else {
+            return;
+        };
+        if self.tabs.len() == 1 {
+            self.close_window(ctx);
+            return;
+        }
+        if index == self.active {
+            self.leave_focus();
+        }
+        if self
+            .renaming
+            .as_ref()
+            .is_some_and(|(renaming, _)| *renaming == id)
+        {
+            self.renaming = None;
+        }
+        let tab = self.tabs.remove(index);
+        for session in tab.layout.panes() {
+            self.sessions.remove(&session);
+        }
+        if index < self.active || self.active == self.tabs.len() {
+            self.active -= 1;
+        }
+        self.clear_tab_indicators();
+        self.focus_set = false;
+    }
 
-        // Set D2Coding as the primary monospace font, but keep existing fallbacks
-        let monospace_fonts = fonts
-            .families
-            .get_mut(&egui::FontFamily::Monospace)
-            .unwrap();
-        monospace_fonts.insert(0, "D2Coding".to_owned());
+    // Close a pane, asking first if something other than the shell runs in it
+    fn request_close_pane(&mut self, ctx: &egui::Context, id: u64) {
+        let Some(session) = self.sessions.get(&id) else {
+            return;
+        };
+        let programs = session.foreground_programs();
+        if programs.is_empty() {
+            self.close_pane(ctx, id);
+        } else {
+            self.close_confirm = Some((CloseTarget::Pane(id), programs));
+        }
+    }
+
+    // Close a tab, asking first if something other than a shell runs in it
+    fn request_close_tab(&mut self, ctx: &egui::Context, id: u64) {
+        let Some(tab) = self.tabs.iter().find(|tab| tab.id == id) else {
+            return;
+        };
+        let mut programs: Vec<String> = Vec::new();
+        for session in tab.layout.panes() {
+            for program in self.sessions[&session].foreground_programs() {
+                if !programs.contains(&program) {
+                    programs.push(program);
+                }
+            }
+        }
+        if programs.is_empty() {
+            self.close_tab(ctx, id);
+        } else {
+            self.close_confirm = Some((CloseTarget::Tab(id), programs));
+        }
+    }
+
+    // Move the active tab one place left (-1) or right (+1)
+    fn move_active_tab(&mut self, delta: isize) {
+        let target = self.active as isize + delta;
+        if target < 0 || target as usize >= self.tabs.len() {
+            return;
+        }
+        self.tabs.swap(self.active, target as usize);
+        self.active = target as usize;
+    }
+
+    // The active tab has been seen
+    fn clear_tab_indicators(&mut self) {
+        for id in self.tabs[self.active].layout.panes() {
+            if let Some(session) = self.sessions.get_mut(&id) {
+                session.activity = false;
+                session.bell = false;
+            }
+        }
+    }
+
+    // Activity / bell bookkeeping for every pane, once per frame
+    fn update_tab_indicators(&mut self, ctx: &egui::Context) {
+        let window_focused = ctx.input(|i| i.viewport().focused).unwrap_or(true);
+        let active_panes = self.tab().layout.panes();
+        for (id, session) in self.sessions.iter_mut() {
+            let Ok(mut state) = session.terminal_state.lock() else {
+                continue;
+            };
+            let bell = std::mem::take(&mut state.bell);
+            if active_panes.contains(id) {
+                if bell && !window_focused {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(
+                        egui::UserAttentionType::Informational,
+                    ));
+                }
+            } else {
+                // Background tabs never rebuild their render buffer, so a dirty
+                // buffer means output arrived since the tab was left. Output held
+                // by a synchronized update counts once the update is published.
+                match state.synchronized_update_remaining() {
+                    Some(remaining) => ctx.request_repaint_after(remaining),
+                    None => session.activity |= state.render_buffer_dirty,
+                }
+                session.bell |= bell;
+            }
+        }
+    }
 
-        // Also add D2Coding to proportional for UI text
-        let proportional_fonts = fonts
-            .families
-            .get_mut(&egui::FontFamily::Proportional)
-            .unwrap();
-        proportional_fonts.insert(0, "D2Coding".to_owned());
+    // Follow the active tab's title in the native title bar / task bar
+    fn update_window_title(&mut self, ctx: &egui::Context) {
+        let title = self
+            .tab()
+            .name
+            .clone()
+            .or_else(|| self.session().custom_title())
+            .or_else(|| self.title.clone())
+            .unwrap_or_else(|| DEFAULT_WINDOW_TITLE.to_string());
+        if title != self.window_title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.window_title = title;
+        }
+    }
 
-        cc.egui_ctx.set_fonts(fonts);
+    // Tabs (click: switch, double-click: rename, right-click: menu) and a
+    // "+" button, laid out left to right inside `rect`
+    fn show_tab_strip(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, rect: egui::Rect) {
+        let mut switch_to = None;
+        let mut close = None;
+        let mut move_by = None;
+        let mut start_rename = None;
+        let mut finish_rename = false;
+        let mut open_tab = false;
+        let mut broadcast = None;
+
+        let rename_id = egui::Id::new("terminal_tab_rename");
+        let strip = ui.scope_builder(
+            egui::UiBuilder::new()
+                .max_rect(rect)
+                .layout(egui::Layout::left_to_right(egui::Align::Center)),
+            |ui| {
+                ui.spacing_mut().item_spacing.x = 4.0;
+                for (index, tab) in self.tabs.iter().enumerate() {
+                    if let Some((id, name)) = &mut self.renaming {
+                        if *id == tab.id {
+                            let response = ui.add(
+                                egui::TextEdit::singleline(name)
+                                    .id(rename_id)
+                                    .desired_width(120.0),
+                            );
+                            if !response.has_focus() && !response.lost_focus() {
+                                response.request_focus();
+                            }
+                            finish_rename |= response.lost_focus();
+                            continue;
+                        }
+                    }
+
+                    let panes: Vec<&Session> = tab
+                        .layout
+                        .panes()
+                        .iter()
+                        .map(|id| &self.sessions[id])
+                        .collect();
+                    let mut label = String::new();
+                    if panes.iter().any(|session| session.bell) {
+                        label.push_str("🔔 ");
+                    } else if panes.iter().any(|session| session.activity) {
+                        label.push_str("● ");
+                    }
+                    if panes.iter().any(|session| session.broadcast) {
+                        label.push_str("📡 ");
+                    }
+                    label.push_str(&self.tab_title(tab));
+                    if panes.len() > 1 {
+                        label.push_str(&format!(" [{}]", panes.len()));
+                    }
+                    let label = egui::RichText::new(label).color(egui::Color32::WHITE);
+                    let response = ui
+                        .add(egui::Button::selectable(index == self.active, label))
+                        .on_hover_text("더블클릭: 이름 바꾸기, 가운데 클릭: 닫기");
+                    if response.double_clicked() {
+                        start_rename = Some(index);
+                    } else if response.clicked() {
+                        switch_to = Some(index);
+                    } else if response.clicked_by(egui::PointerButton::Middle) {
+                        close = Some(tab.id);
+                    }
+                    response.context_menu(|ui| {
+                        if ui.button("이름 바꾸기").clicked() {
+                            start_rename = Some(index);
+                            ui.close();
+                        }
+                        if ui.button("왼쪽으로 이동").clicked() {
+                            switch_to = Some(index);
+                            move_by = Some(-1);
+                            ui.close();
+                        }
+                        if ui.button("오른쪽으로 이동").clicked() {
+                            switch_to = Some(index);
+                            move_by = Some(1);
+                            ui.close();
+                        }
+                        let broadcast_label = if panes.iter().all(|session| session.broadcast) {
+                            "입력 브로드캐스트 해제"
+                        } else {
+                            "모든 패널에 입력 브로드캐스트"
+                        };
+                        if ui.button(broadcast_label).clicked() {
+                            broadcast = Some(tab.layout.panes());
+                            ui.close();
+                        }
+                        if ui.button("탭 닫기").clicked() {
+                            close = Some(tab.id);
+                            ui.close();
+                        }
+                    });
+                }
+                if ui
+                    .add(egui::Button::new(
+                        egui::RichText::new("+").color(egui::Color32::WHITE),
+                    ))
+                    .on_hover_text("새 탭 (Ctrl+Shift+T)")
+                    .clicked()
+                {
+                    open_tab = true;
+                }
+            },
+        );
+        self.tab_strip_rect = Some(strip.response.rect);
+
+        if finish_rename {
+            // Enter or clicking elsewhere keeps the name, Escape drops it; an empty
+            // name goes back to the automatic title
+            self.focus_set = false;
+            if let Some((id, name)) = self.renaming.take() {
+                if !ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                    if let Some(tab) = self.tabs.iter_mut().find(|tab| tab.id == id) {
+                        let name = name.trim();
+                        tab.name = (!name.is_empty()).then(|| name.to_string());
+                    }
+                }
+            }
+        }
+        if let Some(index) = start_rename {
+            let tab = &self.tabs[index];
+            self.renaming = Some((tab.id, self.tab_title(tab)));
+        }
+        if let Some(index) = switch_to {
+            self.activate(index);
+        }
+        if let Some(delta) = move_by {
+            self.move_active_tab(delta);
+        }
+        if let Some(id) = close {
+            self.request_close_tab(ctx, id);
+        }
+        if open_tab {
+            self.new_tab(ctx);
+        }
+        if let Some(panes) = broadcast {
+            self.toggle_broadcast(&panes);
+        }
+    }
+
+    // Dividers between the panes of the active tab; dragging one moves it
+    fn show_dividers(&mut self, ui: &mut egui::Ui, dividers: &[Divider]) {
+        let tab_id = self.tab().id;
+        for divider in dividers {
+            let response = ui.interact(
+                divider.rect,
+                egui::Id::new(("terminal_pane_divider", tab_id, &divider.path)),
+                egui::Sense::drag(),
+            );
+            let area = divider.area;
+            let (cursor, from, to) = match divider.axis {
+                SplitAxis::LeftRight => (
+                    egui::CursorIcon::ResizeHorizontal,
+                    divider.rect.center_top(),
+                    divider.rect.center_bottom(),
+                ),
+                SplitAxis::TopBottom => (
+                    egui::CursorIcon::ResizeVertical,
+                    divider.rect.left_center(),
+                    divider.rect.right_center(),
+                ),
+            };
+            let active = response.hovered() || response.dragged();
+            if active {
+                ui.ctx().set_cursor_icon(cursor);
+            }
+            if let (true, Some(pos)) = (response.dragged(), response.interact_pointer_pos()) {
+                let ratio = match divider.axis {
+                    SplitAxis::LeftRight => {
+                        (pos.x - DIVIDER_WIDTH / 2.0 - area.left()) / (area.width() - DIVIDER_WIDTH)
+                    }
+                    SplitAxis::TopBottom => {
+                        (pos.y - DIVIDER_WIDTH / 2.0 - area.top()) / (area.height() - DIVIDER_WIDTH)
+                    }
+                };
+                self.tabs[self.active]
+                    .layout
+                    .set_ratio(&divider.path, ratio);
+            }
+            let color = if active {
+                DIVIDER_ACTIVE_COLOR
+            } else {
+                DIVIDER_COLOR
+            };
+            ui.painter()
+                .line_segment([from, to], egui::Stroke::new(1.0, color));
+        }
+    }
+
+    // React to child processes exiting: close the pane or show the exit
+    // banner, depending on shell.close_on_exit (--hold never closes)
+    fn check_child_exits(&mut self, ctx: &egui::Context) {
+        let close_on_exit = if self.hold {
+            CloseOnExit::Never
+        } else {
+            self.config.shell.close_on_exit
+        };
+        let focus = self.tab().focus;
+        let mut to_close = Vec::new();
+        let ids: Vec<u64> = self.sessions.keys().copied().collect();
+        for id in ids {
+            let Some(exit) = self.sessions.get_mut(&id).and_then(Session::take_exit) else {
+                continue;
+            };
+            println!("🐚 {}", exit.describe());
+            // A command that never started keeps its pane to say why
+            if exit.start_error.is_none() && close_on_exit.should_close(exit.success()) {
+                to_close.push(id);
+                continue;
+            }
+            if id == focus {
+                self.leave_focus();
+            }
+            self.sessions[&id].show_exit_banner(&exit, ctx);
+        }
+        for id in to_close {
+            self.close_pane(ctx, id);
+        }
+    }
+
+    // Enter / Esc on the exit banner; every other key is dropped
+    fn handle_exit_keys(&mut self, ctx: &egui::Context) {
+        for key in Self::take_modal_keys(ctx) {
+            match key {
+                ViKey::Enter => {
+                    if let Err(e) = self.session_mut().restart(ctx) {
+                        // The banner stays up, so Esc still closes
+                        eprintln!("❌ Failed to restart: {:#}", e);
+                        self.show_toast(format!("재시작 실패: {:#}", e), TOAST_ERROR_COLOR);
+                    }
+                    return;
+                }
+                ViKey::Escape => {
+                    let id = self.tab().focus;
+                    self.close_pane(ctx, id);
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Close without asking (the children are gone, or the user already confirmed)
+    fn close_window(&mut self, ctx: &egui::Context) {
+        self.close_confirmed = true;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+    }
+
+    // Hold a close request (title bar button or window manager) while
+    // something other than a shell runs in the foreground of any pane, and ask first
+    fn intercept_close_request(&mut self, ctx: &egui::Context) {
+        if !ctx.input(|i| i.viewport().close_requested()) || self.close_confirmed {
+            return;
+        }
+        let mut programs: Vec<String> = Vec::new();
+        for session in self.sessions.values() {
+            for program in session.foreground_programs() {
+                if !programs.contains(&program) {
+                    programs.push(program);
+                }
+            }
+        }
+        if !programs.is_empty() {
+            println!("⚠️ Close requested while running: {}", programs.join(", "));
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.close_confirm = Some((CloseTarget::Window, programs));
+        }
+    }
+
+    // "Close anyway?" dialog listing the foreground programs
+    fn show_close_confirm(&mut self, ctx: &egui::Context) {
+        let Some((target, programs)) = &self.close_confirm else {
+            return;
+        };
+        let mut confirm = false;
+        let mut cancel = false;
+        // Enter confirms, Esc cancels; no key reaches the PTY meanwhile
+        for key in Self::take_modal_keys(ctx) {
+            match key {
+                ViKey::Enter => confirm = true,
+                ViKey::Escape => cancel = true,
+                _ => {}
+            }
+        }
+
+        let heading = match target {
+            CloseTarget::Window => "창을 닫을까요?",
+            CloseTarget::Tab(_) => "탭을 닫을까요?",
+            CloseTarget::Pane(_) => "패널을 닫을까요?",
+        };
+        egui::Modal::new(egui::Id::new("terminal_close_confirm")).show(ctx, |ui| {
+            ui.set_width(320.0);
+            ui.heading(heading);
+            ui.label(format!("실행 중인 프로그램: {}", programs.join(", ")));
+            ui.label("닫으면 이 프로그램들이 종료됩니다.");
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                confirm |= ui.button("닫기 (Enter)").clicked();
+                cancel |= ui.button("취소 (Esc)").clicked();
+            });
+        });
+
+        if confirm {
+            match self.close_confirm.take() {
+                Some((CloseTarget::Tab(id), _)) => self.close_tab(ctx, id),
+                Some((CloseTarget::Pane(id), _)) => self.close_pane(ctx, id),
+                _ => self.close_window(ctx),
+            }
+        } else if cancel {
+            self.close_confirm = None;
+        }
+    }
+
+    // Show a notification, replacing the current one
+    fn show_toast(&mut self, message: String, color: egui::Color32) {
+        self.toast = Some(Toast {
+            message,
+            color,
+            shown_at: Instant::now(),
+        });
+    }
+
+    fn draw_toast(&mut self, ctx: &egui::Context) {
+        let Some(toast) = &self.toast else {
+            return;
+        };
+        let remaining = TOAST_DURATION.saturating_sub(toast.shown_at.elapsed());
+        if remaining.is_zero() {
+            self.toast = None;
+            return;
+        }
+        egui::Area::new(egui::Id::new("terminal_toast"))
+            .order(egui::Order::Foreground)
+            .pivot(egui::Align2::CENTER_BOTTOM)
+            .fixed_pos(ctx.screen_rect().center_bottom() + egui::vec2(0.0, -24.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.colored_label(toast.color, &toast.message);
+                });
+            });
+        // Repaint once more to take it down
+        ctx.request_repaint_after(remaining);
+    }
+
+    // Apply an edited config file to the running session. Fonts, colors,
+    // opacity, key bindings, scrollback, word separators, links and hints
+    // change right away; shell, env and window size/decorations need a restart.
+    fn check_config_reload(&mut self, ctx: &egui::Context) {
+        let Some(watcher) = self.config_watcher.as_mut() else {
+            return;
+        };
+        let path = watcher.path().display().to_string();
+        let mut new_config = match watcher.poll() {
+            None => return,
+            Some(Ok(config)) => config,
+            Some(Err(e)) => {
+                eprintln!("❌ {:#}", e);
+                self.show_toast(
+                    format!("설정 파일 오류, 이전 설정 유지: {:#}", e),
+                    TOAST_ERROR_COLOR,
+                );
+                return;
+            }
+        };
+        println!("⚙️ Reloaded config from {}", path);
+
+        if new_config.font != self.config.font {
+            ctx.set_fonts(new_config.font.font_definitions());
+            self.fonts_reloaded = true;
+            ctx.request_repaint();
+        }
+        if new_config.colors != self.config.colors {
+            self.palette = new_config.colors.palette();
+        }
+        if new_config.keybindings != self.config.keybindings {
+            self.bindings = Bindings::new(&new_config.keybindings);
+        }
+        if new_config.links != self.config.links {
+            self.links = LinkDetector::new(&new_config.links);
+        }
+        if new_config.hints != self.config.hints {
+            self.hints = HintState::new(&new_config.hints);
+        }
+        if new_config.scrollback != self.config.scrollback
+            || new_config.selection != self.config.selection
+        {
+            for session in self.sessions.values() {
+                if let Ok(mut state) = session.terminal_state.lock() {
+                    new_config.apply_to_state(&mut state);
+                }
+            }
+        }
+
+        let restart_only = self.config.restart_only_changes(&new_config);
+        self.config.keep_restart_only(&mut new_config);
+        self.config = new_config;
+        if restart_only.is_empty() {
+            self.show_toast("설정을 다시 불러왔습니다".to_string(), TOAST_INFO_COLOR);
+        } else {
+            println!("⚠️ Restart wterm to apply: {}", restart_only.join(", "));
+            self.show_toast(
+                format!(
+                    "설정을 다시 불러왔습니다. 재시작 후 적용: {}",
+                    restart_only.join(", ")
+                ),
+                TOAST_WARNING_COLOR,
+            );
+        }
+    }
+
+    pub fn new(
+        cc: &eframe::CreationContext<'_>,
+        config: Config,
+        config_error: Option<anyhow::Error>,
+        cli: Cli,
+        config_path: Option<PathBuf>,
+    ) -> anyhow::Result<Self> {
+        // Configure the terminal font with egui's fonts as fallback
+        cc.egui_ctx.set_fonts(config.font.font_definitions());
 
         // Calculate a reasonable *initial* terminal size based on estimates.
         // This will be corrected on the first frame in `update()`.
@@ -198,105 +1322,670 @@ impl TerminalApp {
             let line_height = 16.0f32; // Estimate
             let char_width = 7.5f32; // Estimate, adjusted for better fit
 
-            // Use default window size from main() for initial calculation
-            let available_height = 768.0f32;
-            let available_width = 1024.0f32;
+            // Use the configured window size for initial calculation
+            let available_height = config.window.height;
+            let available_width = config.window.width;
 
             // Leave some margin for UI elements and window chrome
             let usable_height = available_height - 100.0;
             let usable_width = available_width - 50.0;
 
-            let rows = (usable_height / line_height).floor() as usize;
-            let cols = (usable_width / char_width).floor() as usize;
+            let rows = (usable_height / line_height).floor() as usize;
+            let cols = (usable_width / char_width).floor() as usize;
+
+            let rows = rows.clamp(20, 100);
+            let cols = cols.clamp(60, 200);
+
+            let pixel_width = (cols as f32 * char_width) as u16;
+            let pixel_height = (rows as f32 * line_height) as u16;
+            (rows, cols, pixel_width, pixel_height)
+        };
+
+        println!(
+            "🖥️ Initial estimated terminal size: {}x{} ({}x{}px)",
+            actual_cols, actual_rows, initial_pixel_width, initial_pixel_height
+        );
+
+        // Spawn `-e` or the configured program - by default the user's login shell
+        let cwd = cli
+            .working_directory
+            .as_deref()
+            .map(expand_home)
+            .or_else(|| config.shell.working_directory());
+        let size = PtySize {
+            rows: actual_rows as u16,
+            cols: actual_cols as u16,
+            pixel_width: initial_pixel_width,
+            pixel_height: initial_pixel_height,
+        };
+        let mut fallback = None;
+        let session = match &cli.command {
+            Some(command) => {
+                let (program, args) = (&command[0], &command[1..]);
+                println!("🐚 Starting {} {:?}", program, args);
+                let (shell, _) = config.shell.command();
+                let cmd = build_command(program, args, cwd, &config, &shell);
+                let title = cli.title.clone().unwrap_or_else(|| program_name(program));
+                match Session::spawn(0, cmd.clone(), size, &config, title.clone(), &cc.egui_ctx) {
+                    Ok(session) => session,
+                    Err(e) => {
+                        // Shown in the exit banner, where Enter tries again
+                        eprintln!("❌ Can't start {}: {:#}", program, e);
+                        let exit = ChildExit::start_failed(program, &e);
+                        Session::failed(0, cmd, size, &config, title, exit)?
+                    }
+                }
+            }
+            None => {
+                let (session, program, fallback_reason) =
+                    spawn_shell(0, &config, cwd, size, cli.title.as_deref(), &cc.egui_ctx)?;
+                println!("🐚 Started {}", program);
+                fallback = fallback_reason.map(|e| (program, e));
+                session
+            }
+        };
+
+        // Request initial repaint to ensure first render
+        cc.egui_ctx.request_repaint();
+
+        let mut app = Self {
+            tabs: vec![Tab::new(1, session.id)],
+            sessions: BTreeMap::from([(session.id, session)]),
+            active: 0,
+            next_id: 2,
+            pane_area: egui::Rect::NOTHING,
+            renaming: None,
+            hold: cli.hold,
+            close_confirm: None,
+            close_confirmed: false,
+            palette: config.colors.palette(),
+            fonts_version: 0,
+            fonts_reloaded: false,
+            bindings: Bindings::new(&config.keybindings),
+            config_watcher: config_path.map(|path| ConfigWatcher::new(path, cc.egui_ctx.clone())),
+            toast: None,
+            links: LinkDetector::new(&config.links),
+            hints: HintState::new(&config.hints),
+            config,
+            window_title: cli
+                .title
+                .clone()
+                .unwrap_or_else(|| DEFAULT_WINDOW_TITLE.to_string()),
+            title: cli.title,
+            tab_strip_rect: None,
+            korean_state: KoreanInputState::new(),
+            primary_selection: platform_primary_selection(),
+            search: SearchState::new(),
+            vi_mode: ViMode::new(),
+            last_tab_time: None,
+            focus_set: false,
+        };
+
+        // Shown in the window: a launcher doesn't show stderr
+        if let Some((program, e)) = &fallback {
+            app.show_shell_fallback(program, e);
+        }
+        if let Some(e) = config_error {
+            app.show_toast(
+                format!("설정 파일 오류, 기본 설정 사용: {:#}", e),
+                TOAST_ERROR_COLOR,
+            );
+        }
+        Ok(app)
+    }
+
+    // Draw pane `id` of the active tab inside `rect`, resizing its grid and PTY
+    // to fit. Clicking into a pane focuses it. Returns the id of the widget
+    // that takes keyboard focus for the pane.
+    fn show_pane(&mut self, ui: &mut egui::Ui, id: u64, rect: egui::Rect) -> egui::Id {
+        let terminal_state = self.sessions[&id].terminal_state.clone();
+        // Search, copy mode, hints and the composition preview belong to the
+        // focused pane only
+        let focused = id == self.tab().focus;
+        let composing = focused && self.korean_state.is_composing;
+
+        // Calculate terminal size based on the pane, including pixel dimensions
+        let (terminal_rows, terminal_cols, pixel_width, pixel_height) =
+            self.calculate_terminal_size(rect, ui);
+
+        // Resize terminal if needed
+        if let Err(e) =
+            self.resize_session(id, terminal_rows, terminal_cols, pixel_width, pixel_height)
+        {
+            eprintln!("⚠️ {:#}", e);
+        }
+
+        let ui = &mut ui.new_child(
+            egui::UiBuilder::new()
+                .id_salt(("terminal_pane", id))
+                .max_rect(rect),
+        );
+
+        // Bring the render buffer up to date and work out where the viewport
+        // should be. TerminalState::display_offset is the source of truth; the
+        // ScrollArea is positioned from it every frame.
+        let font_id = self.config.font.font_id();
+        let line_height = ui.fonts(|f| f.row_height(&font_id));
+        let forced_offset = if let Ok(mut state) = terminal_state.lock() {
+            // If the terminal state has changed, update the reflowed render buffer.
+            if state.update_render_buffer_if_dirty() {
+                // New output: keep search hits in sync with the buffer
+                if focused && self.search.active {
+                    self.search.refresh(&state);
+                }
+                if focused && self.vi_mode.active {
+                    self.vi_mode.buffer_changed(&state);
+                }
+            }
+            // Output inside a synchronized update waits for its end; the reader
+            // thread wakes us then, this covers a program that never ends it
+            if let Some(remaining) = state.synchronized_update_remaining() {
+                ui.ctx().request_repaint_after(remaining);
+            }
+            // Bring the current search hit into the middle of the view
+            if focused {
+                if let Some(row) = self.search.scroll_to_row.take() {
+                    state.scroll_display_to_row(row);
+                }
+            }
+            let content_height = state.render_buffer.len() as f32 * line_height;
+            let max_offset = (content_height - rect.height()).max(0.0);
+            Some((max_offset - state.display_offset as f32 * line_height).max(0.0))
+        } else {
+            None
+        };
+
+        // Terminal display with focus handling and proper scrolling
+        let mut scroll_area = egui::ScrollArea::vertical()
+            .id_salt(("terminal_scroll", id)) // Use id_salt for persistent state (corrected from id_source)
+            .auto_shrink([false; 2]);
+        if let Some(offset) = forced_offset {
+            scroll_area = scroll_area.vertical_scroll_offset(offset);
+        }
+
+        // Set while painting; acted on once the terminal state lock is released
+        let mut selection_finished = false;
+        let mut middle_click_paste = false;
+        let mut pointer_used = false;
+        let mut link_to_open: Option<(Link, Option<std::path::PathBuf>)> = None;
+
+        let terminal_response = scroll_area.show(ui, |ui| {
+            // Calculate exact font metrics
+            let font_id = self.config.font.font_id();
+            let line_height = ui.fonts(|f| f.row_height(&font_id));
+            let char_width = ui.fonts(|f| f.glyph_width(&font_id, 'M'));
+
+            if let Ok(mut state) = terminal_state.lock() {
+                let content_width = state.cols as f32 * char_width;
+                // The total height is now based on the reflowed render_buffer.
+                let total_lines = state.render_buffer.len();
+                let content_height = total_lines as f32 * line_height;
+
+                let (response, painter) = ui.allocate_painter(
+                    egui::Vec2::new(content_width, content_height),
+                    egui::Sense::click_and_drag().union(egui::Sense::focusable_noninteractive()),
+                );
+
+                // Background is already drawn above, no need to draw again here
+
+                // Mouse selection: drag for characters, Alt-drag for a block,
+                // double-click for words, triple-click for lines
+                let press_origin = ui.input(|i| i.pointer.press_origin());
+                let pointer_pos = response.interact_pointer_pos();
+                if response.drag_started_by(egui::PointerButton::Primary) {
+                    if let Some(pos) = press_origin {
+                        let point =
+                            pointer_to_cell(pos, response.rect, char_width, line_height, &state);
+                        // Alt-drag selects a rectangular block of cells
+                        let ty = if ui.input(|i| i.modifiers.alt) {
+                            SelectionType::Block
+                        } else {
+                            SelectionType::Simple
+                        };
+                        state.selection = Some(Selection::new(ty, point));
+                    }
+                } else if response.dragged_by(egui::PointerButton::Primary) {
+                    if let Some(pos) = pointer_pos {
+                        let point =
+                            pointer_to_cell(pos, response.rect, char_width, line_height, &state);
+                        if let Some(selection) = &mut state.selection {
+                            selection.update(point);
+                        }
+                    }
+                }
+
+                if response.drag_stopped_by(egui::PointerButton::Primary) {
+                    selection_finished = true;
+                }
+                if response.clicked_by(egui::PointerButton::Middle) {
+                    middle_click_paste = true;
+                }
+                pointer_used = response.clicked()
+                    || response.drag_started()
+                    || response.clicked_by(egui::PointerButton::Middle);
+
+                // Ctrl+click opens the link under the pointer instead of selecting
+                let ctrl_held = ui.input(|i| i.modifiers.ctrl);
+                let link_clicked = response.clicked() && ctrl_held;
+
+                if response.clicked() && !link_clicked {
+                    ui.memory_mut(|mem| mem.request_focus(response.id));
+                    let click_type = if response.triple_clicked() {
+                        Some(SelectionType::Line)
+                    } else if response.double_clicked() {
+                        Some(SelectionType::Word)
+                    } else {
+                        None
+                    };
+                    state.selection = match (click_type, pointer_pos) {
+                        (Some(ty), Some(pos)) => {
+                            let point = pointer_to_cell(
+                                pos,
+                                response.rect,
+                                char_width,
+                                line_height,
+                                &state,
+                            );
+                            Some(Selection::new(ty, point))
+                        }
+                        _ => None,
+                    };
+                    selection_finished = state.selection.is_some();
+                }
+
+                // Resolve the selection once per frame for the highlight overlay
+                let selection_range = state
+                    .selection
+                    .as_ref()
+                    .and_then(|selection| selection.to_range(&state));
+
+                // --- Row Virtualization ---
+                let first_visible_row = ((ui.clip_rect().top() - response.rect.top()) / line_height)
+                    .floor()
+                    .max(0.0) as usize;
+                let last_visible_row =
+                    ((ui.clip_rect().bottom() - response.rect.top()) / line_height).ceil() as usize;
+                let last_visible_row = last_visible_row.min(total_lines);
+
+                // Update viewport information for optimized render_buffer updates
+                state.update_viewport(first_visible_row, last_visible_row);
+
+                // Link under the pointer while Ctrl is held
+                let hovered_link = match response.hover_pos() {
+                    Some(pos) if ctrl_held => {
+                        let point =
+                            pointer_to_cell(pos, response.rect, char_width, line_height, &state);
+                        self.links
+                            .find_links(&state, first_visible_row, last_visible_row)
+                            .into_iter()
+                            .find(|link| link.contains(point))
+                    }
+                    _ => None,
+                };
+                if let Some(link) = &hovered_link {
+                    ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+                    if link_clicked {
+                        link_to_open = Some((link.clone(), state.working_directory.clone()));
+                    }
+                }
+
+                // Search hits intersecting the visible rows
+                let current_match = self.search.current_match().copied();
+                let visible_matches: Vec<_> = self
+                    .search
+                    .matches
+                    .iter()
+                    .filter(|m| {
+                        focused && m.end.row >= first_visible_row && m.start.row < last_visible_row
+                    })
+                    .map(|m| {
+                        let color = if Some(*m) == current_match {
+                            SEARCH_CURRENT_COLOR
+                        } else {
+                            SEARCH_MATCH_COLOR
+                        };
+                        (*m, color)
+                    })
+                    .collect();
+
+                let row_cache = &mut self
+                    .sessions
+                    .get_mut(&id)
+                    .expect("every pane has a session")
+                    .row_cache;
+                row_cache.begin_frame(
+                    &font_id,
+                    self.fonts_version,
+                    &self.palette,
+                    ui.ctx().pixels_per_point(),
+                );
+
+                // Draw only the visible rows from the render_buffer.
+                for row_idx in first_visible_row..last_visible_row {
+                    // Safety check: ensure row_idx is within render_buffer bounds
+                    if row_idx >= state.render_buffer.len() {
+                        break;
+                    }
+                    let row_data = &state.render_buffer[row_idx];
+                    let y = response.rect.top() + row_idx as f32 * line_height;
+                    // Cells are laid out only for rows that changed since the
+                    // last frame; the rest come from the row cache
+                    let generation = state.render_row_generations.get(row_idx).copied();
+                    let paint = || {
+                        ui.fonts(|fonts| {
+                            paint_row(
+                                fonts,
+                                row_data,
+                                &self.palette,
+                                &font_id,
+                                char_width,
+                                line_height,
+                            )
+                        })
+                    };
+                    let origin = egui::vec2(response.rect.left(), y);
+                    let translate = |mut shape: egui::Shape| {
+                        shape.translate(origin);
+                        shape
+                    };
+                    match generation {
+                        Some(generation) => painter.extend(
+                            row_cache
+                                .row(generation, paint)
+                                .iter()
+                                .cloned()
+                                .map(translate),
+                        ),
+                        None => painter.extend(paint().into_iter().map(translate)),
+                    }
+
+                    // Search hit highlights
+                    for (range, color) in &visible_matches {
+                        if let Some(cols) = range.columns_in_row(row_idx, row_data.len()) {
+                            let origin = egui::Pos2::new(response.rect.left(), y);
+                            paint_cell_span(
+                                &painter,
+                                origin,
+                                cols,
+                                char_width,
+                                line_height,
+                                *color,
+                            );
+                        }
+                    }
+
+                    // Underline the hovered link
+                    if let Some((first, last)) = hovered_link
+                        .as_ref()
+                        .and_then(|link| link.range.columns_in_row(row_idx, row_data.len()))
+                    {
+                        let underline_y = y + line_height - 1.0;
+                        painter.line_segment(
+                            [
+                                egui::Pos2::new(
+                                    response.rect.left() + first as f32 * char_width,
+                                    underline_y,
+                                ),
+                                egui::Pos2::new(
+                                    response.rect.left() + (last + 1) as f32 * char_width,
+                                    underline_y,
+                                ),
+                            ],
+                            egui::Stroke::new(1.0, LINK_COLOR),
+                        );
+                    }
+
+                    // Selection highlight overlay
+                    if let Some(cols) = selection_range
+                        .and_then(|range| range.columns_in_row(row_idx, row_data.len()))
+                    {
+                        let origin = egui::Pos2::new(response.rect.left(), y);
+                        paint_cell_span(
+                            &painter,
+                            origin,
+                            cols,
+                            char_width,
+                            line_height,
+                            SELECTION_COLOR,
+                        );
+                    }
+                }
+
+                // Hint labels on top of everything else, at the start of each match
+                if focused && self.hints.active {
+                    let label_font = self.config.font.font_id();
+                    for hint in self.hints.visible_hints() {
+                        let range = hint.range;
+                        if range.start.row < first_visible_row
+                            || range.start.row >= last_visible_row
+                        {
+                            continue;
+                        }
+                        for row in range.start.row..=range.end.row {
+                            let row_len = state.render_buffer.get(row).map_or(0, |r| r.len());
+                            if let Some(cols) = range.columns_in_row(row, row_len) {
+                                let origin = egui::Pos2::new(
+                                    response.rect.left(),
+                                    response.rect.top() + row as f32 * line_height,
+                                );
+                                paint_cell_span(
+                                    &painter,
+                                    origin,
+                                    cols,
+                                    char_width,
+                                    line_height,
+                                    HINT_MATCH_COLOR,
+                                );
+                            }
+                        }
+
+                        let label_pos = egui::Pos2::new(
+                            response.rect.left() + range.start.col as f32 * char_width,
+                            response.rect.top() + range.start.row as f32 * line_height,
+                        );
+                        let remaining = &hint.label[self.hints.typed.len()..];
+                        painter.rect_filled(
+                            egui::Rect::from_min_size(
+                                label_pos,
+                                egui::Vec2::new(
+                                    remaining.chars().count() as f32 * char_width,
+                                    line_height,
+                                ),
+                            ),
+                            egui::CornerRadius::same(2),
+                            HINT_LABEL_COLOR,
+                        );
+                        painter.text(
+                            label_pos,
+                            egui::Align2::LEFT_TOP,
+                            remaining,
+                            label_font.clone(),
+                            egui::Color32::BLACK,
+                        );
+                    }
+                }
+
+                // Copy mode cursor: outlined cell, two cells wide over wide characters
+                if focused && self.vi_mode.active {
+                    let cursor = self.vi_mode.cursor;
+                    let wide = state
+                        .render_buffer
+                        .get(cursor.row)
+                        .and_then(|row| row.get(cursor.col + 1))
+                        .is_some_and(|cell| cell.ch == '\u{0000}');
+                    let cells = if wide { 2.0 } else { 1.0 };
+                    painter.rect_stroke(
+                        egui::Rect::from_min_size(
+                            egui::Pos2::new(
+                                response.rect.left() + cursor.col as f32 * char_width,
+                                response.rect.top() + cursor.row as f32 * line_height,
+                            ),
+                            egui::Vec2::new(cells * char_width, line_height),
+                        ),
+                        egui::CornerRadius::ZERO,
+                        egui::Stroke::new(1.5, VI_CURSOR_COLOR),
+                        egui::StrokeKind::Inside,
+                    );
+                }
+
+                // Draw cursor based on the calculated visual position from TerminalState.
+                let cursor_y = response.rect.top() + state.render_cursor_row as f32 * line_height;
+                if cursor_y >= ui.clip_rect().top()
+                    && cursor_y + line_height <= ui.clip_rect().bottom()
+                {
+                    let cursor_x =
+                        response.rect.left() + state.render_cursor_col as f32 * char_width;
+
+                    if composing {
+                        // println!("📍 Cursor position: row={}, col={}, x={}, y={}",
+                        //     state.render_cursor_row, state.render_cursor_col, cursor_x, cursor_y);
+                    }
+                    if state.cursor_visible && !composing {
+                        let cursor_line_y = cursor_y + line_height - 2.0;
+                        painter.rect_filled(
+                            egui::Rect::from_min_size(
+                                egui::Pos2::new(cursor_x, cursor_line_y),
+                                egui::Vec2::new(char_width, 2.0),
+                            ),
+                            egui::CornerRadius::ZERO,
+                            egui::Color32::WHITE,
+                        );
+                    }
+                    // Calculate cursor width for Korean composition if needed
+                    let cursor_width = if composing {
+                        // Korean composing characters are always wide (2 chars)
+                        2.0 * char_width
+                    } else {
+                        // Normal cursor width
+                        char_width
+                    };
 
-            let rows = rows.max(20).min(100);
-            let cols = cols.max(60).min(200);
+                    // Draw composing character preview if Korean composition is active
+                    if composing {
+                        if let Some(composing_char) = self.korean_state.get_current_char() {
+                            // Calculate precise cursor X position by walking through the row (like e32f82d)
+                            // This ensures accurate positioning regardless of render_buffer update timing
+                            let mut preview_x = response.rect.left();
 
-            let pixel_width = (cols as f32 * char_width) as u16;
-            let pixel_height = (rows as f32 * line_height) as u16;
-            (rows, cols, pixel_width, pixel_height)
-        };
+                            let cursor_row_data = if state.cursor_row < state.main_buffer.len() {
+                                Some(&state.main_buffer[state.cursor_row])
+                            } else {
+                                None
+                            };
 
-        println!(
-            "🖥️ Initial estimated terminal size: {}x{} ({}x{}px)",
-            actual_cols, actual_rows, initial_pixel_width, initial_pixel_height
-        );
+                            // Walk through the row to calculate precise cursor position
+                            if let Some(row) = cursor_row_data {
+                                for cell in row.iter().take(state.cursor_col) {
+                                    if cell.ch == '\u{0000}' {
+                                        continue;
+                                    }
 
-        // Use calculated size
-        let terminal_state = Arc::new(Mutex::new(TerminalState::new(actual_rows, actual_cols)));
+                                    // Calculate display width like e32f82d
+                                    let char_display_width = if cell.ch.width().unwrap_or(1) == 2 {
+                                        2 // Korean and other wide characters are 2 units
+                                    } else {
+                                        1 // All other characters are 1 unit
+                                    };
+                                    preview_x += char_display_width as f32 * char_width;
+                                }
+                            }
 
-        // Create PTY with calculated size, including pixel dimensions for accuracy
-        let pty_system = portable_pty::native_pty_system();
-        let pty_pair = pty_system.openpty(PtySize {
-            rows: actual_rows as u16,
-            cols: actual_cols as u16,
-            pixel_width: initial_pixel_width,
-            pixel_height: initial_pixel_height,
-        })?;
-
-        // Spawn shell - use zsh with user configs (.zshrc, oh-my-zsh etc)
-        let mut cmd = CommandBuilder::new("/bin/zsh");
-        cmd.args(&["-il"]); // Login shell with user's .zshrc
-        cmd.env("TERM", "xterm-256color");
-        cmd.env("LANG", "ko_KR.UTF-8");
-        cmd.env("LC_ALL", "ko_KR.UTF-8");
-        cmd.env("LC_CTYPE", "UTF-8");
-        cmd.env("SHELL", "/bin/zsh");
-        //P1: '\\x1b]0;', P2: '\\x07'
-        cmd.env("PROMPT_EOL_MARK", "%{%G%}");
-        // Ensure consistent terminal behavior and fix visual glitches
-        cmd.env("TERM_PROGRAM", "wterm");
-        cmd.env("TERM_PROGRAM_VERSION", "1.0");
-        // Disable the reverse-video '%' character at the end of partial lines
-
-        // Prevent oh-my-zsh from trying to set the window title
-        cmd.env("DISABLE_AUTO_TITLE", "true");
-
-        let _child = pty_pair.slave.spawn_command(cmd)?;
-
-        let mut pty_reader = pty_pair.master.try_clone_reader()?;
-        let pty_writer = Arc::new(Mutex::new(pty_pair.master.take_writer()?));
-        let pty_master = Arc::new(Mutex::new(pty_pair.master));
-
-        // Spawn background thread to read from PTY
-        let state_clone = terminal_state.clone();
-        let egui_ctx_clone = cc.egui_ctx.clone();
-        thread::spawn(move || {
-            let mut parser = Parser::new();
-            let mut performer = TerminalPerformer::new(state_clone, egui_ctx_clone);
-
-            let mut buffer = [0u8; 1024];
-            loop {
-                match pty_reader.read(&mut buffer) {
-                    Ok(0) => break, // EOF
-                    Ok(n) => {
-                        let read_data = &buffer[..n];
-
-                        /*    println!(
-                            "🚽 PTY Read ({} bytes): string: \"{}\"",
-                            n,
-                            String::from_utf8_lossy(read_data).escape_debug()
-                        );*/
-
-                        // Process all bytes at once using VTE 0.15 API
-                        parser.advance(&mut performer, read_data);
+                            let preview_y = cursor_y;
+
+                            // println!("🎯 Composing preview at: cursor_col={}, calculated_x={}, y={} for char '{}' (using e32f82d method)",
+                            //     state.cursor_col, preview_x, preview_y, composing_char);
+
+                            // Draw composing character with a different color (gray/dimmed) to show it's temporary
+                            let preview_color = egui::Color32::from_rgb(150, 150, 150); // Gray preview color
+
+                            // Draw a subtle background to make the preview more visible
+                            let preview_bg =
+                                egui::Color32::from_rgba_unmultiplied(100, 100, 100, 50);
+                            painter.rect_filled(
+                                egui::Rect::from_min_size(
+                                    egui::Pos2::new(preview_x, preview_y),
+                                    egui::Vec2::new(cursor_width, line_height),
+                                ),
+                                egui::CornerRadius::ZERO,
+                                preview_bg,
+                            );
+
+                            // Draw the composing character
+                            painter.text(
+                                egui::Pos2::new(preview_x, preview_y),
+                                egui::Align2::LEFT_TOP,
+                                composing_char,
+                                font_id.clone(),
+                                preview_color,
+                            );
+
+                            // Hide the normal cursor when composing
+                            // (The composing character serves as a visual cursor)
+                        }
                     }
-                    Err(_) => break,
                 }
+
+                response
+            } else {
+                ui.allocate_response(egui::Vec2::new(800.0, 600.0), egui::Sense::click())
             }
         });
 
-        // Request initial repaint to ensure first render
-        cc.egui_ctx.request_repaint();
+        // Mouse wheel or scrollbar moved the view: carry that back into display_offset
+        if let Some(forced) = forced_offset {
+            let offset = terminal_response.state.offset.y;
+            if (offset - forced).abs() > 0.5 {
+                let max_offset = (terminal_response.content_size.y
+                    - terminal_response.inner_rect.height())
+                .max(0.0);
+                let lines = ((max_offset - offset) / line_height).round().max(0.0);
+                if let Ok(mut state) = terminal_state.lock() {
+                    state.display_offset = (lines as usize).min(state.max_display_offset());
+                }
+            }
+        }
 
-        Ok(Self {
-            terminal_state,
-            pty_writer,
-            pty_master,
-            korean_state: KoreanInputState::new(),
-            last_tab_time: None,
-            initial_focus_set: false,
-        })
+        // Broadcast group member: outline and a badge in the top-right corner
+        if self.sessions[&id].broadcast {
+            let painter = ui.painter();
+            painter.rect_stroke(
+                rect,
+                egui::CornerRadius::ZERO,
+                egui::Stroke::new(2.0, BROADCAST_COLOR),
+                egui::StrokeKind::Inside,
+            );
+            let badge = painter.layout_no_wrap(
+                "📡 브로드캐스트".to_string(),
+                egui::FontId::proportional(11.0),
+                egui::Color32::BLACK,
+            );
+            let badge_rect = egui::Rect::from_min_size(
+                egui::pos2(rect.right() - badge.size().x - 24.0, rect.top() + 4.0),
+                badge.size() + egui::vec2(8.0, 2.0),
+            );
+            painter.rect_filled(badge_rect, egui::CornerRadius::same(3), BROADCAST_COLOR);
+            painter.galley(
+                badge_rect.min + egui::vec2(4.0, 1.0),
+                badge,
+                egui::Color32::BLACK,
+            );
+        }
+
+        // Clicking into another pane moves the focus there first
+        if pointer_used && !focused {
+            self.focus_pane(id);
+        }
+        if selection_finished {
+            self.update_primary_selection();
+        }
+        if let Some((link, cwd)) = link_to_open {
+            self.links.open(&link.target, cwd.as_deref());
+        }
+        if middle_click_paste && !self.vi_mode.active {
+            self.paste_primary_selection();
+        }
+
+        terminal_response.inner.id
     }
 
     fn calculate_terminal_size(
@@ -304,7 +1993,7 @@ impl TerminalApp {
         available_rect: egui::Rect,
         ui: &egui::Ui,
     ) -> (usize, usize, u16, u16) {
-        let font_id = egui::FontId::new(11.0, egui::FontFamily::Monospace);
+        let font_id = self.config.font.font_id();
         let line_height = ui.fonts(|f| f.row_height(&font_id));
         let char_width = ui.fonts(|f| f.glyph_width(&font_id, 'M'));
 
@@ -315,9 +2004,9 @@ impl TerminalApp {
         let rows = (usable_height / line_height).floor() as usize;
         let cols = (usable_width / char_width).floor() as usize;
 
-        // Minimum size constraints
-        let rows = rows.max(10);
-        let cols = cols.max(40);
+        // Minimum size constraints (split panes can get small)
+        let rows = rows.max(2);
+        let cols = cols.max(10);
 
         let pixel_width = (cols as f32 * char_width) as u16;
         let pixel_height = (rows as f32 * line_height) as u16;
@@ -339,16 +2028,20 @@ impl TerminalApp {
         (rows, cols, pixel_width, pixel_height)
     }
 
-    fn resize_terminal(
-        &mut self,
+    // Resize session `id` (its grid and its PTY) to fit its pane
+    fn resize_session(
+        &self,
+        id: u64,
         new_rows: usize,
         new_cols: usize,
         pixel_width: u16,
         pixel_height: u16,
     ) -> Result<()> {
         // Get current terminal size first
+        let session = &self.sessions[&id];
+        let terminal_state = session.terminal_state.clone();
         let current_size = {
-            let state = self.terminal_state.lock().unwrap();
+            let state = terminal_state.lock().unwrap();
             (state.rows, state.cols)
         };
 
@@ -359,7 +2052,7 @@ impl TerminalApp {
         // Resize the terminal state
         {
             let mut state: std::sync::MutexGuard<'_, TerminalState> =
-                self.terminal_state.lock().unwrap();
+                terminal_state.lock().unwrap();
             let is_alt = state.is_alt_screen;
             state.resize(new_rows, new_cols);
 
@@ -371,7 +2064,7 @@ impl TerminalApp {
 
         // Resize the PTY and send SIGWINCH to notify shell of size change
         {
-            let pty_master = self.pty_master.lock().unwrap();
+            let pty_master = &session.pty_master;
             let new_size = PtySize {
                 rows: new_rows as u16,
                 cols: new_cols as u16,
@@ -389,17 +2082,113 @@ impl TerminalApp {
 }
 
 impl eframe::App for TerminalApp {
-    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if std::mem::take(&mut self.fonts_reloaded) {
+            self.fonts_version += 1; // Rows cached with the old fonts are stale now
+        }
+        self.check_config_reload(ctx);
+        self.check_child_exits(ctx);
+        self.update_tab_indicators(ctx);
+        self.update_window_title(ctx);
+        self.intercept_close_request(ctx);
+        if self.close_confirm.is_some() {
+            self.show_close_confirm(ctx);
+        }
+
+        // After the child exited only Enter (restart) and Esc (close) do anything
+        if self.session().exit_handled {
+            self.handle_exit_keys(ctx);
+        }
+
+        // Configured shortcuts (search, copy mode, hints, scrollback); their keys
+        // never reach the PTY
+        for action in self.bindings.take_actions(ctx) {
+            self.perform_action(ctx, action);
+        }
+
+        // In copy mode every key goes to the copy-mode cursor, none to the PTY
+        if self.vi_mode.active {
+            let keys = Self::take_modal_keys(ctx);
+            let mut yanked = None;
+            if let Ok(mut state) = self.terminal_state().lock() {
+                for key in keys {
+                    match self.vi_mode.handle_key(&mut state, key) {
+                        ViAction::None => {}
+                        ViAction::Yank(text) => {
+                            yanked = Some(text);
+                            break;
+                        }
+                        ViAction::Exit => {
+                            self.vi_mode.exit(&mut state);
+                            break;
+                        }
+                    }
+                }
+            }
+            if let Some(text) = yanked {
+                if let Some(primary) = self.primary_selection.as_mut() {
+                    primary.set_text(&text);
+                }
+                ctx.copy_text(text);
+                if let Ok(mut state) = self.terminal_state().lock() {
+                    self.vi_mode.exit(&mut state);
+                }
+            }
+        }
+
+        // While hints are shown, typed keys pick a label instead of reaching the PTY
+        if self.hints.active {
+            let mut chosen = None;
+            for key in Self::take_modal_keys(ctx) {
+                match key {
+                    ViKey::Char(ch) => chosen = self.hints.key(ch),
+                    ViKey::Backspace => self.hints.backspace(),
+                    ViKey::Escape => self.hints.cancel(),
+                    _ => {}
+                }
+                if chosen.is_some() || !self.hints.active {
+                    break;
+                }
+            }
+            if let Some(hint) = chosen {
+                match hint.action {
+                    HintAction::Copy => ctx.copy_text(hint.text),
+                    HintAction::Paste => self.paste_text(&hint.text),
+                    HintAction::Open => {
+                        let target = self.links.target_for_text(&hint.text);
+                        let cwd = self
+                            .terminal_state()
+                            .lock()
+                            .ok()
+                            .and_then(|state| state.working_directory.clone());
+                        self.links.open(&target, cwd.as_deref());
+                    }
+                }
+            }
+        }
+
+        // Keys typed into the search bar must not reach the PTY
+        let search_focused = (self.search.active
+            && ctx.memory(|mem| mem.has_focus(egui::Id::new("terminal_search_input"))))
+            || self.renaming.is_some();
+
         // No need to check IME timeout with rustkorean
 
         // We'll handle window rounding through the UI elements themselves
 
+        // With native decorations the window manager draws the title bar
+        let custom_title_bar = !self.config.window.decorations;
+
         // Check for drag anywhere in the window (simple approach)
-        if ctx.input(|i| i.pointer.any_pressed()) && ctx.input(|i| i.pointer.primary_down()) {
+        if custom_title_bar
+            && ctx.input(|i| i.pointer.any_pressed())
+            && ctx.input(|i| i.pointer.primary_down())
+        {
             // Get the current pointer position
             if let Some(pos) = ctx.input(|i| i.pointer.interact_pos()) {
                 // If pointer is in the top area of the window (title bar area), start drag
-                if pos.y < 50.0 {
+                let on_tabs = self.tab_strip_rect.is_some_and(|rect| rect.contains(pos));
+                if pos.y < 50.0 && !on_tabs {
                     // Top 50 pixels can be used for dragging
                     ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
                 }
@@ -410,13 +2199,13 @@ impl eframe::App for TerminalApp {
         let corner_radius_u8 = 10u8; // macOS-style corner radius
 
         egui::CentralPanel::default()
-            .frame(egui::Frame::none().fill(egui::Color32::TRANSPARENT)) // Keep window background transparent
+            .frame(egui::Frame::NONE.fill(egui::Color32::TRANSPARENT)) // Keep window background transparent
             .show(ctx, |ui| {
                 // Draw the entire window background in one piece
                 let full_rect = ui.available_rect_before_wrap();
 
                 // Calculate title bar height
-                let title_bar_height = 28.0;
+                let title_bar_height = if custom_title_bar { 28.0 } else { 0.0 };
 
                 // Draw title bar background (top rounded corners)
                 let title_rect = egui::Rect::from_min_size(
@@ -447,358 +2236,198 @@ impl eframe::App for TerminalApp {
                         sw: corner_radius_u8,
                         se: corner_radius_u8,
                     },
-                    egui::Color32::from_rgba_premultiplied(0, 0, 0, 178), // 70% opacity terminal
+                    self.palette
+                        .background
+                        .gamma_multiply(self.config.window.opacity), // Translucent terminal
                 );
 
                 // Custom macOS-style title bar (just the content, background already drawn)
-                ui.horizontal(|ui| {
-                    ui.spacing_mut().item_spacing.x = 0.0;
-
-                    // Create title bar area (just for content positioning)
-                    let (rect, _response) = ui.allocate_exact_size(
-                        egui::Vec2::new(ui.available_width(), title_bar_height),
-                        egui::Sense::hover(),
-                    );
-
-                    // macOS traffic light buttons (left side)
-                    let button_size = 12.0;
-                    let button_spacing = 20.0;
-                    let left_margin = 12.0;
-                    let button_y = rect.center().y;
-
-                    // Close button (red)
-                    let close_center = egui::Pos2::new(rect.left() + left_margin, button_y);
-                    let close_rect =
-                        egui::Rect::from_center_size(close_center, egui::Vec2::splat(button_size));
-                    let close_response = ui.allocate_rect(close_rect, egui::Sense::click());
-
-                    ui.painter().circle_filled(
-                        close_center,
-                        button_size / 2.0,
-                        egui::Color32::from_rgb(255, 95, 87), // macOS red
-                    );
-
-                    if close_response.clicked() {
-                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-                    }
-
-                    // Minimize button (yellow)
-                    let minimize_center =
-                        egui::Pos2::new(rect.left() + left_margin + button_spacing, button_y);
-                    let minimize_rect = egui::Rect::from_center_size(
-                        minimize_center,
-                        egui::Vec2::splat(button_size),
-                    );
-                    let minimize_response = ui.allocate_rect(minimize_rect, egui::Sense::click());
-
-                    ui.painter().circle_filled(
-                        minimize_center,
-                        button_size / 2.0,
-                        egui::Color32::from_rgb(255, 189, 46), // macOS yellow
-                    );
-
-                    if minimize_response.clicked() {
-                        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
-                    }
-
-                    // Maximize button (green)
-                    let maximize_center =
-                        egui::Pos2::new(rect.left() + left_margin + button_spacing * 2.0, button_y);
-                    let maximize_rect = egui::Rect::from_center_size(
-                        maximize_center,
-                        egui::Vec2::splat(button_size),
-                    );
-                    let maximize_response = ui.allocate_rect(maximize_rect, egui::Sense::click());
-
-                    ui.painter().circle_filled(
-                        maximize_center,
-                        button_size / 2.0,
-                        egui::Color32::from_rgb(40, 201, 64), // macOS green
-                    );
-
-                    if maximize_response.clicked() {
-                        // Toggle between maximized and normal
-                        ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(true));
-                    }
-
-                    // Draw title text (centered)
-                    let title_text = "🖥️ WTerm: macOS 스타일 터미널";
-                    let text_size = ui
-                        .fonts(|f| {
-                            f.layout_no_wrap(
-                                title_text.to_string(),
-                                egui::FontId::default(),
-                                egui::Color32::WHITE,
-                            )
-                        })
-                        .size();
-
-                    let text_pos = egui::Pos2::new(
-                        rect.center().x - text_size.x / 2.0,
-                        rect.center().y - text_size.y / 2.0,
-                    );
-
-                    ui.painter().text(
-                        text_pos,
-                        egui::Align2::LEFT_TOP,
-                        title_text,
-                        egui::FontId::default(),
-                        egui::Color32::WHITE,
-                    );
-                });
+                self.tab_strip_rect = None;
+                if custom_title_bar {
+                    ui.horizontal(|ui| {
+                        ui.spacing_mut().item_spacing.x = 0.0;
+
+                        // Create title bar area (just for content positioning)
+                        let (rect, _response) = ui.allocate_exact_size(
+                            egui::Vec2::new(ui.available_width(), title_bar_height),
+                            egui::Sense::hover(),
+                        );
 
-                ui.separator();
+                        // macOS traffic light buttons (left side)
+                        let button_size = 12.0;
+                        let button_spacing = 20.0;
+                        let left_margin = 12.0;
+                        let button_y = rect.center().y;
+
+                        // Close button (red)
+                        let close_center = egui::Pos2::new(rect.left() + left_margin, button_y);
+                        let close_rect = egui::Rect::from_center_size(
+                            close_center,
+                            egui::Vec2::splat(button_size),
+                        );
+                        let close_response = ui.allocate_rect(close_rect, egui::Sense::click());
 
-                // Background is already drawn above as one unified rounded rectangle
+                        ui.painter().circle_filled(
+                            close_center,
+                            button_size / 2.0,
+                            egui::Color32::from_rgb(255, 95, 87), // macOS red
+                        );
 
-                // Calculate available space for terminal after header and info
-                let remaining_rect = ui.available_rect_before_wrap();
+                        if close_response.clicked() {
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                        }
 
-                // Calculate terminal size based on the remaining space, including pixel dimensions
-                let (terminal_rows, terminal_cols, pixel_width, pixel_height) =
-                    self.calculate_terminal_size(remaining_rect, ui);
-
-                // Resize terminal if needed
-                self.resize_terminal(terminal_rows, terminal_cols, pixel_width, pixel_height)
-                    .unwrap();
-
-                // Terminal display with focus handling and proper scrolling
-                let scroll_area = egui::ScrollArea::vertical()
-                    .id_salt("terminal_scroll") // Use id_salt for persistent state (corrected from id_source)
-                    .stick_to_bottom(true)
-                    .auto_shrink([false; 2]);
-
-                let terminal_response = scroll_area.show(ui, |ui| {
-                    // Calculate exact font metrics
-                    let font_id = egui::FontId::new(11.0, egui::FontFamily::Monospace);
-                    let line_height = ui.fonts(|f| f.row_height(&font_id));
-                    let char_width = ui.fonts(|f| f.glyph_width(&font_id, 'M'));
-
-                    if let Ok(mut state) = self.terminal_state.lock() {
-                        // If the terminal state has changed, update the reflowed render buffer.
-                        state.update_render_buffer_if_dirty();
-
-                        let content_width = state.cols as f32 * char_width;
-                        // The total height is now based on the reflowed render_buffer.
-                        let total_lines = state.render_buffer.len();
-                        let content_height = total_lines as f32 * line_height;
-
-                        let (response, painter) = ui.allocate_painter(
-                            egui::Vec2::new(content_width, content_height),
-                            egui::Sense::click_and_drag()
-                                .union(egui::Sense::focusable_noninteractive()),
+                        // Minimize button (yellow)
+                        let minimize_center =
+                            egui::Pos2::new(rect.left() + left_margin + button_spacing, button_y);
+                        let minimize_rect = egui::Rect::from_center_size(
+                            minimize_center,
+                            egui::Vec2::splat(button_size),
                         );
+                        let minimize_response =
+                            ui.allocate_rect(minimize_rect, egui::Sense::click());
 
-                        // Background is already drawn above, no need to draw again here
+                        ui.painter().circle_filled(
+                            minimize_center,
+                            button_size / 2.0,
+                            egui::Color32::from_rgb(255, 189, 46), // macOS yellow
+                        );
 
-                        if response.clicked() {
-                            ui.memory_mut(|mem| mem.request_focus(response.id));
+                        if minimize_response.clicked() {
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
                         }
 
-                        // --- Row Virtualization ---
-                        let first_visible_row = ((ui.clip_rect().top() - response.rect.top())
-                            / line_height)
-                            .floor()
-                            .max(0.0) as usize;
-                        let last_visible_row = ((ui.clip_rect().bottom() - response.rect.top())
-                            / line_height)
-                            .ceil() as usize;
-                        let last_visible_row = last_visible_row.min(total_lines);
-
-                        // Update viewport information for optimized render_buffer updates
-                        state.update_viewport(first_visible_row, last_visible_row);
-
-                        // Draw only the visible rows from the render_buffer.
-                        for row_idx in first_visible_row..last_visible_row {
-                            // Safety check: ensure row_idx is within render_buffer bounds
-                            if row_idx >= state.render_buffer.len() {
-                                break;
-                            }
-                            let row_data = &state.render_buffer[row_idx];
-                            let y = response.rect.top() + row_idx as f32 * line_height;
-                            let mut col_offset = 0.0;
-
-                            for cell in row_data.iter() {
-                                if cell.ch == '\u{0000}' {
-                                    continue;
-                                }
-
-                                let char_display_width = if cell.ch.width().unwrap_or(1) == 2 {
-                                    2.0
-                                } else {
-                                    1.0
-                                };
-                                let display_width = char_display_width * char_width;
-                                let x = response.rect.left() + col_offset;
-                                let pos = egui::Pos2::new(x, y);
-                                let cell_rect = egui::Rect::from_min_size(
-                                    pos,
-                                    egui::Vec2::new(display_width, line_height),
-                                );
+                        // Maximize button (green)
+                        let maximize_center = egui::Pos2::new(
+                            rect.left() + left_margin + button_spacing * 2.0,
+                            button_y,
+                        );
+                        let maximize_rect = egui::Rect::from_center_size(
+                            maximize_center,
+                            egui::Vec2::splat(button_size),
+                        );
+                        let maximize_response =
+                            ui.allocate_rect(maximize_rect, egui::Sense::click());
 
-                                let (final_fg, final_bg) = if cell.color.reverse {
-                                    (cell.color.background, cell.color.foreground)
-                                } else {
-                                    (cell.color.foreground, cell.color.background)
-                                };
-
-                                if final_bg != egui::Color32::TRANSPARENT {
-                                    painter.rect_filled(
-                                        cell_rect,
-                                        egui::CornerRadius::ZERO,
-                                        final_bg,
-                                    );
-                                }
+                        ui.painter().circle_filled(
+                            maximize_center,
+                            button_size / 2.0,
+                            egui::Color32::from_rgb(40, 201, 64), // macOS green
+                        );
 
-                                if cell.ch != ' ' {
-                                    let mut text_color = final_fg;
-                                    if cell.color.bold {
-                                        let [r, g, b, a] = text_color.to_array();
-                                        text_color = egui::Color32::from_rgba_unmultiplied(
-                                            (r as f32 * 1.3).min(255.0) as u8,
-                                            (g as f32 * 1.3).min(255.0) as u8,
-                                            (b as f32 * 1.3).min(255.0) as u8,
-                                            a,
-                                        );
-                                    }
-                                    painter.text(
-                                        pos,
-                                        egui::Align2::LEFT_TOP,
-                                        cell.ch,
-                                        font_id.clone(),
-                                        text_color,
-                                    );
-                                    if cell.color.underline {
-                                        let underline_y = y + line_height - 1.0;
-                                        painter.line_segment(
-                                            [
-                                                egui::Pos2::new(x, underline_y),
-                                                egui::Pos2::new(x + display_width, underline_y),
-                                            ],
-                                            egui::Stroke::new(1.0, text_color),
-                                        );
-                                    }
-                                }
-                                col_offset += display_width;
-                            }
+                        if maximize_response.clicked() {
+                            // Toggle between maximized and normal
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(true));
                         }
 
-                        // Draw cursor based on the calculated visual position from TerminalState.
-                        let cursor_y =
-                            response.rect.top() + state.render_cursor_row as f32 * line_height;
-                        if cursor_y >= ui.clip_rect().top()
-                            && cursor_y + line_height <= ui.clip_rect().bottom()
-                        {
-                            let cursor_x =
-                                response.rect.left() + state.render_cursor_col as f32 * char_width;
+                        // With several tabs the title bar holds the tab strip
+                        if self.tabs.len() > 1 {
+                            let strip_rect = egui::Rect::from_min_max(
+                                egui::pos2(
+                                    rect.left() + left_margin + button_spacing * 3.0,
+                                    rect.top(),
+                                ),
+                                egui::pos2(rect.right() - 8.0, rect.bottom()),
+                            );
+                            self.show_tab_strip(ui, ctx, strip_rect);
+                            return;
+                        }
 
-                            if self.korean_state.is_composing {
-                                // println!("📍 Cursor position: row={}, col={}, x={}, y={}",
-                                //     state.render_cursor_row, state.render_cursor_col, cursor_x, cursor_y);
-                            }
-                            if state.cursor_visible && !self.korean_state.is_composing {
-                                let cursor_line_y = cursor_y + line_height - 2.0;
-                                painter.rect_filled(
-                                    egui::Rect::from_min_size(
-                                        egui::Pos2::new(cursor_x, cursor_line_y),
-                                        egui::Vec2::new(char_width, 2.0),
-                                    ),
-                                    egui::CornerRadius::ZERO,
+                        // Draw title text (centered)
+                        let title_text = self
+                            .tab()
+                            .name
+                            .clone()
+                            .or_else(|| self.session().custom_title())
+                            .unwrap_or_else(|| {
+                                self.title
+                                    .clone()
+                                    .unwrap_or_else(|| TITLE_BAR_TEXT.to_string())
+                            });
+                        let title_text = title_text.as_str();
+                        let text_size = ui
+                            .fonts(|f| {
+                                f.layout_no_wrap(
+                                    title_text.to_string(),
+                                    egui::FontId::default(),
                                     egui::Color32::WHITE,
-                                );
-                            }
-                            // Calculate cursor width for Korean composition if needed
-                            let cursor_width = if self.korean_state.is_composing {
-                                // Korean composing characters are always wide (2 chars)
-                                2.0 * char_width
-                            } else {
-                                // Normal cursor width
-                                char_width
-                            };
+                                )
+                            })
+                            .size();
 
-                            // Draw composing character preview if Korean composition is active
-                            if self.korean_state.is_composing {
-                                if let Some(composing_char) = self.korean_state.get_current_char() {
-                                    // Calculate precise cursor X position by walking through the row (like e32f82d)
-                                    // This ensures accurate positioning regardless of render_buffer update timing
-                                    let mut preview_x = response.rect.left();
+                        let text_pos = egui::Pos2::new(
+                            rect.center().x - text_size.x / 2.0,
+                            rect.center().y - text_size.y / 2.0,
+                        );
 
-                                    let cursor_row_data =
-                                        if state.cursor_row < state.main_buffer.len() {
-                                            Some(&state.main_buffer[state.cursor_row])
-                                        } else {
-                                            None
-                                        };
-
-                                    // Walk through the row to calculate precise cursor position
-                                    if let Some(row) = cursor_row_data {
-                                        for cell_idx in 0..state.cursor_col.min(row.len()) {
-                                            let cell = &row[cell_idx];
-                                            if cell.ch == '\u{0000}' {
-                                                continue;
-                                            }
+                        ui.painter().text(
+                            text_pos,
+                            egui::Align2::LEFT_TOP,
+                            title_text,
+                            egui::FontId::default(),
+                            egui::Color32::WHITE,
+                        );
+                    });
 
-                                            // Calculate display width like e32f82d
-                                            let char_display_width =
-                                                if cell.ch.width().unwrap_or(1) == 2 {
-                                                    2 // Korean and other wide characters are 2 units
-                                                } else {
-                                                    1 // All other characters are 1 unit
-                                                };
-                                            preview_x += char_display_width as f32 * char_width;
-                                        }
-                                    }
+                    ui.separator();
+                } else if self.tabs.len() > 1 {
+                    // Native title bar: tabs get a row of their own
+                    let (strip_rect, _) = ui.allocate_exact_size(
+                        egui::vec2(ui.available_width(), 24.0),
+                        egui::Sense::hover(),
+                    );
+                    self.show_tab_strip(ui, ctx, strip_rect);
+                    ui.separator();
+                }
 
-                                    let preview_y = cursor_y;
-
-                                    // println!("🎯 Composing preview at: cursor_col={}, calculated_x={}, y={} for char '{}' (using e32f82d method)",
-                                    //     state.cursor_col, preview_x, preview_y, composing_char);
-
-                                    // Draw composing character with a different color (gray/dimmed) to show it's temporary
-                                    let preview_color = egui::Color32::from_rgb(150, 150, 150); // Gray preview color
-
-                                    // Draw a subtle background to make the preview more visible
-                                    let preview_bg =
-                                        egui::Color32::from_rgba_unmultiplied(100, 100, 100, 50);
-                                    painter.rect_filled(
-                                        egui::Rect::from_min_size(
-                                            egui::Pos2::new(preview_x, preview_y),
-                                            egui::Vec2::new(cursor_width, line_height),
-                                        ),
-                                        egui::CornerRadius::ZERO,
-                                        preview_bg,
-                                    );
-
-                                    // Draw the composing character
-                                    painter.text(
-                                        egui::Pos2::new(preview_x, preview_y),
-                                        egui::Align2::LEFT_TOP,
-                                        composing_char,
-                                        font_id.clone(),
-                                        preview_color,
-                                    );
-
-                                    // Hide the normal cursor when composing
-                                    // (The composing character serves as a visual cursor)
-                                }
-                            }
-                        }
+                // Background is already drawn above as one unified rounded rectangle
 
-                        response
-                    } else {
-                        ui.allocate_response(egui::Vec2::new(800.0, 600.0), egui::Sense::click())
-                    }
-                });
+                // Lay the active tab's panes out in the space left
+                let remaining_rect = ui.available_rect_before_wrap();
+                self.pane_area = remaining_rect;
+                let (panes, dividers) = self.tab().visible_panes(remaining_rect);
+                self.show_dividers(ui, &dividers);
+                let mut pane_widgets = Vec::new();
+                for &(id, rect) in &panes {
+                    pane_widgets.push((id, self.show_pane(ui, id, rect)));
+                }
+
+                // The focused pane gets the keys, the search bar and the
+                // copy-mode status, and an outline while there are several
+                let focus = self.tab().focus;
+                let focused_rect = panes
+                    .iter()
+                    .find(|(id, _)| *id == focus)
+                    .map_or(remaining_rect, |(_, rect)| *rect);
+                let terminal_id = pane_widgets
+                    .iter()
+                    .find(|(id, _)| *id == focus)
+                    .map_or(egui::Id::NULL, |(_, widget)| *widget);
+                if panes.len() > 1 {
+                    ui.painter().rect_stroke(
+                        focused_rect,
+                        egui::CornerRadius::ZERO,
+                        egui::Stroke::new(1.0, FOCUS_BORDER_COLOR),
+                        egui::StrokeKind::Inside,
+                    );
+                }
+
+                if self.search.active {
+                    self.show_search_bar(ctx, focused_rect);
+                }
+                if self.vi_mode.active {
+                    self.show_vi_status(ctx, focused_rect);
+                }
+                self.draw_toast(ctx);
 
-                // Set initial focus when app starts
-                if !self.initial_focus_set {
-                    ui.memory_mut(|mem| mem.request_focus(terminal_response.inner.id));
-                    self.initial_focus_set = true;
-                    println!("🎯 Initial focus set to terminal");
+                // Focus the terminal when the app starts and after the focus moved
+                if !self.focus_set {
+                    ui.memory_mut(|mem| mem.request_focus(terminal_id));
+                    self.focus_set = true;
                 }
 
                 // Handle keyboard input when terminal has focus
-                let has_focus = ui.memory(|mem| mem.has_focus(terminal_response.inner.id));
+                let has_focus = ui.memory(|mem| mem.has_focus(terminal_id));
 
                 // Handle Tab key with raw event processing and debouncing
                 let tab_handled = ctx.input_mut(|i| {
@@ -830,11 +2459,7 @@ impl eframe::App for TerminalApp {
                     });
 
                     // Only handle Tab PRESS, ignore RELEASE to prevent duplicate sending
-                    if tab_press_found {
-                        true
-                    } else {
-                        false
-                    }
+                    tab_press_found
                 });
 
                 // Send Tab to PTY with debouncing (only if enough time has passed since last Tab)
@@ -847,21 +2472,25 @@ impl eframe::App for TerminalApp {
                         true // First Tab key
                     };
 
-                    if should_send {
+                    if should_send && !search_focused {
                         // Ensure terminal has focus before and after sending Tab
-                        ui.memory_mut(|mem| mem.request_focus(terminal_response.inner.id));
+                        ui.memory_mut(|mem| mem.request_focus(terminal_id));
                         self.finalize_korean_composition();
                         self.send_to_pty("\t");
                         self.last_tab_time = Some(now);
                         // Force focus again after sending Tab to prevent losing focus
-                        ui.memory_mut(|mem| mem.request_focus(terminal_response.inner.id));
+                        ui.memory_mut(|mem| mem.request_focus(terminal_id));
                     }
                 }
 
-                // Handle ESC key specially using direct input check
-                if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                // Escape in the search bar closes it instead of reaching the PTY
+                if search_focused && ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                    self.search.close();
+                    ui.memory_mut(|mem| mem.request_focus(terminal_id));
+                } else if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                    // Handle ESC key specially using direct input check
                     // Ensure terminal has focus
-                    ui.memory_mut(|mem| mem.request_focus(terminal_response.inner.id));
+                    ui.memory_mut(|mem| mem.request_focus(terminal_id));
 
                     if self.korean_state.is_composing {
                         // 조합 중이면 조합만 완성하고 ESC는 무시
@@ -882,17 +2511,19 @@ impl eframe::App for TerminalApp {
                         true // First Ctrl+I
                     };
 
-                    if should_send {
+                    if should_send && !search_focused {
                         // Ensure terminal has focus before and after sending Tab
-                        ui.memory_mut(|mem| mem.request_focus(terminal_response.inner.id));
+                        ui.memory_mut(|mem| mem.request_focus(terminal_id));
                         self.finalize_korean_composition();
                         self.send_to_pty("\t");
                         self.last_tab_time = Some(now);
                         // Force focus again after sending Tab to prevent losing focus
-                        ui.memory_mut(|mem| mem.request_focus(terminal_response.inner.id));
+                        ui.memory_mut(|mem| mem.request_focus(terminal_id));
                     }
                 }
 
+                let mut copy_requested = false;
+                let mut pasted_text: Option<String> = None;
                 if has_focus {
                     ctx.input(|i| {
                         // Debug: Log events only when relevant
@@ -919,6 +2550,12 @@ impl eframe::App for TerminalApp {
                                         continue;
                                     }
 
+                                    // Ctrl+Shift+C copies the selection instead of sending ^C
+                                    if *key == egui::Key::C && modifiers.ctrl && modifiers.shift {
+                                        copy_requested = true;
+                                        continue;
+                                    }
+
                                     // Debug: Log all other key events
                                     //println!("🔑 Key event: {:?} (modifiers: {:?})", key, modifiers);
                                     // Handle keys that should finalize Korean composition
@@ -927,7 +2564,7 @@ impl eframe::App for TerminalApp {
                                             //println!("🔑 DEBUG: Enter key pressed");
                                             self.finalize_korean_composition();
                                             // Reset arrow key state when user presses Enter
-                                            if let Ok(mut state) = self.terminal_state.lock() {
+                                            if let Ok(mut state) = self.terminal_state().lock() {
                                                 state.clear_arrow_key_protection();
                                             }
                                             // Send newline instead of carriage return to avoid duplication
@@ -948,7 +2585,8 @@ impl eframe::App for TerminalApp {
                                             } else {
                                                 // For regular backspace, let shell handle everything
                                                 // Shell has its own prompt protection (readline, zle, etc.)
-                                                if let Ok(mut state) = self.terminal_state.lock() {
+                                                if let Ok(mut state) = self.terminal_state().lock()
+                                                {
                                                     state.clear_arrow_key_protection();
                                                 }
                                                 // Send backspace directly to shell - no terminal-level protection needed
@@ -979,7 +2617,8 @@ impl eframe::App for TerminalApp {
                                                 self.finalize_korean_composition();
                                             } else {
                                                 // DIRECT cursor movement - bypass PTY to avoid backspace issue
-                                                if let Ok(mut state) = self.terminal_state.lock() {
+                                                if let Ok(mut state) = self.terminal_state().lock()
+                                                {
                                                     state.set_arrow_key_protection();
                                                     let current_col = state.cursor_col;
 
@@ -1034,7 +2673,8 @@ impl eframe::App for TerminalApp {
                                                 self.finalize_korean_composition();
                                             } else {
                                                 // DIRECT cursor movement - bypass PTY to avoid backspace issue
-                                                if let Ok(mut state) = self.terminal_state.lock() {
+                                                if let Ok(mut state) = self.terminal_state().lock()
+                                                {
                                                     state.set_arrow_key_protection();
                                                     let current_col = state.cursor_col;
 
@@ -1072,135 +2712,152 @@ impl eframe::App for TerminalApp {
                                         }
                                         _ => {
                                             // For other keys, handle normally without composition finalization
-                                            if let Ok(mut writer) = self.pty_writer.lock() {
-                                                match key {
-                                                    egui::Key::A if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x01");
-                                                        // Ctrl+A (Start of line)
-                                                    }
-                                                    egui::Key::B if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x02");
-                                                        // Ctrl+B (Backward char)
-                                                    }
-                                                    egui::Key::C if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x03");
-                                                        // Ctrl+C (Interrupt)
-                                                    }
-                                                    egui::Key::D if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x04");
-                                                        // Ctrl+D (EOF)
-                                                    }
-                                                    egui::Key::E if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x05");
-                                                        // Ctrl+E (End of line)
-                                                    }
-                                                    egui::Key::F if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x06");
-                                                        // Ctrl+F (Forward char)
-                                                    }
-                                                    egui::Key::G if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x07");
-                                                        // Ctrl+G (Bell)
-                                                    }
-                                                    egui::Key::H if modifiers.ctrl => {
-                                                        // Ctrl+H is same as Backspace, but Backspace is already handled above
-                                                        // Don't send duplicate
-                                                        // let _ = writer.write_all(b"\x08");
-                                                    }
-                                                    egui::Key::I if modifiers.ctrl => {
-                                                        // Ctrl+I is handled above as Tab alternative - ignore here
-                                                        //println!("🔄 Ctrl+I (already handled above as Tab alternative)");
-                                                    }
-                                                    egui::Key::J if modifiers.ctrl => {
-                                                        // Ctrl+J (Line feed) is similar to Enter
-                                                        // Keep this as it's a distinct terminal control sequence
-                                                        let _ = writer.write_all(b"\x0a");
-                                                    }
-                                                    egui::Key::K if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x0b");
-                                                        // Ctrl+K (Kill line)
-                                                    }
-                                                    egui::Key::L if modifiers.ctrl => {
-                                                        // Ctrl+L (Form Feed/Clear) - clear screen and request new prompt
-                                                        if let Ok(mut state) =
-                                                            self.terminal_state.lock()
-                                                        {
-                                                            state.clear_arrow_key_protection();
-                                                            state.clear_screen();
-                                                        }
-                                                        // Send Ctrl+L to PTY so shell displays new prompt
-                                                        let _ = writer.write_all(b"\x0c");
-                                                    }
-                                                    egui::Key::M if modifiers.ctrl => {
-                                                        // Ctrl+M is same as Enter, but Enter is already handled above
-                                                        // Don't send duplicate
-                                                        // let _ = writer.write_all(b"\x0d");
-                                                    }
-                                                    egui::Key::N if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x0e");
-                                                        // Ctrl+N (Next line)
-                                                    }
-                                                    egui::Key::O if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x0f");
-                                                        // Ctrl+O
-                                                    }
-                                                    egui::Key::P if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x10");
-                                                        // Ctrl+P (Previous line)
-                                                    }
-                                                    egui::Key::Q if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x11");
-                                                        // Ctrl+Q (XON)
-                                                    }
-                                                    egui::Key::R if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x12");
-                                                        // Ctrl+R (Reverse search)
-                                                    }
-                                                    egui::Key::S if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x13");
-                                                        // Ctrl+S (XOFF)
-                                                    }
-                                                    egui::Key::T if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x14");
-                                                        // Ctrl+T (Transpose)
-                                                    }
-                                                    egui::Key::U if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x15");
-                                                        // Ctrl+U (Kill line backward)
-                                                    }
-                                                    egui::Key::V if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x16");
-                                                        // Ctrl+V (Literal next)
-                                                    }
-                                                    egui::Key::W if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x17");
-                                                        // Ctrl+W (Kill word backward)
-                                                    }
-                                                    egui::Key::X if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x18");
-                                                        // Ctrl+X
-                                                    }
-                                                    egui::Key::Y if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x19");
-                                                        // Ctrl+Y (Yank)
-                                                    }
-                                                    egui::Key::Z if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x1a");
-                                                        // Ctrl+Z (Suspend)
-                                                    }
-                                                    egui::Key::Enter if modifiers.ctrl => {
-                                                        let _ = writer.write_all(b"\x0d");
-                                                        // Ctrl+Enter (may be useful for gemini)
-                                                    }
-                                                    _ => {
-                                                        // For other keys, don't need special handling
+                                            if modifiers.ctrl {
+                                                self.snap_to_bottom();
+                                            }
+                                            match key {
+                                                egui::Key::A if modifiers.ctrl => {
+                                                    self.send_to_pty("\x01");
+                                                    // Ctrl+A (Start of line)
+                                                }
+                                                egui::Key::B if modifiers.ctrl => {
+                                                    self.send_to_pty("\x02");
+                                                    // Ctrl+B (Backward char)
+                                                }
+                                                egui::Key::C if modifiers.ctrl => {
+                                                    self.send_to_pty("\x03");
+                                                    // Ctrl+C (Interrupt)
+                                                }
+                                                egui::Key::D if modifiers.ctrl => {
+                                                    self.send_to_pty("\x04");
+                                                    // Ctrl+D (EOF)
+                                                }
+                                                egui::Key::E if modifiers.ctrl => {
+                                                    self.send_to_pty("\x05");
+                                                    // Ctrl+E (End of line)
+                                                }
+                                                egui::Key::F if modifiers.ctrl => {
+                                                    self.send_to_pty("\x06");
+                                                    // Ctrl+F (Forward char)
+                                                }
+                                                egui::Key::G if modifiers.ctrl => {
+                                                    self.send_to_pty("\x07");
+                                                    // Ctrl+G (Bell)
+                                                }
+                                                egui::Key::H if modifiers.ctrl => {
+                                                    // Ctrl+H is same as Backspace, but Backspace is already handled above
+                                                    // Don't send duplicate
+                                                    // self.send_to_pty("\x08");
+                                                }
+                                                egui::Key::I if modifiers.ctrl => {
+                                                    // Ctrl+I is handled above as Tab alternative - ignore here
+                                                    //println!("🔄 Ctrl+I (already handled above as Tab alternative)");
+                                                }
+                                                egui::Key::J if modifiers.ctrl => {
+                                                    // Ctrl+J (Line feed) is similar to Enter
+                                                    // Keep this as it's a distinct terminal control sequence
+                                                    self.send_to_pty("\x0a");
+                                                }
+                                                egui::Key::K if modifiers.ctrl => {
+                                                    self.send_to_pty("\x0b");
+                                                    // Ctrl+K (Kill line)
+                                                }
+                                                egui::Key::L if modifiers.ctrl => {
+                                                    // Ctrl+L (Form Feed/Clear) - clear screen and request new prompt
+                                                    if let Ok(mut state) =
+                                                        self.terminal_state().lock()
+                                                    {
+                                                        state.clear_arrow_key_protection();
+                                                        state.clear_screen();
                                                     }
+                                                    // Send Ctrl+L to PTY so shell displays new prompt
+                                                    self.send_to_pty("\x0c");
+                                                }
+                                                egui::Key::M if modifiers.ctrl => {
+                                                    // Ctrl+M is same as Enter, but Enter is already handled above
+                                                    // Don't send duplicate
+                                                    // self.send_to_pty("\x0d");
+                                                }
+                                                egui::Key::N if modifiers.ctrl => {
+                                                    self.send_to_pty("\x0e");
+                                                    // Ctrl+N (Next line)
+                                                }
+                                                egui::Key::O if modifiers.ctrl => {
+                                                    self.send_to_pty("\x0f");
+                                                    // Ctrl+O
+                                                }
+                                                egui::Key::P if modifiers.ctrl => {
+                                                    self.send_to_pty("\x10");
+                                                    // Ctrl+P (Previous line)
+                                                }
+                                                egui::Key::Q if modifiers.ctrl => {
+                                                    self.send_to_pty("\x11");
+                                                    // Ctrl+Q (XON)
+                                                }
+                                                egui::Key::R if modifiers.ctrl => {
+                                                    self.send_to_pty("\x12");
+                                                    // Ctrl+R (Reverse search)
+                                                }
+                                                egui::Key::S if modifiers.ctrl => {
+                                                    self.send_to_pty("\x13");
+                                                    // Ctrl+S (XOFF)
+                                                }
+                                                egui::Key::T if modifiers.ctrl => {
+                                                    self.send_to_pty("\x14");
+                                                    // Ctrl+T (Transpose)
+                                                }
+                                                egui::Key::U if modifiers.ctrl => {
+                                                    self.send_to_pty("\x15");
+                                                    // Ctrl+U (Kill line backward)
+                                                }
+                                                egui::Key::V if modifiers.ctrl => {
+                                                    self.send_to_pty("\x16");
+                                                    // Ctrl+V (Literal next)
+                                                }
+                                                egui::Key::W if modifiers.ctrl => {
+                                                    self.send_to_pty("\x17");
+                                                    // Ctrl+W (Kill word backward)
+                                                }
+                                                egui::Key::X if modifiers.ctrl => {
+                                                    self.send_to_pty("\x18");
+                                                    // Ctrl+X
+                                                }
+                                                egui::Key::Y if modifiers.ctrl => {
+                                                    self.send_to_pty("\x19");
+                                                    // Ctrl+Y (Yank)
+                                                }
+                                                egui::Key::Z if modifiers.ctrl => {
+                                                    self.send_to_pty("\x1a");
+                                                    // Ctrl+Z (Suspend)
+                                                }
+                                                egui::Key::Enter if modifiers.ctrl => {
+                                                    self.send_to_pty("\x0d");
+                                                    // Ctrl+Enter (may be useful for gemini)
+                                                }
+                                                _ => {
+                                                    // For other keys, don't need special handling
                                                 }
-                                                let _ = writer.flush();
                                             }
                                         }
                                     }
                                 }
+                                egui::Event::Copy => {
+                                    // egui turns Cmd+C (macOS) and Ctrl+C (elsewhere) into Copy.
+                                    // Off macOS only Ctrl+Shift+C copies; Ctrl+C stays an interrupt.
+                                    if cfg!(target_os = "macos") || i.modifiers.shift {
+                                        copy_requested = true;
+                                    } else {
+                                        self.send_to_pty("\x03");
+                                    }
+                                }
+                                egui::Event::Paste(text) => {
+                                    // Cmd+V (macOS) / Ctrl+Shift+V; plain Ctrl+V stays ^V
+                                    if cfg!(target_os = "macos") || i.modifiers.shift {
+                                        pasted_text = Some(text.clone());
+                                    } else {
+                                        self.send_to_pty("\x16");
+                                    }
+                                }
                                 egui::Event::Text(text) => {
                                     // Debug: Log what text events we receive (disabled for performance)
                                     // println!("🔍 Text event received: {:?} (bytes: {:?})", text, text.as_bytes());
@@ -1227,6 +2884,214 @@ impl eframe::App for TerminalApp {
                         }
                     });
                 }
+
+                // Clipboard access needs the context, so it happens outside the input closure
+                if copy_requested {
+                    self.copy_selection(ctx);
+                }
+                if let Some(text) = pasted_text {
+                    self.paste_text(&text);
+                }
             });
     }
 }
+
+// Convert a pointer position inside the terminal painter to a render_buffer cell
+fn pointer_to_cell(
+    pos: egui::Pos2,
+    rect: egui::Rect,
+    char_width: f32,
+    line_height: f32,
+    state: &TerminalState,
+) -> SelectionPoint {
+    let row = ((pos.y - rect.top()) / line_height).floor().max(0.0) as usize;
+    let col = ((pos.x - rect.left()) / char_width).floor().max(0.0) as usize;
+    SelectionPoint::new(
+        row.min(state.render_buffer.len().saturating_sub(1)),
+        col.min(state.cols.saturating_sub(1)),
+    )
+}
+
+// Shapes for the cells of one render_buffer row, its top-left corner at the origin
+fn paint_row(
+    fonts: &egui::epaint::Fonts,
+    row: &[TerminalCell],
+    palette: &Palette,
+    font_id: &egui::FontId,
+    char_width: f32,
+    line_height: f32,
+) -> Vec<egui::Shape> {
+    let mut shapes = Vec::new();
+    let mut col_offset = 0.0;
+
+    for cell in row.iter() {
+        if cell.ch == '\u{0000}' {
+            continue;
+        }
+
+        let char_display_width = if cell.ch.width().unwrap_or(1) == 2 {
+            2.0
+        } else {
+            1.0
+        };
+        let display_width = char_display_width * char_width;
+        let x = col_offset;
+        let pos = egui::Pos2::new(x, 0.0);
+        let cell_rect = egui::Rect::from_min_size(pos, egui::Vec2::new(display_width, line_height));
+
+        let (final_fg, final_bg) = if cell.color.reverse {
+            (cell.color.background, cell.color.foreground)
+        } else {
+            (cell.color.foreground, cell.color.background)
+        };
+
+        // The default background is the (translucent) window itself
+        if final_bg != TermColor::Background {
+            shapes.push(egui::Shape::rect_filled(
+                cell_rect,
+                egui::CornerRadius::ZERO,
+                palette.resolve(final_bg),
+            ));
+        }
+        let final_fg = palette.resolve(final_fg);
+
+        if cell.ch != ' ' {
+            let mut text_color = final_fg;
+            if cell.color.bold {
+                let [r, g, b, a] = text_color.to_array();
+                text_color = egui::Color32::from_rgba_unmultiplied(
+                    (r as f32 * 1.3).min(255.0) as u8,
+                    (g as f32 * 1.3).min(255.0) as u8,
+                    (b as f32 * 1.3).min(255.0) as u8,
+                    a,
+                );
+            }
+            shapes.push(egui::Shape::text(
+                fonts,
+                pos,
+                egui::Align2::LEFT_TOP,
+                cell.ch,
+                font_id.clone(),
+                text_color,
+            ));
+            if cell.color.underline {
+                let underline_y = line_height - 1.0;
+                shapes.push(egui::Shape::line_segment(
+                    [
+                        egui::Pos2::new(x, underline_y),
+                        egui::Pos2::new(x + display_width, underline_y),
+                    ],
+                    egui::Stroke::new(1.0, text_color),
+                ));
+            }
+        }
+        col_offset += display_width;
+    }
+    shapes
+}
+
+// Fill the cells first..=last of a row starting at `origin` (used for highlights)
+fn paint_cell_span(
+    painter: &egui::Painter,
+    origin: egui::Pos2,
+    (first_col, last_col): (usize, usize),
+    char_width: f32,
+    line_height: f32,
+    color: egui::Color32,
+) {
+    painter.rect_filled(
+        egui::Rect::from_min_max(
+            egui::Pos2::new(origin.x + first_col as f32 * char_width, origin.y),
+            egui::Pos2::new(
+                origin.x + (last_col + 1) as f32 * char_width,
+                origin.y + line_height,
+            ),
+        ),
+        egui::CornerRadius::ZERO,
+        color,
+    );
+}
+
+// Command for a session: `program args` with the terminal environment.
+// `shell` is what the child sees as $SHELL.
+fn build_command(
+    program: &str,
+    args: &[String],
+    cwd: Option<PathBuf>,
+    config: &Config,
+    shell: &str,
+) -> CommandBuilder {
+    let mut cmd = CommandBuilder::new(program);
+    cmd.args(args);
+    if let Some(dir) = cwd {
+        cmd.cwd(dir);
+    }
+    cmd.env("SHELL", shell);
+    // Disable the reverse-video '%' character at the end of partial lines
+    //P1: '\\x1b]0;', P2: '\\x07'
+    cmd.env("PROMPT_EOL_MARK", "%{%G%}");
+    // Ensure consistent terminal behavior and fix visual glitches
+    cmd.env("TERM_PROGRAM", "wterm");
+    cmd.env("TERM_PROGRAM_VERSION", "1.0");
+
+    // Prevent oh-my-zsh from trying to set the window title
+    cmd.env("DISABLE_AUTO_TITLE", "true");
+    // TERM, LANG and the config file's [env], which may override any of the above
+    for (key, value) in config.child_env() {
+        cmd.env(key, value);
+    }
+    cmd
+}
+
+// Start the configured shell in a new session, or $SHELL and then /bin/sh
+// if it can't be started. Returns the session, the program that runs in it
+// and, after a fallback, why the configured shell didn't start.
+fn spawn_shell(
+    id: u64,
+    config: &Config,
+    cwd: Option<PathBuf>,
+    size: PtySize,
+    title: Option<&str>,
+    egui_ctx: &egui::Context,
+) -> Result<(Session, String, Option<anyhow::Error>)> {
+    let mut first_error = None;
+    for (program, args) in config.shell.commands_to_try() {
+        let cmd = build_command(&program, &args, cwd.clone(), config, &program);
+        let title = title.map_or_else(|| program_name(&program), str::to_string);
+        match Session::spawn(id, cmd, size, config, title, egui_ctx) {
+            Ok(session) => return Ok((session, program, first_error)),
+            Err(e) => {
+                eprintln!("❌ Can't start {}: {:#}", program, e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    Err(first_error.unwrap_or_else(|| anyhow::anyhow!("no shell to start")))
+}
+
+// "/usr/bin/zsh" -> "zsh", the default tab title
+fn program_name(program: &str) -> String {
+    std::path::Path::new(program)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| program.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_from_the_config_reaches_the_command() {
+        let config = Config::parse("[env]\nLANG = \"en_US.UTF-8\"\nTERM = \"xterm\"").unwrap();
+        let cmd = build_command("/bin/bash", &[], None, &config, "/bin/bash");
+        assert_eq!(cmd.get_env("LANG").unwrap(), "en_US.UTF-8");
+        assert_eq!(cmd.get_env("TERM").unwrap(), "xterm");
+        assert_eq!(cmd.get_env("SHELL").unwrap(), "/bin/bash");
+
+        // Without [env] the built-in defaults apply
+        let cmd = build_command("/bin/bash", &[], None, &Config::default(), "/bin/bash");
+        assert_eq!(cmd.get_env("TERM").unwrap(), "xterm-256color");
+        assert_eq!(cmd.get_env("LANG").unwrap(), "ko_KR.UTF-8");
+    }
+}
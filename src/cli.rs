@@ -0,0 +1,147 @@
+use std::path::PathBuf;
+
+const USAGE: &str = "\
+Usage: wterm [OPTIONS] [-e <COMMAND>...]
+
+Options:
+  -e, --command <COMMAND>...      Run COMMAND instead of the shell (takes the rest of the arguments)
+  --working-directory <DIR>       Start in DIR
+  --title <TITLE>                 Window title
+  --class <CLASS>                 Window class / Wayland app id
+  --hold                          Keep the window open after the command exits
+  --config <FILE>                 Config file (default: $XDG_CONFIG_HOME/wterm/wterm.toml)
+  -h, --help                      Print this help";
+
+// Command-line options
+#[derive(Debug, Default)]
+pub struct Cli {
+    pub config: Option<PathBuf>,
+    // Program and arguments from -e, overriding [shell]
+    pub command: Option<Vec<String>>,
+    // Overrides shell.working_directory
+    pub working_directory: Option<PathBuf>,
+    pub title: Option<String>,
+    pub class: Option<String>,
+    pub hold: bool,
+}
+
+impl Cli {
+    // Parse argv; prints usage and exits on --help or bad arguments
+    pub fn parse() -> Self {
+        match Self::parse_from(std::env::args().skip(1)) {
+            Ok(Some(cli)) => cli,
+            Ok(None) => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            Err(message) => {
+                eprintln!("wterm: {}\n\n{}", message, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    // Ok(None) means help was requested
+    fn parse_from(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut cli = Cli::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // --option=value is accepted as well as --option value
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => {
+                    (name.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+            let mut value = |option: &str| {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("{} needs a value", option))
+            };
+
+            match name.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "--config" => cli.config = Some(PathBuf::from(value("--config")?)),
+                "--working-directory" => {
+                    cli.working_directory = Some(PathBuf::from(value("--working-directory")?))
+                }
+                "--title" => cli.title = Some(value("--title")?),
+                "--class" => cli.class = Some(value("--class")?),
+                "--hold" if inline_value.is_none() => cli.hold = true,
+                // Everything after -e belongs to the command, options included
+                "-e" | "--command" => {
+                    let command: Vec<String> = inline_value.into_iter().chain(args).collect();
+                    if command.is_empty() {
+                        return Err(format!("{} needs a command", name));
+                    }
+                    cli.command = Some(command);
+                    break;
+                }
+                _ => return Err(format!("unknown argument {:?}", arg)),
+            }
+        }
+        Ok(Some(cli))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Cli>, String> {
+        Cli::parse_from(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn command_takes_the_rest_of_the_arguments() {
+        let cli = parse(&["--hold", "-e", "vim", "--title", "x", "-e"])
+            .unwrap()
+            .unwrap();
+        assert!(cli.hold);
+        assert_eq!(
+            cli.command.unwrap(),
+            ["vim", "--title", "x", "-e"].map(String::from)
+        );
+        assert_eq!(cli.title, None);
+
+        let cli = parse(&["--command=htop", "-d", "10"]).unwrap().unwrap();
+        assert_eq!(cli.command.unwrap(), ["htop", "-d", "10"].map(String::from));
+
+        assert_eq!(parse(&["-e"]).unwrap_err(), "-e needs a command");
+    }
+
+    #[test]
+    fn options_take_separate_or_inline_values() {
+        let cli = parse(&[
+            "--working-directory",
+            "/tmp",
+            "--title=My Term",
+            "--class",
+            "wterm-dev",
+            "--config=/etc/wterm.toml",
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(cli.working_directory, Some(PathBuf::from("/tmp")));
+        assert_eq!(cli.title.as_deref(), Some("My Term"));
+        assert_eq!(cli.class.as_deref(), Some("wterm-dev"));
+        assert_eq!(cli.config, Some(PathBuf::from("/etc/wterm.toml")));
+        assert!(!cli.hold);
+        assert_eq!(cli.command, None);
+    }
+
+    #[test]
+    fn bad_arguments_are_reported() {
+        assert_eq!(
+            parse(&["--working-directory"]).unwrap_err(),
+            "--working-directory needs a value"
+        );
+        assert_eq!(
+            parse(&["--hold=yes"]).unwrap_err(),
+            "unknown argument \"--hold=yes\""
+        );
+        assert_eq!(parse(&["vim"]).unwrap_err(), "unknown argument \"vim\"");
+        assert!(parse(&["--title", "x", "--help"]).unwrap().is_none());
+    }
+}
@@ -0,0 +1,163 @@
+use anyhow::{bail, Result};
+use eframe::egui;
+use serde::Deserialize;
+
+// Things a key binding can trigger
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum Action {
+    ToggleSearch,
+    ToggleCopyMode,
+    ToggleHints,
+    ScrollPageUp,
+    ScrollPageDown,
+    ScrollToTop,
+    ScrollToBottom,
+    NewTab,
+    CloseTab,
+    NextTab,
+    PreviousTab,
+    MoveTabLeft,
+    MoveTabRight,
+    SplitRight,
+    SplitDown,
+    ClosePane,
+    FocusPaneLeft,
+    FocusPaneRight,
+    FocusPaneUp,
+    FocusPaneDown,
+    ToggleZoom,
+    ToggleBroadcast,
+    BroadcastTab,
+    None, // Removes a default binding; the key goes to the terminal again
+}
+
+// [[keybindings]] entry, e.g. { key = "F", mods = "Ctrl|Shift", action = "ToggleSearch" }
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyBinding {
+    pub key: String,
+    #[serde(default)]
+    pub mods: String, // "Ctrl", "Shift", "Alt", "Command", joined with '|' or '+'
+    pub action: Action,
+}
+
+impl KeyBinding {
+    fn new(key: &str, mods: &str, action: Action) -> Self {
+        Self {
+            key: key.to_string(),
+            mods: mods.to_string(),
+            action,
+        }
+    }
+
+    fn parse(&self) -> Result<(egui::Modifiers, egui::Key)> {
+        let Some(key) = egui::Key::from_name(&self.key) else {
+            bail!(
+                "keybindings: unknown key {:?} (use names like \"F\", \"PageUp\", \"Space\")",
+                self.key
+            );
+        };
+
+        let mut modifiers = egui::Modifiers::NONE;
+        for name in self
+            .mods
+            .split(['|', '+'])
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            modifiers |= match name.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => egui::Modifiers::CTRL,
+                "shift" => egui::Modifiers::SHIFT,
+                "alt" | "option" => egui::Modifiers::ALT,
+                "command" | "cmd" | "super" => egui::Modifiers::COMMAND,
+                _ => bail!(
+                    "keybindings: unknown modifier {:?} in {:?} (expected Ctrl, Shift, Alt or Command)",
+                    name,
+                    self.mods
+                ),
+            };
+        }
+        Ok((modifiers, key))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        self.parse().map(|_| ())
+    }
+}
+
+fn default_bindings() -> Vec<KeyBinding> {
+    vec![
+        KeyBinding::new("F", "Ctrl|Shift", Action::ToggleSearch),
+        KeyBinding::new("Space", "Ctrl|Shift", Action::ToggleCopyMode),
+        KeyBinding::new("H", "Ctrl|Shift", Action::ToggleHints),
+        KeyBinding::new("PageUp", "Shift", Action::ScrollPageUp),
+        KeyBinding::new("PageDown", "Shift", Action::ScrollPageDown),
+        KeyBinding::new("Home", "Shift", Action::ScrollToTop),
+        KeyBinding::new("End", "Shift", Action::ScrollToBottom),
+        KeyBinding::new("T", "Ctrl|Shift", Action::NewTab),
+        KeyBinding::new("W", "Ctrl|Shift", Action::ClosePane),
+        KeyBinding::new("Tab", "Ctrl", Action::NextTab),
+        KeyBinding::new("Tab", "Ctrl|Shift", Action::PreviousTab),
+        KeyBinding::new("PageDown", "Ctrl", Action::NextTab),
+        KeyBinding::new("PageUp", "Ctrl", Action::PreviousTab),
+        KeyBinding::new("PageUp", "Ctrl|Shift", Action::MoveTabLeft),
+        KeyBinding::new("PageDown", "Ctrl|Shift", Action::MoveTabRight),
+        KeyBinding::new("E", "Ctrl|Shift", Action::SplitRight),
+        KeyBinding::new("O", "Ctrl|Shift", Action::SplitDown),
+        KeyBinding::new("Z", "Ctrl|Shift", Action::ToggleZoom),
+        KeyBinding::new("ArrowLeft", "Alt", Action::FocusPaneLeft),
+        KeyBinding::new("ArrowRight", "Alt", Action::FocusPaneRight),
+        KeyBinding::new("ArrowUp", "Alt", Action::FocusPaneUp),
+        KeyBinding::new("ArrowDown", "Alt", Action::FocusPaneDown),
+        KeyBinding::new("B", "Ctrl|Shift", Action::ToggleBroadcast),
+        KeyBinding::new("B", "Ctrl|Alt", Action::BroadcastTab),
+    ]
+}
+
+// Resolved binding table: defaults plus the user's bindings
+pub struct Bindings {
+    bindings: Vec<(egui::Modifiers, egui::Key, Action)>,
+}
+
+impl Bindings {
+    pub fn new(user: &[KeyBinding]) -> Self {
+        let mut bindings: Vec<(egui::Modifiers, egui::Key, Action)> = Vec::new();
+        for binding in default_bindings().iter().chain(user) {
+            let Ok((modifiers, key)) = binding.parse() else {
+                continue; // Rejected when the config was loaded
+            };
+            bindings.retain(|&(m, k, _)| (m, k) != (modifiers, key));
+            if binding.action != Action::None {
+                bindings.push((modifiers, key, binding.action));
+            }
+        }
+        // egui ignores extra Shift/Alt when matching, so Ctrl+Shift+Tab must be
+        // tried before Ctrl+Tab
+        bindings.sort_by_key(|(modifiers, _, _)| {
+            std::cmp::Reverse(
+                [
+                    modifiers.ctrl,
+                    modifiers.shift,
+                    modifiers.alt,
+                    modifiers.command,
+                ]
+                .iter()
+                .filter(|&&held| held)
+                .count(),
+            )
+        });
+        Self { bindings }
+    }
+
+    // Actions whose keys were pressed this frame. The key events are consumed
+    // so they don't also reach the terminal.
+    pub fn take_actions(&self, ctx: &egui::Context) -> Vec<Action> {
+        ctx.input_mut(|i| {
+            self.bindings
+                .iter()
+                .filter(|&&(modifiers, key, _)| i.consume_key(modifiers, key))
+                .map(|&(_, _, action)| action)
+                .collect()
+        })
+    }
+}
@@ -0,0 +1,620 @@
+pub mod bindings;
+pub mod watcher;
+
+use crate::terminal::hints::HintSettings;
+use crate::terminal::links::LinkSettings;
+use crate::terminal::selection::DEFAULT_WORD_SEPARATORS;
+use crate::terminal::state::{TerminalState, MAX_HISTORY_LINES};
+use crate::utils::color::Palette;
+use anyhow::{Context, Result};
+use bindings::KeyBinding;
+use eframe::egui;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// Environment of the child unless [env] says otherwise. LC_ALL and LC_CTYPE
+// are left alone, so a LANG from [env] isn't overridden by them.
+const DEFAULT_ENV: [(&str, &str); 2] = [("TERM", "xterm-256color"), ("LANG", "ko_KR.UTF-8")];
+
+// Built-in font, always available
+pub const BUILTIN_FONT: &str = "D2Coding";
+
+// wterm.toml. Every section is optional; unknown keys are rejected so typos
+// don't go unnoticed.
+#[derive(Clone, Debug, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub shell: ShellConfig,
+    // Added to (or overriding) the environment of the child process
+    pub env: BTreeMap<String, String>,
+    pub font: FontConfig,
+    pub colors: ColorsConfig,
+    pub scrollback: ScrollbackConfig,
+    pub selection: SelectionConfig,
+    pub window: WindowConfig,
+    // Appended to the default bindings; the same chord overrides a default
+    pub keybindings: Vec<KeyBinding>,
+    pub links: LinkSettings,
+    pub hints: HintSettings,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShellConfig {
+    // Defaults to $SHELL, then /bin/sh
+    pub program: Option<String>,
+    // Defaults to a login shell (-l) when `program` is not set
+    pub args: Option<Vec<String>>,
+    pub working_directory: Option<PathBuf>,
+    pub close_on_exit: CloseOnExit,
+}
+
+// What happens to the window when the child process exits
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum CloseOnExit {
+    Always,
+    Never, // Show the exit banner (Enter restarts, Esc closes)
+    #[default]
+    OnSuccess, // Close on exit code 0, otherwise show the banner
+}
+
+impl CloseOnExit {
+    pub fn should_close(self, success: bool) -> bool {
+        match self {
+            CloseOnExit::Always => true,
+            CloseOnExit::Never => false,
+            CloseOnExit::OnSuccess => success,
+        }
+    }
+}
+
+impl ShellConfig {
+    pub fn working_directory(&self) -> Option<PathBuf> {
+        self.working_directory.as_deref().map(expand_home)
+    }
+
+    // Program and arguments to spawn in the PTY
+    pub fn command(&self) -> (String, Vec<String>) {
+        match &self.program {
+            Some(program) => (program.clone(), self.args.clone().unwrap_or_default()),
+            None => {
+                let shell = std::env::var("SHELL")
+                    .ok()
+                    .filter(|shell| !shell.is_empty())
+                    .unwrap_or_else(|| "/bin/sh".to_string());
+                let args = self.args.clone().unwrap_or_else(|| vec!["-l".to_string()]);
+                (shell, args)
+            }
+        }
+    }
+
+    // What to start, in order: the command above, then $SHELL and /bin/sh as
+    // login shells in case it can't be started
+    pub fn commands_to_try(&self) -> Vec<(String, Vec<String>)> {
+        let mut commands = vec![self.command()];
+        let fallbacks = [std::env::var("SHELL").ok(), Some("/bin/sh".to_string())];
+        for shell in fallbacks.into_iter().flatten() {
+            if !shell.is_empty() && !commands.iter().any(|(program, _)| *program == shell) {
+                commands.push((shell, vec!["-l".to_string()]));
+            }
+        }
+        commands
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FontConfig {
+    // "D2Coding" (built in) or a path to a .ttf/.otf file
+    pub family: String,
+    pub size: f32,
+}
+
+impl Default for FontConfig {
+    fn default() -> Self {
+        Self {
+            family: BUILTIN_FONT.to_string(),
+            size: 11.0,
+        }
+    }
+}
+
+impl FontConfig {
+    pub fn font_id(&self) -> egui::FontId {
+        egui::FontId::new(self.size, egui::FontFamily::Monospace)
+    }
+
+    // Font definitions with the configured font first in both families.
+    // Falls back to the built-in font if the file can't be read.
+    pub fn font_definitions(&self) -> egui::FontDefinitions {
+        let mut fonts = egui::FontDefinitions::default();
+
+        // Load D2Coding font from file
+        let d2coding_font_data = include_bytes!("../../assets/fonts/D2Coding.ttf");
+        fonts.font_data.insert(
+            BUILTIN_FONT.to_owned(),
+            std::sync::Arc::new(egui::FontData::from_static(d2coding_font_data)),
+        );
+
+        let mut primary = vec![BUILTIN_FONT.to_owned()];
+        if self.family != BUILTIN_FONT {
+            match std::fs::read(expand_home(Path::new(&self.family))) {
+                Ok(data) => {
+                    fonts.font_data.insert(
+                        self.family.clone(),
+                        std::sync::Arc::new(egui::FontData::from_owned(data)),
+                    );
+                    // Keep D2Coding behind it for Hangul
+                    primary.insert(0, self.family.clone());
+                }
+                Err(e) => eprintln!(
+                    "⚠️ Can't load font {:?} ({}), using {}",
+                    self.family, e, BUILTIN_FONT
+                ),
+            }
+        }
+
+        // Put the terminal fonts first, but keep existing fallbacks
+        for family in [egui::FontFamily::Monospace, egui::FontFamily::Proportional] {
+            let list = fonts.families.entry(family).or_default();
+            for (i, name) in primary.iter().enumerate() {
+                list.insert(i, name.clone());
+            }
+        }
+        fonts
+    }
+}
+
+// "#rrggbb" in the config file
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct HexColor(pub egui::Color32);
+
+impl TryFrom<String> for HexColor {
+    type Error = String;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        let hex = value.strip_prefix('#').unwrap_or(&value);
+        let parsed = (hex.len() == 6)
+            .then(|| u32::from_str_radix(hex, 16).ok())
+            .flatten();
+        match parsed {
+            Some(rgb) => Ok(HexColor(egui::Color32::from_rgb(
+                (rgb >> 16) as u8,
+                (rgb >> 8) as u8,
+                rgb as u8,
+            ))),
+            None => Err(format!("invalid color {:?}, expected \"#rrggbb\"", value)),
+        }
+    }
+}
+
+// The eight colors of one half of the 16-color palette
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnsiColors {
+    pub black: Option<HexColor>,
+    pub red: Option<HexColor>,
+    pub green: Option<HexColor>,
+    pub yellow: Option<HexColor>,
+    pub blue: Option<HexColor>,
+    pub magenta: Option<HexColor>,
+    pub cyan: Option<HexColor>,
+    pub white: Option<HexColor>,
+}
+
+impl AnsiColors {
+    fn apply(&self, colors: &mut [egui::Color32]) {
+        let entries = [
+            self.black,
+            self.red,
+            self.green,
+            self.yellow,
+            self.blue,
+            self.magenta,
+            self.cyan,
+            self.white,
+        ];
+        for (slot, entry) in colors.iter_mut().zip(entries) {
+            if let Some(HexColor(color)) = entry {
+                *slot = color;
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorsConfig {
+    pub foreground: Option<HexColor>,
+    pub background: Option<HexColor>,
+    pub normal: AnsiColors, // Palette 0-7
+    pub bright: AnsiColors, // Palette 8-15
+}
+
+impl ColorsConfig {
+    pub fn palette(&self) -> Palette {
+        let mut palette = Palette::default();
+        if let Some(HexColor(color)) = self.foreground {
+            palette.foreground = color;
+        }
+        if let Some(HexColor(color)) = self.background {
+            palette.background = color;
+        }
+        self.normal.apply(&mut palette.ansi[..8]);
+        self.bright.apply(&mut palette.ansi[8..]);
+        palette
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScrollbackConfig {
+    // Lines kept in the main buffer, screen included. Values below the
+    // screen height act as the screen height: there is just no history.
+    pub lines: usize,
+}
+
+impl Default for ScrollbackConfig {
+    fn default() -> Self {
+        Self {
+            lines: MAX_HISTORY_LINES,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SelectionConfig {
+    // Characters that end a word for double-click selection
+    pub word_separators: String,
+}
+
+impl Default for SelectionConfig {
+    fn default() -> Self {
+        Self {
+            word_separators: DEFAULT_WORD_SEPARATORS.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    pub width: f32,
+    pub height: f32,
+    // Terminal background opacity, 0.0 - 1.0
+    pub opacity: f32,
+    // Native title bar instead of the built-in macOS-style one
+    pub decorations: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            width: 1024.0,
+            height: 768.0,
+            opacity: 0.7,
+            decorations: false,
+        }
+    }
+}
+
+impl Config {
+    // Settings in `new` that differ from `self` but only take effect after a
+    // restart: the child process and the native window are created once
+    pub fn restart_only_changes(&self, new: &Config) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        let command = |shell: &ShellConfig| {
+            (
+                shell.program.clone(),
+                shell.args.clone(),
+                shell.working_directory.clone(),
+            )
+        };
+        if command(&self.shell) != command(&new.shell) {
+            changed.push("shell");
+        }
+        if self.env != new.env {
+            changed.push("env");
+        }
+        if (self.window.width, self.window.height) != (new.window.width, new.window.height) {
+            changed.push("window.width/height");
+        }
+        if self.window.decorations != new.window.decorations {
+            changed.push("window.decorations");
+        }
+        changed
+    }
+
+    // Carry the restart-only settings of the running session over to `new`,
+    // so the session keeps matching what was actually started
+    pub fn keep_restart_only(&self, new: &mut Config) {
+        new.shell.program = self.shell.program.clone();
+        new.shell.args = self.shell.args.clone();
+        new.shell.working_directory = self.shell.working_directory.clone();
+        new.env = self.env.clone();
+        new.window.width = self.window.width;
+        new.window.height = self.window.height;
+        new.window.decorations = self.window.decorations;
+    }
+
+    // Variables to set for the child: the defaults, then [env]
+    pub fn child_env(&self) -> BTreeMap<String, String> {
+        let mut env: BTreeMap<String, String> = DEFAULT_ENV
+            .iter()
+            .map(|&(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        env.extend(self.env.clone());
+        env
+    }
+
+    // The settings that live in each session's TerminalState; applied when
+    // a session is spawned and again on reload
+    pub fn apply_to_state(&self, state: &mut TerminalState) {
+        state.history_lines = self.scrollback.lines;
+        state.word_separators = self.selection.word_separators.clone();
+    }
+
+    // $XDG_CONFIG_HOME/wterm/wterm.toml, or ~/.config/wterm/wterm.toml
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(config_home.join("wterm").join("wterm.toml"))
+    }
+
+    // Load the config file. A missing default file means defaults; a missing
+    // explicit (--config) file is an error.
+    pub fn load(explicit: Option<&Path>) -> Result<(Self, Option<PathBuf>)> {
+        let path = match explicit {
+            Some(path) => path.to_path_buf(),
+            None => match Self::default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok((Self::default(), None)),
+            },
+        };
+
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("can't read config file {}", path.display()))?;
+        let config = Self::parse(&text)
+            .with_context(|| format!("invalid config file {}", path.display()))?;
+        Ok((config, Some(path)))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let config: Config = toml::from_str(text)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    // Checks serde can't express
+    fn validate(&self) -> Result<()> {
+        if !(self.font.size > 0.0 && self.font.size <= 200.0) {
+            anyhow::bail!(
+                "font.size must be between 0 and 200, got {}",
+                self.font.size
+            );
+        }
+        if !(0.0..=1.0).contains(&self.window.opacity) {
+            anyhow::bail!(
+                "window.opacity must be between 0.0 and 1.0, got {}",
+                self.window.opacity
+            );
+        }
+        if self.window.width < 100.0 || self.window.height < 100.0 {
+            anyhow::bail!("window.width and window.height must be at least 100");
+        }
+        if self.scrollback.lines == 0 {
+            anyhow::bail!("scrollback.lines must be at least 1");
+        }
+        for binding in &self.keybindings {
+            binding.validate()?;
+        }
+        self.links.validate()?;
+        self.hints.validate()?;
+        Ok(())
+    }
+}
+
+// Leading `~` to $HOME
+pub fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(text: &str) -> String {
+        format!("{:#}", Config::parse(text).unwrap_err())
+    }
+
+    #[test]
+    fn empty_file_is_the_default() {
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn every_section_parses() {
+        let config = Config::parse(
+            r##"
+            [shell]
+            program = "/bin/bash"
+            args = ["-l"]
+            working_directory = "/tmp"
+            close_on_exit = "Never"
+
+            [env]
+            EDITOR = "vim"
+
+            [font]
+            size = 13.5
+
+            [colors]
+            foreground = "#ffffff"
+            background = "000000"
+            [colors.normal]
+            red = "#c23621"
+            [colors.bright]
+            red = "#FC391F"
+
+            [scrollback]
+            lines = 5000
+
+            [selection]
+            word_separators = " /"
+
+            [window]
+            width = 800
+            height = 600
+            opacity = 1.0
+            decorations = true
+
+            [[keybindings]]
+            key = "N"
+            mods = "Ctrl+Shift"
+            action = "NewTab"
+
+            [links]
+            editor_command = "code --goto {file}:{line}:{column}"
+
+            [hints]
+            alphabet = "asdf"
+            [[hints.patterns]]
+            name = "ticket"
+            regex = "JIRA-\\d+"
+            action = "Paste"
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.shell.command(),
+            ("/bin/bash".to_string(), vec!["-l".to_string()])
+        );
+        assert_eq!(config.shell.close_on_exit, CloseOnExit::Never);
+        assert_eq!(config.env["EDITOR"], "vim");
+        assert_eq!(config.font.size, 13.5);
+        assert_eq!(config.font.family, BUILTIN_FONT); // Unset keys keep their default
+        assert_eq!(config.scrollback.lines, 5000);
+        assert_eq!(config.selection.word_separators, " /");
+        assert!(config.window.decorations);
+        assert_eq!(config.keybindings.len(), 1);
+        assert_eq!(config.hints.patterns.len(), 1);
+        assert_eq!(config.hints.patterns[0].name, "ticket");
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected_with_what_was_expected() {
+        let error = parse_error("[font]\nsiz = 12");
+        assert!(error.contains("unknown field `siz`"), "{}", error);
+        assert!(error.contains("expected `family` or `size`"), "{}", error);
+
+        let error = parse_error("[windows]\nwidth = 800");
+        assert!(error.contains("unknown field `windows`"), "{}", error);
+
+        let error = parse_error("[shell]\nclose_on_exit = \"Sometimes\"");
+        assert!(error.contains("unknown variant `Sometimes`"), "{}", error);
+    }
+
+    #[test]
+    fn colors_are_hex_triplets() {
+        let config = Config::parse(
+            "[colors]\nforeground = \"#ff8000\"\n[colors.normal]\nblue = \"0000ff\"\n[colors.bright]\nblack = \"#808080\"",
+        )
+        .unwrap();
+        let palette = config.colors.palette();
+        assert_eq!(palette.foreground, egui::Color32::from_rgb(255, 128, 0));
+        assert_eq!(palette.ansi[4], egui::Color32::from_rgb(0, 0, 255));
+        assert_eq!(palette.ansi[8], egui::Color32::from_rgb(128, 128, 128));
+        // Unset colors keep the default palette
+        assert_eq!(palette.ansi[1], Palette::default().ansi[1]);
+
+        for bad in ["#fff", "#12345g", "red", "#ff80000"] {
+            let error = parse_error(&format!("[colors]\nbackground = \"{}\"", bad));
+            assert!(
+                error.contains(&format!("invalid color {:?}, expected \"#rrggbb\"", bad)),
+                "{}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn out_of_range_values_are_rejected() {
+        assert!(parse_error("[font]\nsize = 0").contains("font.size"));
+        assert!(parse_error("[window]\nopacity = 1.5").contains("window.opacity"));
+        assert!(parse_error("[scrollback]\nlines = 0").contains("scrollback.lines"));
+        assert!(
+            parse_error("[[keybindings]]\nkey = \"Nope\"\naction = \"NewTab\"")
+                .contains("unknown key \"Nope\"")
+        );
+        assert!(parse_error("[links]\nurl_pattern = \"(\"").contains("links.url_pattern"));
+        assert!(
+            parse_error("[[hints.patterns]]\nname = \"x\"\nregex = \"[\"\naction = \"Copy\"")
+                .contains("hints.patterns \"x\"")
+        );
+    }
+
+    #[test]
+    fn shell_fallbacks_end_with_bin_sh() {
+        let shell = ShellConfig {
+            program: Some("/no/such/shell".to_string()),
+            ..ShellConfig::default()
+        };
+        let commands = shell.commands_to_try();
+        assert_eq!(commands[0], ("/no/such/shell".to_string(), Vec::new()));
+        assert_eq!(
+            commands.last().unwrap(),
+            &("/bin/sh".to_string(), vec!["-l".to_string()])
+        );
+        for (i, (program, _)) in commands.iter().enumerate() {
+            assert!(!commands[i + 1..].iter().any(|(other, _)| other == program));
+        }
+
+        // Nothing to fall back to from /bin/sh itself but $SHELL
+        let shell = ShellConfig {
+            program: Some("/bin/sh".to_string()),
+            ..ShellConfig::default()
+        };
+        assert!(shell.commands_to_try().len() <= 2);
+    }
+
+    #[test]
+    fn session_settings_are_applied_to_the_state() {
+        let config =
+            Config::parse("[scrollback]\nlines = 500\n[selection]\nword_separators = \" =\"")
+                .unwrap();
+        let mut state = TerminalState::new(24, 80);
+        assert_eq!(state.word_separators, DEFAULT_WORD_SEPARATORS);
+
+        config.apply_to_state(&mut state);
+        assert_eq!(state.history_lines, 500);
+        assert_eq!(state.word_separators, " =");
+    }
+
+    #[test]
+    fn restart_only_settings_are_reported_and_kept() {
+        let running = Config::default();
+        let mut new = Config::parse(
+            "[shell]\nprogram = \"/bin/bash\"\n[env]\nA = \"1\"\n[font]\nsize = 20\n[window]\nwidth = 500",
+        )
+        .unwrap();
+        assert_eq!(
+            running.restart_only_changes(&new),
+            ["shell", "env", "window.width/height"]
+        );
+
+        running.keep_restart_only(&mut new);
+        assert_eq!(new.shell.program, None);
+        assert!(new.env.is_empty());
+        assert_eq!(new.window.width, running.window.width);
+        assert_eq!(new.font.size, 20.0); // Applied live
+    }
+}
@@ -0,0 +1,164 @@
+use super::Config;
+use anyhow::Result;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+// How often the config file's mtime is checked
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Watches the config file by polling its modification time on a background
+// thread, so edits can be applied to the running session. The UI is only
+// woken when the file changed. The file doesn't have to exist yet.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    reloaded: Receiver<Result<Config>>,
+    stopped: Arc<AtomicBool>, // Set on drop; the thread exits at its next check
+}
+
+impl ConfigWatcher {
+    pub fn new(path: PathBuf, egui_ctx: egui::Context) -> Self {
+        Self::spawn(path, POLL_INTERVAL, move || egui_ctx.request_repaint())
+    }
+
+    // Check every `interval`, calling `wake` after loading a changed file. The
+    // thread stops at the first check after the watcher is dropped.
+    fn spawn(path: PathBuf, interval: Duration, wake: impl Fn() + Send + 'static) -> Self {
+        let (sender, reloaded) = mpsc::channel();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let watched = path.clone();
+        let stop = stopped.clone();
+        thread::spawn(move || {
+            let mut modified = modified_time(&watched);
+            loop {
+                thread::sleep(interval);
+                if stop.load(Ordering::Acquire) {
+                    return;
+                }
+                let now = modified_time(&watched);
+                if now == modified {
+                    continue;
+                }
+                modified = now;
+                // A deleted file is ignored; the current settings stay in effect
+                if modified.is_none() {
+                    continue;
+                }
+                let config = Config::load(Some(&watched)).map(|(config, _)| config);
+                if sender.send(config).is_err() {
+                    return;
+                }
+                wake();
+            }
+        });
+        Self {
+            path,
+            reloaded,
+            stopped,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // The reloaded config if the file changed since the last call
+    pub fn poll(&mut self) -> Option<Result<Config>> {
+        self.reloaded.try_iter().last()
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Release);
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Instant;
+
+    const INTERVAL: Duration = Duration::from_millis(10);
+
+    // Write `text` with an mtime `age` in the past, so each write is seen as a change
+    fn write(path: &Path, text: &str, age: u64) {
+        std::fs::write(path, text).unwrap();
+        let file = std::fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(age))
+            .unwrap();
+    }
+
+    fn wait_for(watcher: &mut ConfigWatcher) -> Result<Config> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some(config) = watcher.poll() {
+                return config;
+            }
+            assert!(Instant::now() < deadline, "no reload");
+            thread::sleep(INTERVAL);
+        }
+    }
+
+    #[test]
+    fn changes_are_loaded_and_wake_the_ui() {
+        let dir = std::env::temp_dir().join(format!("wterm-watcher-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("wterm.toml");
+        write(&path, "[font]\nsize = 12", 30);
+
+        let wakes = Arc::new(AtomicUsize::new(0));
+        let counter = wakes.clone();
+        let mut watcher = ConfigWatcher::spawn(path.clone(), INTERVAL, move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        // Nothing changed yet
+        thread::sleep(INTERVAL * 5);
+        assert!(watcher.poll().is_none());
+        assert_eq!(wakes.load(Ordering::SeqCst), 0);
+
+        write(&path, "[font]\nsize = 20", 20);
+        assert_eq!(wait_for(&mut watcher).unwrap().font.size, 20.0);
+        // Woken right after the config was sent
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while wakes.load(Ordering::SeqCst) == 0 {
+            assert!(Instant::now() < deadline, "not woken");
+            thread::sleep(INTERVAL);
+        }
+        assert_eq!(wakes.load(Ordering::SeqCst), 1);
+
+        write(&path, "[font]\nsiz = 20", 10);
+        let error = format!("{:#}", wait_for(&mut watcher).unwrap_err());
+        assert!(error.contains("unknown field `siz`"), "{}", error);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn the_thread_stops_when_the_watcher_is_dropped() {
+        let path = std::env::temp_dir().join("wterm-watcher-never-written.toml");
+        // The thread owns `wake`, and with it a reference to `alive`
+        let alive = Arc::new(());
+        let held = alive.clone();
+        let watcher = ConfigWatcher::spawn(path, INTERVAL, move || drop(held.clone()));
+        assert_eq!(Arc::strong_count(&alive), 2);
+
+        drop(watcher);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Arc::strong_count(&alive) > 1 {
+            assert!(Instant::now() < deadline, "thread still running");
+            thread::sleep(INTERVAL);
+        }
+    }
+}
@@ -0,0 +1,374 @@
+use eframe::egui;
+
+// Gap between two panes; the divider is drawn and dragged there
+pub const DIVIDER_WIDTH: f32 = 6.0;
+// Neither side of a split shrinks below this share of it
+const MIN_RATIO: f32 = 0.1;
+
+// How a split arranges its two panes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitAxis {
+    LeftRight, // Side by side, vertical divider
+    TopBottom, // Stacked, horizontal divider
+}
+
+// Focus movement between panes (Alt+arrows)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+// Binary tree of the panes in a tab; every leaf is a session id
+#[derive(Clone, Debug, PartialEq)]
+pub enum PaneLayout {
+    Pane(u64),
+    Split {
+        axis: SplitAxis,
+        ratio: f32, // Share of the first child
+        first: Box<PaneLayout>,
+        second: Box<PaneLayout>,
+    },
+}
+
+// Divider between the two halves of a split, as laid out this frame
+pub struct Divider {
+    pub path: Vec<bool>, // From the root: false = first child, true = second
+    pub axis: SplitAxis,
+    pub rect: egui::Rect, // Grab area between the panes
+    pub area: egui::Rect, // The whole split
+}
+
+impl PaneLayout {
+    // Session ids, left/top first
+    pub fn panes(&self) -> Vec<u64> {
+        match self {
+            PaneLayout::Pane(id) => vec![*id],
+            PaneLayout::Split { first, second, .. } => {
+                let mut panes = first.panes();
+                panes.extend(second.panes());
+                panes
+            }
+        }
+    }
+
+    pub fn contains(&self, id: u64) -> bool {
+        match self {
+            PaneLayout::Pane(pane) => *pane == id,
+            PaneLayout::Split { first, second, .. } => first.contains(id) || second.contains(id),
+        }
+    }
+
+    // Split pane `target` in half, `new` going right of / below it
+    pub fn split(&mut self, target: u64, new: u64, axis: SplitAxis) -> bool {
+        match self {
+            PaneLayout::Pane(id) if *id == target => {
+                *self = PaneLayout::Split {
+                    axis,
+                    ratio: 0.5,
+                    first: Box::new(PaneLayout::Pane(target)),
+                    second: Box::new(PaneLayout::Pane(new)),
+                };
+                true
+            }
+            PaneLayout::Pane(_) => false,
+            PaneLayout::Split { first, second, .. } => {
+                first.split(target, new, axis) || second.split(target, new, axis)
+            }
+        }
+    }
+
+    // Take pane `id` out; its sibling gets the space. A lone pane can't be
+    // removed, the tab has to be closed instead.
+    pub fn remove(&mut self, id: u64) -> bool {
+        let sibling = match self {
+            PaneLayout::Pane(_) => return false,
+            PaneLayout::Split { first, second, .. } => {
+                if **first == PaneLayout::Pane(id) {
+                    std::mem::replace(second.as_mut(), PaneLayout::Pane(id))
+                } else if **second == PaneLayout::Pane(id) {
+                    std::mem::replace(first.as_mut(), PaneLayout::Pane(id))
+                } else {
+                    return first.remove(id) || second.remove(id);
+                }
+            }
+        };
+        *self = sibling;
+        true
+    }
+
+    // Rects of the panes and dividers when the layout fills `rect`
+    pub fn layout(
+        &self,
+        rect: egui::Rect,
+        panes: &mut Vec<(u64, egui::Rect)>,
+        dividers: &mut Vec<Divider>,
+    ) {
+        self.layout_at(rect, &mut Vec::new(), panes, dividers);
+    }
+
+    fn layout_at(
+        &self,
+        rect: egui::Rect,
+        path: &mut Vec<bool>,
+        panes: &mut Vec<(u64, egui::Rect)>,
+        dividers: &mut Vec<Divider>,
+    ) {
+        match self {
+            PaneLayout::Pane(id) => panes.push((*id, rect)),
+            PaneLayout::Split {
+                axis,
+                ratio,
+                first,
+                second,
+            } => {
+                let (first_rect, divider, second_rect) = split_rect(rect, *axis, *ratio);
+                dividers.push(Divider {
+                    path: path.clone(),
+                    axis: *axis,
+                    rect: divider,
+                    area: rect,
+                });
+                path.push(false);
+                first.layout_at(first_rect, path, panes, dividers);
+                path.pop();
+                path.push(true);
+                second.layout_at(second_rect, path, panes, dividers);
+                path.pop();
+            }
+        }
+    }
+
+    // Move the divider of the split at `path`
+    pub fn set_ratio(&mut self, path: &[bool], new_ratio: f32) {
+        let PaneLayout::Split {
+            ratio,
+            first,
+            second,
+            ..
+        } = self
+        else {
+            return;
+        };
+        match path.split_first() {
+            None => *ratio = new_ratio.clamp(MIN_RATIO, 1.0 - MIN_RATIO),
+            Some((false, rest)) => first.set_ratio(rest, new_ratio),
+            Some((true, rest)) => second.set_ratio(rest, new_ratio),
+        }
+    }
+}
+
+// Cut `rect` into first pane, divider and second pane
+fn split_rect(
+    rect: egui::Rect,
+    axis: SplitAxis,
+    ratio: f32,
+) -> (egui::Rect, egui::Rect, egui::Rect) {
+    match axis {
+        SplitAxis::LeftRight => {
+            let x = rect.left() + ((rect.width() - DIVIDER_WIDTH) * ratio).max(0.0);
+            (
+                egui::Rect::from_min_max(rect.min, egui::pos2(x, rect.bottom())),
+                egui::Rect::from_min_max(
+                    egui::pos2(x, rect.top()),
+                    egui::pos2(x + DIVIDER_WIDTH, rect.bottom()),
+                ),
+                egui::Rect::from_min_max(egui::pos2(x + DIVIDER_WIDTH, rect.top()), rect.max),
+            )
+        }
+        SplitAxis::TopBottom => {
+            let y = rect.top() + ((rect.height() - DIVIDER_WIDTH) * ratio).max(0.0);
+            (
+                egui::Rect::from_min_max(rect.min, egui::pos2(rect.right(), y)),
+                egui::Rect::from_min_max(
+                    egui::pos2(rect.left(), y),
+                    egui::pos2(rect.right(), y + DIVIDER_WIDTH),
+                ),
+                egui::Rect::from_min_max(egui::pos2(rect.left(), y + DIVIDER_WIDTH), rect.max),
+            )
+        }
+    }
+}
+
+// The pane next to `from` in `direction`: the closest one that lines up with
+// it, preferring the one sharing the longest edge
+pub fn neighbor(panes: &[(u64, egui::Rect)], from: u64, direction: Direction) -> Option<u64> {
+    let (_, origin) = panes.iter().find(|(id, _)| *id == from)?;
+    panes
+        .iter()
+        .filter(|(id, _)| *id != from)
+        .filter_map(|(id, rect)| {
+            let (gap, overlap) = match direction {
+                Direction::Left => (
+                    origin.left() - rect.right(),
+                    origin.y_range().intersection(rect.y_range()),
+                ),
+                Direction::Right => (
+                    rect.left() - origin.right(),
+                    origin.y_range().intersection(rect.y_range()),
+                ),
+                Direction::Up => (
+                    origin.top() - rect.bottom(),
+                    origin.x_range().intersection(rect.x_range()),
+                ),
+                Direction::Down => (
+                    rect.top() - origin.bottom(),
+                    origin.x_range().intersection(rect.x_range()),
+                ),
+            };
+            let overlap = overlap.span();
+            (gap >= -1.0 && overlap > 0.0).then_some((gap, overlap, *id))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0).then(b.1.total_cmp(&a.1)))
+        .map(|(_, _, id)| id)
+}
+
+// A tab: its panes and which one has the keyboard
+pub struct Tab {
+    pub id: u64,
+    pub layout: PaneLayout,
+    pub focus: u64,           // Session id of the focused pane
+    pub zoomed: bool,         // Focused pane fills the tab
+    pub name: Option<String>, // Set by renaming the tab; wins over the OSC title
+}
+
+impl Tab {
+    pub fn new(id: u64, session: u64) -> Self {
+        Self {
+            id,
+            layout: PaneLayout::Pane(session),
+            focus: session,
+            zoomed: false,
+            name: None,
+        }
+    }
+
+    // Panes on screen while the tab is active, and where
+    pub fn visible_panes(&self, rect: egui::Rect) -> (Vec<(u64, egui::Rect)>, Vec<Divider>) {
+        let mut panes = Vec::new();
+        let mut dividers = Vec::new();
+        if self.zoomed {
+            panes.push((self.focus, rect));
+        } else {
+            self.layout.layout(rect, &mut panes, &mut dividers);
+        }
+        (panes, dividers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen() -> egui::Rect {
+        egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(806.0, 606.0))
+    }
+
+    fn rects(layout: &PaneLayout) -> Vec<(u64, egui::Rect)> {
+        let mut panes = Vec::new();
+        layout.layout(screen(), &mut panes, &mut Vec::new());
+        panes
+    }
+
+    // ┌───┬───┐
+    // │   │ 2 │
+    // │ 1 ├─┬─┤
+    // │   │3│4│
+    // └───┴─┴─┘
+    fn nested() -> PaneLayout {
+        let mut layout = PaneLayout::Pane(1);
+        assert!(layout.split(1, 2, SplitAxis::LeftRight));
+        assert!(layout.split(2, 3, SplitAxis::TopBottom));
+        assert!(layout.split(3, 4, SplitAxis::LeftRight));
+        layout
+    }
+
+    #[test]
+    fn split_puts_the_new_pane_after_the_target() {
+        let layout = nested();
+        assert_eq!(layout.panes(), [1, 2, 3, 4]);
+        assert!(!layout.clone().split(9, 10, SplitAxis::TopBottom));
+    }
+
+    #[test]
+    fn removing_a_pane_collapses_its_split() {
+        let mut layout = nested();
+        assert!(layout.remove(4));
+        assert!(layout.remove(2));
+        // 3 took the place of the split it was left alone in
+        let mut expected = PaneLayout::Pane(1);
+        expected.split(1, 3, SplitAxis::LeftRight);
+        assert_eq!(layout, expected);
+
+        assert!(!layout.remove(9));
+        assert!(layout.remove(1));
+        assert_eq!(layout, PaneLayout::Pane(3));
+        // The last pane stays
+        assert!(!layout.remove(3));
+    }
+
+    #[test]
+    fn set_ratio_clamps_and_follows_the_path() {
+        let mut layout = nested();
+        layout.set_ratio(&[], 0.0);
+        layout.set_ratio(&[true], 2.0);
+        layout.set_ratio(&[true, true], 0.25);
+        layout.set_ratio(&[false], 0.5); // Pane 1 isn't split
+
+        let PaneLayout::Split { ratio, second, .. } = &layout else {
+            panic!("not a split");
+        };
+        assert_eq!(*ratio, MIN_RATIO);
+        let PaneLayout::Split { ratio, second, .. } = second.as_ref() else {
+            panic!("not a split");
+        };
+        assert_eq!(*ratio, 1.0 - MIN_RATIO);
+        let PaneLayout::Split { ratio, .. } = second.as_ref() else {
+            panic!("not a split");
+        };
+        assert_eq!(*ratio, 0.25);
+    }
+
+    #[test]
+    fn layout_tiles_the_rect_without_overlap() {
+        let mut layout = nested();
+        layout.set_ratio(&[], 0.3);
+        let mut panes = Vec::new();
+        let mut dividers = Vec::new();
+        layout.layout(screen(), &mut panes, &mut dividers);
+
+        assert_eq!(panes.len(), 4);
+        assert_eq!(dividers.len(), 3);
+        let mut rects: Vec<egui::Rect> = panes.iter().map(|(_, rect)| *rect).collect();
+        rects.extend(dividers.iter().map(|divider| divider.rect));
+        for (i, a) in rects.iter().enumerate() {
+            assert!(screen().contains_rect(*a), "{:?}", a);
+            for b in &rects[i + 1..] {
+                assert!(a.intersect(*b).area() <= 0.0, "{:?} overlaps {:?}", a, b);
+            }
+        }
+        // Together they cover it
+        let area: f32 = rects.iter().map(|rect| rect.area()).sum();
+        assert!((area - screen().area()).abs() < 0.01);
+
+        assert_eq!(dividers[2].path, [true, true]);
+        assert_eq!(dividers[2].axis, SplitAxis::LeftRight);
+    }
+
+    #[test]
+    fn neighbor_finds_the_adjacent_pane_in_nested_splits() {
+        let panes = rects(&nested());
+        assert_eq!(neighbor(&panes, 1, Direction::Right), Some(2)); // Longest shared edge
+        assert_eq!(neighbor(&panes, 1, Direction::Left), None);
+        assert_eq!(neighbor(&panes, 2, Direction::Left), Some(1));
+        assert_eq!(neighbor(&panes, 2, Direction::Down), Some(3));
+        assert_eq!(neighbor(&panes, 3, Direction::Left), Some(1));
+        assert_eq!(neighbor(&panes, 3, Direction::Right), Some(4));
+        assert_eq!(neighbor(&panes, 3, Direction::Up), Some(2));
+        assert_eq!(neighbor(&panes, 4, Direction::Up), Some(2));
+        assert_eq!(neighbor(&panes, 4, Direction::Down), None);
+        assert_eq!(neighbor(&panes, 9, Direction::Up), None);
+    }
+}
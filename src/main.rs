@@ -1,11 +1,18 @@
 use eframe::egui;
 
 mod app;
+mod cli;
+mod config;
 mod ime;
+mod layout;
+mod pty;
+mod session;
 mod terminal;
 mod utils;
 
 use app::TerminalApp;
+use cli::Cli;
+use config::Config;
 
 // macOS 전용 둥근 창 설정
 #[cfg(target_os = "macos")]
@@ -36,21 +43,42 @@ fn round_window_corners(ns_view: *mut std::os::raw::c_void) {
 }
 
 fn main() {
+    let cli = Cli::parse();
+    let (config, config_path, config_error) = match Config::load(cli.config.as_deref()) {
+        Ok((config, path)) => (config, path, None),
+        Err(e) => {
+            // Keep the terminal usable; the message says what to fix
+            eprintln!("❌ {:#}", e);
+            eprintln!("⚠️ Using the default configuration");
+            (Config::default(), None, Some(e))
+        }
+    };
+    if let Some(path) = &config_path {
+        println!("⚙️ Loaded config from {}", path.display());
+    }
+    // Watched for live reload, even if it doesn't exist or failed to load yet
+    let watch_path = cli.config.clone().or_else(Config::default_path);
+
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size([config.window.width, config.window.height])
+        .with_decorations(config.window.decorations)
+        .with_resizable(true) // Make window resizable
+        .with_transparent(true) // Enable transparency
+        .with_window_level(egui::WindowLevel::Normal)
+        .with_title(cli.title.as_deref().unwrap_or(app::DEFAULT_WINDOW_TITLE)); // Window title
+    if let Some(class) = &cli.class {
+        // WM_CLASS on X11, app_id on Wayland
+        viewport = viewport.with_app_id(class);
+    }
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([1024.0, 768.0])
-            .with_decorations(false)
-            .with_resizable(true) // Make window resizable
-            .with_transparent(true) // Enable transparency
-            .with_window_level(egui::WindowLevel::Normal)
-            .with_title("WTerm - 터미널"), // Window title
+        viewport,
         ..Default::default()
     };
 
-    let _result = eframe::run_native(
+    let result = eframe::run_native(
         "WTerm",
         options,
-        Box::new(|cc| {
+        Box::new(move |cc| {
             // macOS에서 윈도우를 둥글게 만들기
             #[cfg(target_os = "macos")]
             {
@@ -67,9 +95,13 @@ fn main() {
                 }
             }
 
-            Ok(Box::new(
-                TerminalApp::new(cc).expect("Failed to create terminal app"),
-            ))
+            // Nothing could be started: eframe reports the error and exits
+            let app = TerminalApp::new(cc, config, config_error, cli, watch_path)?;
+            Ok(Box::new(app))
         }),
     );
+    if let Err(e) = result {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,379 @@
+use anyhow::Result;
+use eframe::egui;
+use portable_pty::{CommandBuilder, MasterPty, PtySize};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use wterm_core::Terminal;
+
+use crate::terminal::state::TerminalState;
+use crate::terminal::{PtyWriter, RepaintListener};
+
+// How long the exit waits for the reader to reach the end of the output
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_millis(200);
+
+// How the child process ended
+#[derive(Clone, Debug)]
+pub struct ChildExit {
+    pub code: u32,
+    pub signal: Option<String>, // e.g. "Killed", when terminated by a signal
+    pub start_error: Option<String>, // The child never ran
+}
+
+impl ChildExit {
+    // `program` could not be started: "command not found: foo" when it isn't
+    // on PATH, otherwise the first line of the spawn error
+    pub fn start_failed(program: &str, error: &anyhow::Error) -> Self {
+        let reason = if program_exists(program) {
+            let error = format!("{:#}", error);
+            let first_line = error.lines().next().unwrap_or_default();
+            format!("failed to start {}: {}", program, first_line)
+        } else {
+            format!("command not found: {}", program)
+        };
+        Self {
+            code: 127,
+            signal: None,
+            start_error: Some(reason),
+        }
+    }
+
+    pub fn success(&self) -> bool {
+        self.start_error.is_none() && self.signal.is_none() && self.code == 0
+    }
+
+    // "[process exited with code 1]"
+    pub fn describe(&self) -> String {
+        match (&self.start_error, &self.signal) {
+            (Some(reason), _) => format!("[{}]", reason),
+            (None, Some(signal)) => format!("[process killed by signal: {}]", signal),
+            (None, None) => format!("[process exited with code {}]", self.code),
+        }
+    }
+}
+
+// A path that exists, or a name found in one of the PATH directories
+fn program_exists(program: &str) -> bool {
+    if program.contains('/') {
+        return std::path::Path::new(program).exists();
+    }
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+impl From<portable_pty::ExitStatus> for ChildExit {
+    fn from(status: portable_pty::ExitStatus) -> Self {
+        Self {
+            code: status.exit_code(),
+            signal: status.signal().map(str::to_string),
+            start_error: None,
+        }
+    }
+}
+
+// A child process running on its own PTY
+pub struct PtyProcess {
+    pub writer: PtyWriter,
+    pub master: Box<dyn MasterPty + Send>,
+    pub pid: Option<u32>, // The shell (or -e command) itself
+    // Set to detach the process from the state: its reader stops applying
+    // output, e.g. from a background job still holding the PTY after a restart
+    pub detached: Arc<AtomicBool>,
+}
+
+// Open a PTY of `size`, start `cmd` on it and a reader thread feeding `state`.
+// A second thread waits for the child and stores how it ended in `exit`.
+pub fn spawn(
+    cmd: CommandBuilder,
+    size: PtySize,
+    state: Arc<Mutex<TerminalState>>,
+    egui_ctx: egui::Context,
+    exit: Arc<Mutex<Option<ChildExit>>>,
+) -> Result<PtyProcess> {
+    let pty_system = portable_pty::native_pty_system();
+    let pty_pair = pty_system.openpty(size)?;
+    let mut child = pty_pair.slave.spawn_command(cmd)?;
+    let pid = child.process_id();
+
+    let mut pty_reader = pty_pair.master.try_clone_reader()?;
+    let writer: PtyWriter = Arc::new(Mutex::new(pty_pair.master.take_writer()?));
+
+    // Spawn background thread to read from PTY
+    let reply_writer = writer.clone();
+    let repaint_ctx = egui_ctx.clone();
+    let (output_done, output_ended) = mpsc::channel::<()>();
+    let detached = Arc::new(AtomicBool::new(false));
+    let reader_detached = detached.clone();
+    let waiter_detached = detached.clone();
+    thread::spawn(move || {
+        let listener = Arc::new(RepaintListener {
+            ctx: repaint_ctx,
+            pty_writer: Some(reply_writer),
+        });
+        let mut terminal = Terminal::from_state(state, listener);
+
+        let mut buffer = [0u8; 16 * 1024]; // Whole chunks are parsed under one lock
+        loop {
+            match pty_reader.read(&mut buffer) {
+                Ok(0) => break, // EOF
+                Ok(_) if reader_detached.load(Ordering::Acquire) => break,
+                Ok(n) => {
+                    let read_data = &buffer[..n];
+
+                    /*    println!(
+                        "🚽 PTY Read ({} bytes): string: \"{}\"",
+                        n,
+                        String::from_utf8_lossy(read_data).escape_debug()
+                    );*/
+
+                    terminal.advance(read_data);
+                }
+                Err(_) => break, // EIO once the child is gone (Linux)
+            }
+        }
+        drop(output_done);
+    });
+
+    // Wait for the child on its own: a background job it started may keep
+    // the PTY open, so the output doesn't have to end when it exits
+    thread::spawn(move || {
+        let status = match child.wait() {
+            Ok(status) => ChildExit::from(status),
+            Err(e) => {
+                eprintln!("⚠️ Failed to wait for the child process: {}", e);
+                ChildExit {
+                    code: 1,
+                    signal: None,
+                    start_error: None,
+                }
+            }
+        };
+        // Let the reader parse the child's last output before the banner
+        let _ = output_ended.recv_timeout(OUTPUT_DRAIN_TIMEOUT);
+        if waiter_detached.load(Ordering::Acquire) {
+            return;
+        }
+        if let Ok(mut exit) = exit.lock() {
+            *exit = Some(status);
+        }
+        egui_ctx.request_repaint();
+    });
+
+    Ok(PtyProcess {
+        writer,
+        master: pty_pair.master,
+        pid,
+        detached,
+    })
+}
+
+// A PTY of `size` with nothing running on it, for a command that couldn't
+// be started; what is typed into it goes nowhere
+pub fn open(size: PtySize) -> Result<PtyProcess> {
+    let pty_pair = portable_pty::native_pty_system().openpty(size)?;
+    let writer: PtyWriter = Arc::new(Mutex::new(pty_pair.master.take_writer()?));
+    Ok(PtyProcess {
+        writer,
+        master: pty_pair.master,
+        pid: None,
+        detached: Arc::new(AtomicBool::new(false)),
+    })
+}
+
+// Names of the programs in the PTY's foreground process group (tcgetpgrp on
+// the master), or nothing when the shell itself is in the foreground
+#[cfg(unix)]
+pub fn foreground_programs(master: &dyn MasterPty, shell_pid: Option<u32>) -> Vec<String> {
+    let Some(leader) = master
+        .process_group_leader()
+        .and_then(|pgid| u32::try_from(pgid).ok())
+    else {
+        return Vec::new();
+    };
+    if Some(leader) == shell_pid {
+        return Vec::new();
+    }
+    let names = process_group_names(leader);
+    if names.is_empty() {
+        vec![format!("pid {}", leader)]
+    } else {
+        names
+    }
+}
+
+#[cfg(not(unix))]
+pub fn foreground_programs(_master: &dyn MasterPty, _shell_pid: Option<u32>) -> Vec<String> {
+    Vec::new()
+}
+
+// Commands of every process in group `pgid` (a pipeline has several)
+#[cfg(target_os = "linux")]
+fn process_group_names(pgid: u32) -> Vec<String> {
+    let mut names = Vec::new();
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return names;
+    };
+    for entry in entries.flatten() {
+        let Ok(stat) = std::fs::read_to_string(entry.path().join("stat")) else {
+            continue;
+        };
+        // "pid (comm) state ppid pgrp ..."; comm may itself contain ')'
+        let (Some(open), Some(close)) = (stat.find('('), stat.rfind(')')) else {
+            continue;
+        };
+        let pgrp = stat[close + 1..].split_whitespace().nth(2);
+        if pgrp.and_then(|pgrp| pgrp.parse::<u32>().ok()) == Some(pgid) {
+            let name = stat[open + 1..close].to_string();
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+// Only the group leader's command elsewhere
+#[cfg(all(unix, not(target_os = "linux")))]
+fn process_group_names(pgid: u32) -> Vec<String> {
+    std::process::Command::new("ps")
+        .args(["-o", "comm=", "-p", &pgid.to_string()])
+        .output()
+        .ok()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(|line| line.trim().rsplit('/').next().unwrap_or("").to_string())
+                .filter(|name| !name.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start(cmd: CommandBuilder, exit: Arc<Mutex<Option<ChildExit>>>) -> Result<PtyProcess> {
+        let state = Arc::new(Mutex::new(TerminalState::new(24, 80)));
+        start_on(cmd, state, exit)
+    }
+
+    fn start_on(
+        cmd: CommandBuilder,
+        state: Arc<Mutex<TerminalState>>,
+        exit: Arc<Mutex<Option<ChildExit>>>,
+    ) -> Result<PtyProcess> {
+        let size = PtySize {
+            rows: 24,
+            cols: 80,
+            pixel_width: 0,
+            pixel_height: 0,
+        };
+        spawn(cmd, size, state, egui::Context::default(), exit)
+    }
+
+    fn wait_for_exit(exit: &Mutex<Option<ChildExit>>) -> ChildExit {
+        let deadline = std::time::Instant::now() + Duration::from_secs(3);
+        loop {
+            if let Some(status) = exit.lock().unwrap().clone() {
+                return status;
+            }
+            assert!(std::time::Instant::now() < deadline, "exit not recorded");
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    fn spawn_error(program: &str) -> anyhow::Error {
+        match start(CommandBuilder::new(program), Arc::new(Mutex::new(None))) {
+            Ok(_) => panic!("{} started", program),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn exit_is_recorded_while_a_background_job_keeps_the_pty_open() {
+        let mut cmd = CommandBuilder::new("/bin/sh");
+        cmd.args(["-c", "sleep 5 & exit 3"]);
+        let exit = Arc::new(Mutex::new(None));
+        let _process = start(cmd, exit.clone()).unwrap();
+
+        let status = wait_for_exit(&exit);
+        assert_eq!(status.code, 3);
+        assert!(!status.success());
+    }
+
+    // Poll `done` until it holds, failing after a few seconds
+    fn wait_until(what: &str, mut done: impl FnMut() -> bool) {
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while !done() {
+            assert!(std::time::Instant::now() < deadline, "{}", what);
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn a_detached_reader_stops_applying_output() {
+        // The background job outlives the shell and writes only once the
+        // test releases it through the FIFO
+        let dir = std::env::temp_dir().join(format!("wterm-pty-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let fifo = dir.join("release");
+        let _ = std::fs::remove_file(&fifo);
+        let made = std::process::Command::new("mkfifo").arg(&fifo).status();
+        assert!(made.unwrap().success());
+
+        let mut cmd = CommandBuilder::new("/bin/sh");
+        let script = format!(
+            "(trap '' HUP; read _ < '{}'; echo late) & echo early",
+            fifo.display()
+        );
+        cmd.args(["-c", &script]);
+        let state = Arc::new(Mutex::new(TerminalState::new(24, 80)));
+        let exit = Arc::new(Mutex::new(None));
+        let process = start_on(cmd, state.clone(), exit.clone()).unwrap();
+        let terminal = Terminal::from_state(state.clone(), Arc::new(wterm_core::NoopListener));
+        let screen = || {
+            (0..24)
+                .map(|row| terminal.row_text(row))
+                .collect::<Vec<_>>()
+        };
+
+        wait_for_exit(&exit);
+        wait_until("no early output", || {
+            screen().iter().any(|row| row.contains("early"))
+        });
+        process.detached.store(true, Ordering::Release);
+        std::fs::write(&fifo, "go\n").unwrap();
+
+        // The reader gives up its handle on the state when it stops, at the
+        // job's output or at the end of it
+        wait_until("reader still running", || Arc::strong_count(&state) == 2);
+        let text = screen();
+        assert!(!text.iter().any(|row| row.contains("late")), "{:?}", text);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_commands_are_reported_as_not_found() {
+        for program in ["wterm-does-not-exist", "/no/such/dir/wterm"] {
+            let exit = ChildExit::start_failed(program, &spawn_error(program));
+            assert!(!exit.success());
+            assert_eq!(exit.describe(), format!("[command not found: {}]", program));
+        }
+    }
+
+    #[test]
+    fn other_start_errors_keep_the_reason() {
+        let exit = ChildExit::start_failed("/", &spawn_error("/"));
+        assert!(!exit.success());
+        assert!(
+            exit.describe().starts_with("[failed to start /: "),
+            "{}",
+            exit.describe()
+        );
+        assert!(!exit.describe().contains('\n'));
+    }
+}
@@ -0,0 +1,224 @@
+use anyhow::Result;
+use eframe::egui;
+use portable_pty::{CommandBuilder, MasterPty, PtySize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use wterm_core::Terminal;
+
+use crate::config::Config;
+use crate::pty::{self, ChildExit};
+use crate::terminal::row_cache::RowCache;
+use crate::terminal::state::TerminalState;
+use crate::terminal::{PtyWriter, RepaintListener};
+
+// One terminal: its grid, its PTY and the child process running on it.
+// Every pane of every tab is one; output is parsed by the session's own
+// reader thread.
+pub struct Session {
+    pub id: u64,
+    pub terminal_state: Arc<Mutex<TerminalState>>,
+    pub pty_writer: PtyWriter,
+    pub pty_master: Box<dyn MasterPty + Send>,
+    child_pid: Option<u32>, // Shell pid, to tell it apart from foreground jobs
+    detached: Arc<AtomicBool>, // Stops the current PTY's reader, see restart
+    command: CommandBuilder, // Respawned on Enter after the child exits
+    child_exit: Arc<Mutex<Option<ChildExit>>>, // Set once the child exits
+    pub exit_handled: bool, // Exit banner shown, waiting for Enter/Esc
+    default_title: String,  // --title or the program name
+    pub activity: bool,     // Output while in the background
+    pub bell: bool,         // BEL while in the background
+    pub broadcast: bool,    // In the broadcast group: gets what is typed into any member
+    pub row_cache: RowCache, // Rows painted last frame
+}
+
+impl Session {
+    pub fn spawn(
+        id: u64,
+        command: CommandBuilder,
+        size: PtySize,
+        config: &Config,
+        default_title: String,
+        egui_ctx: &egui::Context,
+    ) -> Result<Self> {
+        let terminal_state = new_state(size, config);
+        let child_exit = Arc::new(Mutex::new(None));
+        let process = pty::spawn(
+            command.clone(),
+            size,
+            terminal_state.clone(),
+            egui_ctx.clone(),
+            child_exit.clone(),
+        )?;
+        Ok(Self::new(
+            id,
+            terminal_state,
+            process,
+            command,
+            child_exit,
+            default_title,
+        ))
+    }
+
+    // A session whose command couldn't be started: it shows the exit banner
+    // with `exit` on the next frame, and Enter tries the command again
+    pub fn failed(
+        id: u64,
+        command: CommandBuilder,
+        size: PtySize,
+        config: &Config,
+        default_title: String,
+        exit: ChildExit,
+    ) -> Result<Self> {
+        let process = pty::open(size)?;
+        Ok(Self::new(
+            id,
+            new_state(size, config),
+            process,
+            command,
+            Arc::new(Mutex::new(Some(exit))),
+            default_title,
+        ))
+    }
+
+    fn new(
+        id: u64,
+        terminal_state: Arc<Mutex<TerminalState>>,
+        process: pty::PtyProcess,
+        command: CommandBuilder,
+        child_exit: Arc<Mutex<Option<ChildExit>>>,
+        default_title: String,
+    ) -> Self {
+        Self {
+            id,
+            terminal_state,
+            pty_writer: process.writer,
+            pty_master: process.master,
+            child_pid: process.pid,
+            detached: process.detached,
+            command,
+            child_exit,
+            exit_handled: false,
+            default_title,
+            activity: false,
+            bell: false,
+            broadcast: false,
+            row_cache: RowCache::default(),
+        }
+    }
+
+    // The title the program set (OSC 0/2)
+    pub fn custom_title(&self) -> Option<String> {
+        self.terminal_state
+            .lock()
+            .ok()
+            .and_then(|state| state.title.clone())
+    }
+
+    // Pane label
+    pub fn title(&self) -> String {
+        self.custom_title()
+            .unwrap_or_else(|| self.default_title.clone())
+    }
+
+    // Shell working directory reported via OSC 7
+    pub fn working_directory(&self) -> Option<PathBuf> {
+        self.terminal_state
+            .lock()
+            .ok()
+            .and_then(|state| state.working_directory.clone())
+    }
+
+    pub fn write(&mut self, text: &str) {
+        if let Ok(mut writer) = self.pty_writer.lock() {
+            let _ = writer.write_all(text.as_bytes());
+            let _ = writer.flush();
+        }
+    }
+
+    // Programs other than the shell running in the foreground
+    pub fn foreground_programs(&self) -> Vec<String> {
+        if self.exit_handled {
+            return Vec::new();
+        }
+        pty::foreground_programs(self.pty_master.as_ref(), self.child_pid)
+    }
+
+    // How the child ended, the first time this is asked after it exited
+    pub fn take_exit(&mut self) -> Option<ChildExit> {
+        if self.exit_handled {
+            return None;
+        }
+        let exit = self.child_exit.lock().ok().and_then(|exit| exit.clone())?;
+        self.exit_handled = true;
+        Some(exit)
+    }
+
+    // Write "[process exited with code 1] — press Enter to restart, Esc to close"
+    // below the output, after undoing what the program left behind
+    pub fn show_exit_banner(&self, exit: &ChildExit, egui_ctx: &egui::Context) {
+        let line_break = match self.terminal_state.lock() {
+            Ok(mut state) => {
+                state.reset_after_child_exit();
+                state.scroll_display_to_bottom();
+                if state.cursor_col > 0 {
+                    "\r\n"
+                } else {
+                    ""
+                }
+            }
+            Err(_) => return,
+        };
+        let banner = format!(
+            "{}\x1b[7m{}\x1b[0m — press Enter to restart, Esc to close\r\n",
+            line_break,
+            exit.describe()
+        );
+        let listener = Arc::new(RepaintListener {
+            ctx: egui_ctx.clone(),
+            pty_writer: None, // The program is gone
+        });
+        Terminal::from_state(self.terminal_state.clone(), listener).advance(banner.as_bytes());
+    }
+
+    // Start the same command again on a fresh PTY of the current size
+    pub fn restart(&mut self, egui_ctx: &egui::Context) -> Result<()> {
+        let (rows, cols) = self
+            .terminal_state
+            .lock()
+            .map(|state| (state.rows, state.cols))
+            .unwrap_or((24, 80));
+        let size = PtySize {
+            rows: rows as u16,
+            cols: cols as u16,
+            pixel_width: 0,
+            pixel_height: 0,
+        };
+        // Whatever still holds the old PTY open must not write into the
+        // state next to the new reader
+        self.detached.store(true, Ordering::Release);
+        if let Ok(mut exit) = self.child_exit.lock() {
+            *exit = None;
+        }
+        let process = pty::spawn(
+            self.command.clone(),
+            size,
+            self.terminal_state.clone(),
+            egui_ctx.clone(),
+            self.child_exit.clone(),
+        )?;
+        println!("🐚 Restarted {:?}", self.command.get_argv());
+        self.pty_writer = process.writer;
+        self.pty_master = process.master;
+        self.child_pid = process.pid;
+        self.detached = process.detached;
+        self.exit_handled = false;
+        Ok(())
+    }
+}
+
+fn new_state(size: PtySize, config: &Config) -> Arc<Mutex<TerminalState>> {
+    let mut state = TerminalState::new(size.rows as usize, size.cols as usize);
+    config.apply_to_state(&mut state);
+    Arc::new(Mutex::new(state))
+}
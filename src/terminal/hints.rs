@@ -0,0 +1,374 @@
+use crate::terminal::links::DEFAULT_URL_PATTERN;
+use crate::terminal::search::logical_lines;
+use crate::terminal::selection::SelectionRange;
+use crate::terminal::state::TerminalState;
+use anyhow::{bail, Result};
+use regex::Regex;
+use serde::Deserialize;
+
+// Paths with a directory part, or `file.ext:line[:column]`
+pub const HINT_PATH_PATTERN: &str =
+    r"(?:~|\.{1,2})?/?(?:[\w.\-]+/)+[\w.\-]+(?::\d+){0,2}|[\w\-]+\.\w+:\d+(?::\d+)?";
+
+// Label characters, home row first (same order as alacritty)
+pub const DEFAULT_HINT_ALPHABET: &str = "jfkdls;ahgurieowpq";
+
+// What happens to a match when its label is typed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum HintAction {
+    Copy,  // System clipboard
+    Paste, // Into the PTY, as if pasted
+    Open,  // Same as Ctrl+click on a link
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HintPattern {
+    pub name: String,
+    pub regex: String,
+    pub action: HintAction,
+}
+
+impl HintPattern {
+    fn new(name: &str, regex: &str, action: HintAction) -> Self {
+        Self {
+            name: name.to_string(),
+            regex: regex.to_string(),
+            action,
+        }
+    }
+}
+
+// Hint mode configuration ([hints] in the config file).
+// Earlier patterns win where matches overlap.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HintSettings {
+    pub alphabet: String,
+    pub patterns: Vec<HintPattern>,
+}
+
+impl Default for HintSettings {
+    fn default() -> Self {
+        Self {
+            alphabet: DEFAULT_HINT_ALPHABET.to_string(),
+            patterns: vec![
+                HintPattern::new("url", DEFAULT_URL_PATTERN, HintAction::Open),
+                HintPattern::new(
+                    "uuid",
+                    r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b",
+                    HintAction::Copy,
+                ),
+                HintPattern::new(
+                    "ipv4",
+                    r"\b(?:\d{1,3}\.){3}\d{1,3}(?::\d{1,5})?\b",
+                    HintAction::Copy,
+                ),
+                HintPattern::new(
+                    "ipv6",
+                    r"\b(?:[0-9a-fA-F]{1,4}:){7}[0-9a-fA-F]{1,4}\b",
+                    HintAction::Copy,
+                ),
+                HintPattern::new("sha", r"\b[0-9a-f]{7,40}\b", HintAction::Copy),
+                HintPattern::new("path", HINT_PATH_PATTERN, HintAction::Open),
+            ],
+        }
+    }
+}
+
+impl HintSettings {
+    // Rejected when the config is loaded: a label needs at least two
+    // characters to choose from, and every pattern must compile
+    pub fn validate(&self) -> Result<()> {
+        if distinct_chars(&self.alphabet).len() < 2 {
+            bail!(
+                "hints.alphabet needs at least two different characters, got {:?}",
+                self.alphabet
+            );
+        }
+        for pattern in &self.patterns {
+            if let Err(e) = Regex::new(&pattern.regex) {
+                bail!(
+                    "hints.patterns {:?}: regex is not valid: {}",
+                    pattern.name,
+                    e
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+fn distinct_chars(text: &str) -> Vec<char> {
+    let mut chars: Vec<char> = Vec::new();
+    for ch in text.chars() {
+        if !chars.contains(&ch) {
+            chars.push(ch);
+        }
+    }
+    chars
+}
+
+// A labelled match on screen
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hint {
+    pub label: String,
+    pub range: SelectionRange,
+    pub text: String,
+    pub action: HintAction,
+}
+
+// Keyboard hint mode (Ctrl+Shift+H): label every pattern match in the visible
+// rows and act on the one whose label is typed
+pub struct HintState {
+    pub active: bool,
+    pub hints: Vec<Hint>,
+    pub typed: String, // Label prefix typed so far
+    alphabet: Vec<char>,
+    patterns: Vec<(Regex, HintAction)>,
+}
+
+impl HintState {
+    // `settings` went through HintSettings::validate; anything it would
+    // reject falls back to the defaults here
+    pub fn new(settings: &HintSettings) -> Self {
+        let mut alphabet = distinct_chars(&settings.alphabet);
+        if alphabet.len() < 2 {
+            alphabet = DEFAULT_HINT_ALPHABET.chars().collect();
+        }
+
+        let patterns = settings
+            .patterns
+            .iter()
+            .filter_map(|pattern| Some((Regex::new(&pattern.regex).ok()?, pattern.action)))
+            .collect();
+
+        Self {
+            active: false,
+            hints: Vec::new(),
+            typed: String::new(),
+            alphabet,
+            patterns,
+        }
+    }
+
+    // Label the matches on render_buffer rows `first_row..last_row`.
+    // Returns false (and stays inactive) if there is nothing to label.
+    pub fn start(&mut self, state: &TerminalState, first_row: usize, last_row: usize) -> bool {
+        let mut found: Vec<(SelectionRange, String, HintAction)> = Vec::new();
+        for line in logical_lines(state, first_row, last_row) {
+            let mut taken: Vec<std::ops::Range<usize>> = Vec::new();
+            let mut line_hits = Vec::new();
+            for (regex, action) in &self.patterns {
+                for m in regex.find_iter(&line.text) {
+                    let overlaps = taken.iter().any(|r| r.start < m.end() && m.start() < r.end);
+                    if m.is_empty() || overlaps {
+                        continue;
+                    }
+                    if let Some(range) = line.range(m.start(), m.end()) {
+                        taken.push(m.range());
+                        line_hits.push((range, m.as_str().to_string(), *action));
+                    }
+                }
+            }
+            line_hits.sort_by_key(|(range, _, _)| range.start);
+            found.extend(line_hits);
+        }
+        // Matches on partially visible logical lines may lie outside the view
+        found.retain(|(range, _, _)| range.end.row >= first_row && range.start.row < last_row);
+
+        let labels = make_labels(&self.alphabet, found.len());
+        self.hints = found
+            .into_iter()
+            .zip(labels)
+            .map(|((range, text, action), label)| Hint {
+                label,
+                range,
+                text,
+                action,
+            })
+            .collect();
+        self.typed.clear();
+        self.active = !self.hints.is_empty();
+        self.active
+    }
+
+    pub fn cancel(&mut self) {
+        self.active = false;
+        self.hints.clear();
+        self.typed.clear();
+    }
+
+    // Label character typed: returns the hint once its label is complete.
+    // Characters that don't continue any label are ignored. Typing the label
+    // in upper case pastes the match instead of the pattern's own action.
+    pub fn key(&mut self, ch: char) -> Option<Hint> {
+        if ch.is_uppercase() {
+            let hint = self.key(ch.to_lowercase().next()?);
+            return hint.map(|hint| Hint {
+                action: HintAction::Paste,
+                ..hint
+            });
+        }
+        let typed = format!("{}{}", self.typed, ch);
+        if !self.hints.iter().any(|hint| hint.label.starts_with(&typed)) {
+            return None;
+        }
+        if let Some(hint) = self.hints.iter().find(|hint| hint.label == typed) {
+            let hint = hint.clone();
+            self.cancel();
+            return Some(hint);
+        }
+        self.typed = typed;
+        None
+    }
+
+    pub fn backspace(&mut self) {
+        self.typed.pop();
+    }
+
+    // Hints still reachable with what has been typed so far
+    pub fn visible_hints(&self) -> impl Iterator<Item = &Hint> {
+        self.hints
+            .iter()
+            .filter(|hint| hint.label.starts_with(&self.typed))
+    }
+}
+
+// `count` labels of equal length, so no label is a prefix of another
+fn make_labels(alphabet: &[char], count: usize) -> Vec<String> {
+    let mut length = 1;
+    while alphabet.len().pow(length) < count {
+        length += 1;
+    }
+    (0..count)
+        .map(|mut n| {
+            let mut label = vec![alphabet[0]; length as usize];
+            for slot in label.iter_mut().rev() {
+                *slot = alphabet[n % alphabet.len()];
+                n /= alphabet.len();
+            }
+            label.into_iter().collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::laid_out;
+
+    // (pattern name, matched text) of every hint on `text`
+    fn hints_on(text: &str) -> Vec<(String, String)> {
+        let settings = HintSettings::default();
+        let state = laid_out(200, text);
+        let mut hints = HintState::new(&settings);
+        hints.start(&state, 0, state.render_buffer.len());
+        hints
+            .hints
+            .iter()
+            .map(|hint| {
+                let name = settings
+                    .patterns
+                    .iter()
+                    .find(|p| Regex::new(&p.regex).unwrap().is_match(&hint.text))
+                    .map_or("", |p| p.name.as_str());
+                (name.to_string(), hint.text.clone())
+            })
+            .collect()
+    }
+
+    fn hint(name: &str, text: &str) -> (String, String) {
+        (name.to_string(), text.to_string())
+    }
+
+    #[test]
+    fn labels_have_equal_length_and_are_never_prefixes() {
+        let alphabet: Vec<char> = DEFAULT_HINT_ALPHABET.chars().collect();
+        for count in [0, 1, 2, 17, 18, 19, 100, 324, 325, 1000] {
+            let labels = make_labels(&alphabet, count);
+            assert_eq!(labels.len(), count);
+
+            let length = labels.first().map_or(0, |label| label.chars().count());
+            for (i, label) in labels.iter().enumerate() {
+                assert_eq!(label.chars().count(), length, "{:?}", labels);
+                assert!(label.chars().all(|ch| alphabet.contains(&ch)));
+                for other in &labels[i + 1..] {
+                    assert!(!other.starts_with(label.as_str()), "{} / {}", label, other);
+                    assert!(!label.starts_with(other.as_str()), "{} / {}", label, other);
+                }
+            }
+        }
+        // Single characters while they last, the home row first
+        assert_eq!(make_labels(&alphabet, 3), ["j", "f", "k"]);
+        assert_eq!(
+            make_labels(&['a', 'b'], 5),
+            ["aaa", "aab", "aba", "abb", "baa"]
+        );
+    }
+
+    #[test]
+    fn built_in_patterns() {
+        assert_eq!(
+            hints_on("commit 3deb904 merged into 3deb904a1c2e5f60718293a4b5c6d7e8f9a0b1c2"),
+            vec![
+                hint("sha", "3deb904"),
+                hint("sha", "3deb904a1c2e5f60718293a4b5c6d7e8f9a0b1c2"),
+            ]
+        );
+        assert_eq!(
+            hints_on("listening on 192.168.0.12:8080 and fe80:0:0:0:202:b3ff:fe1e:8329"),
+            vec![
+                hint("ipv4", "192.168.0.12:8080"),
+                hint("ipv6", "fe80:0:0:0:202:b3ff:fe1e:8329"),
+            ]
+        );
+        assert_eq!(
+            hints_on("id=123e4567-e89b-12d3-a456-426614174000"),
+            vec![hint("uuid", "123e4567-e89b-12d3-a456-426614174000")]
+        );
+        assert_eq!(
+            hints_on("at ~/src/wterm/src/app.rs:42:7 and main.rs:12, see https://x.org/a"),
+            vec![
+                hint("path", "~/src/wterm/src/app.rs:42:7"),
+                hint("path", "main.rs:12"),
+                hint("url", "https://x.org/a"),
+            ]
+        );
+        // Not hex, too short, not an address
+        assert!(hints_on("deadbe done 12:30 1.2.3").is_empty());
+    }
+
+    #[test]
+    fn typing_a_label_picks_its_hint() {
+        let state = laid_out(80, "a 192.168.0.1 b 10.0.0.1");
+        let mut hints = HintState::new(&HintSettings::default());
+        assert!(hints.start(&state, 0, 1));
+        assert_eq!(hints.key('x'), None); // Not a label
+        let picked = hints.key('f').unwrap();
+        assert_eq!(picked.text, "10.0.0.1");
+        assert_eq!(picked.action, HintAction::Copy);
+        assert!(!hints.active);
+
+        // Upper case pastes instead
+        hints.start(&state, 0, 1);
+        assert_eq!(hints.key('J').unwrap().action, HintAction::Paste);
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let mut settings = HintSettings::default();
+        settings
+            .patterns
+            .push(HintPattern::new("ticket", "JIRA-(\\d+", HintAction::Copy));
+        let error = settings.validate().unwrap_err().to_string();
+        assert!(error.starts_with("hints.patterns \"ticket\""), "{}", error);
+
+        let settings = HintSettings {
+            alphabet: "aaa".to_string(),
+            ..HintSettings::default()
+        };
+        assert!(settings.validate().is_err());
+        assert!(HintSettings::default().validate().is_ok());
+    }
+}
@@ -0,0 +1,416 @@
+use crate::terminal::search::logical_lines;
+use crate::terminal::selection::{SelectionPoint, SelectionRange};
+use crate::terminal::state::TerminalState;
+use anyhow::{bail, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Plain-text URLs
+pub const DEFAULT_URL_PATTERN: &str = r#"(?:https?|ftp|file)://[^\s<>"'`{}|\\^]+"#;
+// `path/to/file.rs:123:45` locations as printed by rustc, cargo, grep -n, ...
+// No part starts with '-', so `--option/x` isn't taken for a path.
+pub const DEFAULT_PATH_PATTERN: &str = r"(?P<file>(?:~|\.{1,2})?/?(?:[\w.][\w.\-]*/)*[\w.][\w.\-]*)(?::(?P<line>\d+)(?::(?P<column>\d+))?)?";
+
+// How links are found and opened ([links] in the config file)
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LinkSettings {
+    pub url_pattern: String,
+    // May use the named groups `file`, `line` and `column`
+    pub path_pattern: String,
+    // Editor for file links, e.g. "code --goto {file}:{line}:{column}".
+    // Files are handed to the system opener when unset.
+    pub editor_command: Option<String>,
+}
+
+impl LinkSettings {
+    // Patterns that don't compile are rejected when the config is loaded
+    pub fn validate(&self) -> Result<()> {
+        for (key, pattern) in [
+            ("links.url_pattern", &self.url_pattern),
+            ("links.path_pattern", &self.path_pattern),
+        ] {
+            if let Err(e) = Regex::new(pattern) {
+                bail!("{} is not a valid regex: {}", key, e);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for LinkSettings {
+    fn default() -> Self {
+        Self {
+            url_pattern: DEFAULT_URL_PATTERN.to_string(),
+            path_pattern: DEFAULT_PATH_PATTERN.to_string(),
+            editor_command: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LinkTarget {
+    Url(String),
+    File {
+        path: String,
+        line: Option<u32>,
+        column: Option<u32>,
+    },
+}
+
+// A link found in the buffer, with the cells it covers
+#[derive(Clone, Debug, PartialEq)]
+pub struct Link {
+    pub range: SelectionRange,
+    pub target: LinkTarget,
+}
+
+impl Link {
+    pub fn contains(&self, point: SelectionPoint) -> bool {
+        self.range.start <= point && point <= self.range.end
+    }
+}
+
+pub struct LinkDetector {
+    url_regex: Regex,
+    path_regex: Regex,
+    editor_command: Option<String>,
+}
+
+impl LinkDetector {
+    // `settings` went through LinkSettings::validate; the defaults are only a
+    // last resort for settings built in code
+    pub fn new(settings: &LinkSettings) -> Self {
+        let compile = |pattern: &str, fallback: &str| {
+            Regex::new(pattern).unwrap_or_else(|e| {
+                eprintln!("⚠️ Invalid link pattern {:?}: {}", pattern, e);
+                Regex::new(fallback).expect("default link pattern")
+            })
+        };
+        Self {
+            url_regex: compile(&settings.url_pattern, DEFAULT_URL_PATTERN),
+            path_regex: compile(&settings.path_pattern, DEFAULT_PATH_PATTERN),
+            editor_command: settings.editor_command.clone(),
+        }
+    }
+
+    // Links on render_buffer rows `first_row..last_row`, following soft wraps
+    pub fn find_links(
+        &self,
+        state: &TerminalState,
+        first_row: usize,
+        last_row: usize,
+    ) -> Vec<Link> {
+        let mut links = Vec::new();
+        for line in logical_lines(state, first_row, last_row) {
+            let mut urls = Vec::new();
+            for m in self.url_regex.find_iter(&line.text) {
+                let url = trim_url(m.as_str());
+                if let Some(range) = line.range(m.start(), m.start() + url.len()) {
+                    urls.push(m.start()..m.start() + url.len());
+                    links.push(Link {
+                        range,
+                        target: LinkTarget::Url(url.to_string()),
+                    });
+                }
+            }
+
+            for caps in self.path_regex.captures_iter(&line.text) {
+                let Some(m) = caps.get(0) else {
+                    continue;
+                };
+                // Paths inside URLs are part of the URL
+                if urls
+                    .iter()
+                    .any(|url| url.start < m.end() && m.start() < url.end)
+                {
+                    continue;
+                }
+                let target = file_target(&caps);
+                let LinkTarget::File {
+                    path,
+                    line: line_number,
+                    ..
+                } = &target
+                else {
+                    continue;
+                };
+                // A bare word is only a path if it has a directory, or an
+                // extension and a line number (`main.rs:12`, not `12:30`)
+                let looks_like_path =
+                    path.contains('/') || (path.contains('.') && line_number.is_some());
+                if !looks_like_path {
+                    continue;
+                }
+                if let Some(range) = line.range(m.start(), m.end()) {
+                    links.push(Link { range, target });
+                }
+            }
+        }
+        links
+    }
+
+    // Classify arbitrary matched text (e.g. from hint mode) as a URL or a file location
+    pub fn target_for_text(&self, text: &str) -> LinkTarget {
+        let whole = |m: regex::Match| m.start() == 0 && m.end() == text.len();
+        if self.url_regex.find(text).is_some_and(whole) {
+            return LinkTarget::Url(text.to_string());
+        }
+        match self.path_regex.captures(text) {
+            Some(caps) if caps.get(0).is_some_and(whole) => file_target(&caps),
+            _ => LinkTarget::File {
+                path: text.to_string(),
+                line: None,
+                column: None,
+            },
+        }
+    }
+
+    // Open a link: URLs and (without an editor) files via the system opener,
+    // relative paths resolved against the shell's working directory
+    pub fn open(&self, target: &LinkTarget, cwd: Option<&Path>) {
+        let command = match target {
+            LinkTarget::Url(url) => system_open_command(url),
+            LinkTarget::File { path, line, column } => {
+                let path = resolve_path(path, cwd);
+                let path = path.to_string_lossy();
+                match &self.editor_command {
+                    Some(editor) => editor_command(editor, &path, *line, *column),
+                    None => system_open_command(&path),
+                }
+            }
+        };
+
+        let Some((program, args)) = command.split_first() else {
+            return;
+        };
+        match Command::new(program).args(args).spawn() {
+            Ok(mut child) => {
+                // Reap the opener once it exits
+                std::thread::spawn(move || {
+                    let _ = child.wait();
+                });
+            }
+            Err(e) => eprintln!("❌ Failed to open link with {}: {}", program, e),
+        }
+    }
+}
+
+fn file_target(caps: &regex::Captures) -> LinkTarget {
+    let path = caps
+        .name("file")
+        .or_else(|| caps.get(0))
+        .map_or("", |m| m.as_str());
+    LinkTarget::File {
+        path: path.to_string(),
+        line: caps.name("line").and_then(|n| n.as_str().parse().ok()),
+        column: caps.name("column").and_then(|n| n.as_str().parse().ok()),
+    }
+}
+
+// Drop trailing punctuation that belongs to the surrounding sentence,
+// keeping a closing parenthesis that has its opening one in the URL
+fn trim_url(url: &str) -> &str {
+    let mut url = url;
+    loop {
+        let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '"']);
+        let trimmed = match trimmed.strip_suffix(')') {
+            Some(rest) if trimmed.matches('(').count() < trimmed.matches(')').count() => rest,
+            _ => trimmed,
+        };
+        if trimmed.len() == url.len() {
+            return url;
+        }
+        url = trimmed;
+    }
+}
+
+fn resolve_path(path: &str, cwd: Option<&Path>) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Ok(home) = std::env::var("HOME") {
+            return Path::new(&home).join(rest);
+        }
+    }
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    // Never left starting with '-', where the opener would read an option
+    match cwd {
+        Some(cwd) => cwd.join(path),
+        None => std::env::current_dir()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|_| Path::new(".").join(path)),
+    }
+}
+
+// The editor command line with {file}, {line} and {column} filled in; a
+// missing line or column is 1
+fn editor_command(editor: &str, path: &str, line: Option<u32>, column: Option<u32>) -> Vec<String> {
+    let line = line.unwrap_or(1).to_string();
+    let column = column.unwrap_or(1).to_string();
+    editor
+        .split_whitespace()
+        .map(|arg| {
+            arg.replace("{file}", path)
+                .replace("{line}", &line)
+                .replace("{column}", &column)
+        })
+        .collect()
+}
+
+fn system_open_command(target: &str) -> Vec<String> {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    vec![opener.to_string(), target.to_string()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::laid_out;
+    use wterm_core::Terminal;
+
+    fn file(path: &str, line: Option<u32>, column: Option<u32>) -> LinkTarget {
+        LinkTarget::File {
+            path: path.to_string(),
+            line,
+            column,
+        }
+    }
+
+    #[test]
+    fn path_pattern_captures_line_and_column() {
+        let detector = LinkDetector::new(&LinkSettings::default());
+        assert_eq!(
+            detector.target_for_text("src/app.rs:120:9"),
+            file("src/app.rs", Some(120), Some(9))
+        );
+        assert_eq!(
+            detector.target_for_text("main.rs:12"),
+            file("main.rs", Some(12), None)
+        );
+        assert_eq!(
+            detector.target_for_text("~/notes/todo.md"),
+            file("~/notes/todo.md", None, None)
+        );
+        assert_eq!(
+            detector.target_for_text("https://example.com/a.rs:3"),
+            LinkTarget::Url("https://example.com/a.rs:3".to_string())
+        );
+    }
+
+    #[test]
+    fn links_are_found_in_compiler_output() {
+        let state = laid_out(
+            80,
+            "error: --> src/terminal/links.rs:42:7 at 12:30, see https://doc.rust-lang.org/book.",
+        );
+        let detector = LinkDetector::new(&LinkSettings::default());
+        let targets: Vec<LinkTarget> = detector
+            .find_links(&state, 0, state.render_buffer.len())
+            .into_iter()
+            .map(|link| link.target)
+            .collect();
+
+        assert_eq!(
+            targets,
+            vec![
+                // Trailing sentence punctuation is not part of the URL
+                LinkTarget::Url("https://doc.rust-lang.org/book".to_string()),
+                file("src/terminal/links.rs", Some(42), Some(7)),
+            ]
+        );
+    }
+
+    #[test]
+    fn paths_never_start_with_a_dash() {
+        let state = laid_out(80, "grep --include=*.rs --foo/bar.rs:3");
+        let detector = LinkDetector::new(&LinkSettings::default());
+        let targets: Vec<LinkTarget> = detector
+            .find_links(&state, 0, state.render_buffer.len())
+            .into_iter()
+            .map(|link| link.target)
+            .collect();
+        assert_eq!(targets, vec![file("foo/bar.rs", Some(3), None)]);
+
+        // What reaches the opener is absolute, whatever the pattern matched
+        assert_eq!(
+            resolve_path("-n.rs", Some(Path::new("/src"))),
+            Path::new("/src/-n.rs")
+        );
+        assert!(resolve_path("--foo/bar.rs", None).is_absolute());
+    }
+
+    #[test]
+    fn links_span_soft_wraps() {
+        // "see src/ter|minal/mod.rs:7" at 11 columns
+        let state = laid_out(11, "see src/terminal/mod.rs:7");
+        assert!(state.is_render_row_wrapped(0));
+        let detector = LinkDetector::new(&LinkSettings::default());
+        let links = detector.find_links(&state, 1, 2);
+
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].target, file("src/terminal/mod.rs", Some(7), None));
+        assert_eq!(links[0].range.start, SelectionPoint::new(0, 4));
+        assert_eq!(links[0].range.end, SelectionPoint::new(2, 2));
+        assert!(links[0].contains(SelectionPoint::new(1, 5)));
+    }
+
+    #[test]
+    fn links_span_soft_wraps_in_live_output() {
+        // Laid out as output arrives, not by a resize
+        let mut terminal = Terminal::new(6, 12);
+        terminal.advance(b"open https://example.com/docs now\r\n$ ");
+        let mut state = terminal.state().lock().unwrap();
+        state.update_render_buffer_if_dirty();
+        assert!(state.is_render_row_wrapped(0));
+        let detector = LinkDetector::new(&LinkSettings::default());
+        let links = detector.find_links(&state, 0, state.render_buffer.len());
+
+        assert_eq!(links.len(), 1);
+        assert_eq!(
+            links[0].target,
+            LinkTarget::Url("https://example.com/docs".to_string())
+        );
+        assert_eq!(links[0].range.start, SelectionPoint::new(0, 5));
+        assert_eq!(links[0].range.end, SelectionPoint::new(2, 4));
+    }
+
+    #[test]
+    fn editor_command_fills_in_the_location() {
+        assert_eq!(
+            editor_command(
+                "code --goto {file}:{line}:{column}",
+                "/src/a.rs",
+                Some(3),
+                Some(9)
+            ),
+            ["code", "--goto", "/src/a.rs:3:9"]
+        );
+        assert_eq!(
+            editor_command("vim +{line} {file}", "/src/a.rs", None, None),
+            ["vim", "+1", "/src/a.rs"]
+        );
+    }
+
+    #[test]
+    fn invalid_patterns_name_their_key() {
+        let settings = LinkSettings {
+            path_pattern: "(?P<file>[".to_string(),
+            ..LinkSettings::default()
+        };
+        let error = settings.validate().unwrap_err().to_string();
+        assert!(
+            error.starts_with("links.path_pattern is not a valid regex"),
+            "{}",
+            error
+        );
+        assert!(LinkSettings::default().validate().is_ok());
+    }
+}
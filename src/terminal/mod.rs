@@ -1,2 +1,51 @@
-pub mod performer;
-pub mod state;
+use eframe::egui;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+pub use wterm_core::{selection, state};
+
+pub mod hints;
+pub mod links;
+pub mod row_cache;
+pub mod search;
+pub mod vi_mode;
+
+// Feed `text` into a 6-row terminal and lay it out at the terminal width,
+// long lines wrapped
+#[cfg(test)]
+pub fn laid_out(cols: usize, text: &str) -> state::TerminalState {
+    wterm_core::testing::laid_out(6, cols, text)
+}
+
+// The PTY writer, shared by the UI (keyboard input) and the reader thread
+// (answers to queries)
+pub type PtyWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+
+// Repaints the window whenever the emulator reports a change and writes its
+// replies back to the program. Title and bell are picked up from the state
+// while painting; they wake the UI too, since a background pane that is never
+// rebuilt doesn't report further repaints.
+pub struct RepaintListener {
+    pub ctx: egui::Context,
+    pub pty_writer: Option<PtyWriter>,
+}
+
+impl wterm_core::EventListener for RepaintListener {
+    fn title_changed(&self, _title: Option<&str>) {
+        self.ctx.request_repaint();
+    }
+
+    fn bell(&self) {
+        self.ctx.request_repaint();
+    }
+
+    fn repaint(&self) {
+        self.ctx.request_repaint();
+    }
+
+    fn pty_write(&self, bytes: &[u8]) {
+        if let Some(Ok(mut writer)) = self.pty_writer.as_ref().map(|writer| writer.lock()) {
+            let _ = writer.write_all(bytes);
+            let _ = writer.flush();
+        }
+    }
+}
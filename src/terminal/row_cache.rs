@@ -0,0 +1,84 @@
+use eframe::egui;
+use std::collections::HashMap;
+
+use crate::utils::color::Palette;
+
+// What the cached shapes depend on besides the cells: font size, the loaded
+// fonts (a version, as FontId doesn't name the font file), colors and scale
+type Style = (egui::FontId, u64, Palette, f32);
+
+// Shapes of painted render_buffer rows, keyed by the row's generation
+// (TerminalState::render_row_generations). A row that didn't change since the
+// last frame is drawn from here instead of being laid out cell by cell again.
+#[derive(Default)]
+pub struct RowCache {
+    style: Option<Style>,
+    previous: HashMap<u64, Vec<egui::Shape>>, // Rows painted last frame
+    current: HashMap<u64, Vec<egui::Shape>>,  // Rows painted this frame
+}
+
+impl RowCache {
+    // Start a frame. Rows painted last frame carry over unless the font,
+    // colors or scale changed; rows no longer on screen are dropped.
+    pub fn begin_frame(
+        &mut self,
+        font_id: &egui::FontId,
+        fonts_version: u64,
+        palette: &Palette,
+        pixels_per_point: f32,
+    ) {
+        self.previous = std::mem::take(&mut self.current);
+        let style = (
+            font_id.clone(),
+            fonts_version,
+            palette.clone(),
+            pixels_per_point,
+        );
+        if self.style.as_ref() != Some(&style) {
+            self.previous.clear();
+            self.style = Some(style);
+        }
+    }
+
+    // Shapes of the row with `generation`, relative to its top-left corner;
+    // `paint` makes them when the row wasn't painted last frame
+    pub fn row(
+        &mut self,
+        generation: u64,
+        paint: impl FnOnce() -> Vec<egui::Shape>,
+    ) -> &[egui::Shape] {
+        if !self.current.contains_key(&generation) {
+            let shapes = self.previous.remove(&generation).unwrap_or_else(paint);
+            self.current.insert(generation, shapes);
+        }
+        &self.current[&generation]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Paint row 1 for a frame; true if it had to be laid out again
+    fn repainted(cache: &mut RowCache, font_size: f32, fonts_version: u64) -> bool {
+        let font_id = egui::FontId::monospace(font_size);
+        cache.begin_frame(&font_id, fonts_version, &Palette::default(), 1.0);
+        let mut painted = false;
+        cache.row(1, || {
+            painted = true;
+            Vec::new()
+        });
+        painted
+    }
+
+    #[test]
+    fn rows_are_reused_until_the_style_changes() {
+        let mut cache = RowCache::default();
+        assert!(repainted(&mut cache, 12.0, 0));
+        assert!(!repainted(&mut cache, 12.0, 0));
+        assert!(repainted(&mut cache, 14.0, 0));
+        // Same FontId with other fonts loaded
+        assert!(repainted(&mut cache, 14.0, 1));
+        assert!(!repainted(&mut cache, 14.0, 1));
+    }
+}
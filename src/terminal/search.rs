@@ -0,0 +1,453 @@
+use crate::terminal::selection::{line_end_row, SelectionPoint, SelectionRange};
+use crate::terminal::state::TerminalState;
+use regex::{Regex, RegexBuilder};
+use std::collections::HashMap;
+use unicode_width::UnicodeWidthChar;
+
+// A logical line of render_buffer text: soft-wrapped rows joined together,
+// with the cell each character came from so matches can be mapped back.
+pub struct LineText {
+    pub text: String,
+    cells: Vec<(usize, SelectionPoint)>, // (byte offset in text, cell) per character
+}
+
+impl LineText {
+    // Cell of the character starting at `byte`
+    pub fn point_at(&self, byte: usize) -> Option<SelectionPoint> {
+        let idx = self.cells.partition_point(|&(offset, _)| offset < byte);
+        self.cells.get(idx).map(|&(_, point)| point)
+    }
+
+    // Last cell covered by the text ending at `byte` (exclusive), including
+    // the right half of a trailing wide character
+    pub fn end_point_before(&self, byte: usize) -> Option<SelectionPoint> {
+        let idx = self.cells.partition_point(|&(offset, _)| offset < byte);
+        let &(offset, point) = self.cells.get(idx.checked_sub(1)?)?;
+        let ch = self.text[offset..].chars().next()?;
+        let width = ch.width().unwrap_or(1).max(1);
+        Some(SelectionPoint::new(point.row, point.col + width - 1))
+    }
+
+    // Convert a byte range of `text` into a cell range
+    pub fn range(&self, start: usize, end: usize) -> Option<SelectionRange> {
+        Some(SelectionRange {
+            start: self.point_at(start)?,
+            end: self.end_point_before(end)?,
+            is_block: false,
+        })
+    }
+}
+
+// Collect the logical lines covering render_buffer rows `first_row..last_row`,
+// extending the range to whole logical lines at both ends.
+pub fn logical_lines(state: &TerminalState, first_row: usize, last_row: usize) -> Vec<LineText> {
+    let last_row = last_row.min(state.render_buffer.len());
+    let mut row = first_row;
+    while row > 0 && row < last_row && state.is_render_row_wrapped(row - 1) {
+        row -= 1;
+    }
+
+    let mut lines = Vec::new();
+    while row < last_row {
+        let (line, next_row) = line_at(state, row);
+        lines.push(line);
+        row = next_row;
+    }
+    lines
+}
+
+// The logical line starting at render_buffer row `row`, and the row after it
+fn line_at(state: &TerminalState, mut row: usize) -> (LineText, usize) {
+    let mut line = LineText {
+        text: String::new(),
+        cells: Vec::new(),
+    };
+    while row < state.render_buffer.len() {
+        for (col, cell) in state.render_buffer[row].iter().enumerate() {
+            if cell.ch == '\u{0000}' {
+                continue; // Right half of a wide character
+            }
+            if state.is_wrap_padding(row, col) {
+                continue; // The wide character starting the next row didn't fit
+            }
+            line.cells
+                .push((line.text.len(), SelectionPoint::new(row, col)));
+            line.text.push(cell.ch);
+        }
+
+        let wrapped = state.is_render_row_wrapped(row);
+        row += 1;
+        if !wrapped {
+            break;
+        }
+    }
+    (line, row)
+}
+
+pub fn build_regex(
+    query: &str,
+    use_regex: bool,
+    case_sensitive: bool,
+) -> Result<Regex, regex::Error> {
+    let pattern = if use_regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!case_sensitive)
+        .build()
+}
+
+// All non-empty matches over scrollback + screen, sorted top to bottom
+pub fn find_matches(state: &TerminalState, regex: &Regex) -> Vec<SelectionRange> {
+    logical_lines(state, 0, state.render_buffer.len())
+        .iter()
+        .flat_map(|line| line_matches(line, regex))
+        .collect()
+}
+
+fn line_matches(line: &LineText, regex: &Regex) -> Vec<SelectionRange> {
+    regex
+        .find_iter(&line.text)
+        .filter(|m| !m.is_empty())
+        .filter_map(|m| line.range(m.start(), m.end()))
+        .collect()
+}
+
+// The range with `f` applied to its rows
+fn map_rows(mut range: SelectionRange, f: impl Fn(usize) -> usize) -> SelectionRange {
+    range.start.row = f(range.start.row);
+    range.end.row = f(range.end.row);
+    range
+}
+
+// What a compiled regex was built from: (query, use_regex, case_sensitive)
+type RegexKey = (String, bool, bool);
+
+// Scrollback search state for the search bar
+pub struct SearchState {
+    pub active: bool,
+    pub query: String,
+    pub use_regex: bool,
+    pub case_sensitive: bool,
+    pub matches: Vec<SelectionRange>, // Sorted top to bottom
+    pub current: Option<usize>,
+    pub error: Option<String>, // Invalid regex
+    pub focus_requested: bool,
+    pub scroll_to_row: Option<usize>, // Pending scroll to the current match
+    regex: Option<(RegexKey, Result<Regex, String>)>, // Rebuilt when the query or flags change
+    // Matches of each logical line as of the last refresh, keyed by the
+    // generation of its first render row, rows relative to that row. Lines
+    // whose rows weren't laid out again are not searched again.
+    line_matches: HashMap<u64, Vec<SelectionRange>>,
+}
+
+impl SearchState {
+    pub fn new() -> Self {
+        Self {
+            active: false,
+            query: String::new(),
+            use_regex: false,
+            case_sensitive: false,
+            matches: Vec::new(),
+            current: None,
+            error: None,
+            focus_requested: false,
+            scroll_to_row: None,
+            regex: None,
+            line_matches: HashMap::new(),
+        }
+    }
+
+    pub fn open(&mut self) {
+        self.active = true;
+        self.focus_requested = true;
+    }
+
+    pub fn close(&mut self) {
+        self.active = false;
+        self.matches.clear();
+        self.current = None;
+        self.scroll_to_row = None;
+        self.line_matches.clear();
+    }
+
+    // Re-run the query over scrollback + screen, keeping the current match
+    // index where possible. Called on every render buffer rebuild, so only
+    // the lines laid out again since the last call are searched.
+    pub fn refresh(&mut self, state: &TerminalState) {
+        self.matches.clear();
+        self.error = None;
+        if self.query.is_empty() {
+            self.current = None;
+            self.line_matches.clear();
+            return;
+        }
+
+        let regex = match self.compiled_regex() {
+            Ok(regex) => regex,
+            Err(e) => {
+                self.error = Some(e);
+                self.current = None;
+                return;
+            }
+        };
+
+        let mut previous = std::mem::take(&mut self.line_matches);
+        let mut row = 0;
+        while row < state.render_buffer.len() {
+            let generation = state.render_row_generations.get(row).copied();
+            let (hits, next_row) = match generation.and_then(|g| previous.remove(&g)) {
+                Some(hits) => (hits, line_end_row(state, row) + 1),
+                None => {
+                    let (line, next_row) = line_at(state, row);
+                    let hits = line_matches(&line, &regex)
+                        .into_iter()
+                        .map(|range| map_rows(range, |r| r - row))
+                        .collect();
+                    (hits, next_row)
+                }
+            };
+            self.matches
+                .extend(hits.iter().map(|&range| map_rows(range, |r| r + row)));
+            if let Some(generation) = generation {
+                self.line_matches.insert(generation, hits);
+            }
+            row = next_row;
+        }
+
+        self.current = match self.current {
+            _ if self.matches.is_empty() => None,
+            Some(idx) => Some(idx.min(self.matches.len() - 1)),
+            // Start from the most recent output
+            None => Some(self.matches.len() - 1),
+        };
+    }
+
+    // The regex for the current query and flags, compiled once per change
+    fn compiled_regex(&mut self) -> Result<Regex, String> {
+        let key = (self.query.clone(), self.use_regex, self.case_sensitive);
+        if self.regex.as_ref().is_none_or(|(cached, _)| *cached != key) {
+            let regex = build_regex(&self.query, self.use_regex, self.case_sensitive)
+                .map_err(|e| e.to_string());
+            self.regex = Some((key, regex));
+            self.line_matches.clear();
+        }
+        self.regex.as_ref().expect("compiled above").1.clone()
+    }
+
+    // Re-run the query after it was edited and jump to the closest hit
+    pub fn query_changed(&mut self, state: &TerminalState) {
+        self.current = None;
+        self.refresh(state);
+        self.scroll_to_current();
+    }
+
+    pub fn next_match(&mut self) {
+        if self.matches.is_empty() {
+            return;
+        }
+        self.current = Some(match self.current {
+            Some(idx) => (idx + 1) % self.matches.len(),
+            None => 0,
+        });
+        self.scroll_to_current();
+    }
+
+    pub fn prev_match(&mut self) {
+        if self.matches.is_empty() {
+            return;
+        }
+        self.current = Some(match self.current {
+            Some(0) | None => self.matches.len() - 1,
+            Some(idx) => idx - 1,
+        });
+        self.scroll_to_current();
+    }
+
+    fn scroll_to_current(&mut self) {
+        if let Some(idx) = self.current {
+            self.scroll_to_row = Some(self.matches[idx].start.row);
+        }
+    }
+
+    pub fn current_match(&self) -> Option<&SelectionRange> {
+        self.current.and_then(|idx| self.matches.get(idx))
+    }
+
+    // "3/10" style status for the search bar
+    pub fn status(&self) -> String {
+        if let Some(error) = &self.error {
+            return error.lines().last().unwrap_or("invalid regex").to_string();
+        }
+        match self.current {
+            Some(idx) => format!("{}/{}", idx + 1, self.matches.len()),
+            None if self.query.is_empty() => String::new(),
+            None => "0/0".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::laid_out;
+    use wterm_core::Terminal;
+
+    #[test]
+    fn logical_lines_join_wrapped_rows_and_skip_continuations() {
+        // "ab한글cd|efgh" at 8 columns: 한 and 글 take two cells each
+        let state = laid_out(8, "ab한글cdefgh\r\nnext");
+        assert!(state.is_render_row_wrapped(0));
+
+        let lines = logical_lines(&state, 0, state.render_buffer.len());
+        assert_eq!(lines[0].text.trim_end(), "ab한글cdefgh");
+        assert_eq!(lines[1].text.trim_end(), "next");
+
+        // Characters map back to their cells, across the wrap
+        let text = &lines[0].text;
+        assert_eq!(
+            lines[0].point_at(text.find('글').unwrap()),
+            Some(SelectionPoint::new(0, 4))
+        );
+        assert_eq!(
+            lines[0].point_at(text.find('e').unwrap()),
+            Some(SelectionPoint::new(1, 0))
+        );
+        // A range ending in a wide character covers its right half
+        let end = text.find('c').unwrap();
+        assert_eq!(
+            lines[0].end_point_before(end),
+            Some(SelectionPoint::new(0, 5))
+        );
+    }
+
+    #[test]
+    fn logical_lines_skip_the_padding_before_a_wrapped_wide_character() {
+        // "로그: 안 |녕하세요 " at 9 columns: 녕 doesn't fit in the last cell
+        let state = laid_out(9, "로그: 안녕하세요 세계");
+        assert!(state.is_render_row_wrapped(0));
+        assert_eq!(state.render_buffer[0][8].ch, ' ');
+
+        let lines = logical_lines(&state, 0, state.render_buffer.len());
+        assert_eq!(lines[0].text.trim_end(), "로그: 안녕하세요 세계");
+
+        let regex = build_regex("안녕", false, true).unwrap();
+        let matches = find_matches(&state, &regex);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].start, SelectionPoint::new(0, 6));
+        assert_eq!(matches[0].end, SelectionPoint::new(1, 1));
+    }
+
+    #[test]
+    fn logical_lines_extend_to_whole_lines() {
+        let state = laid_out(8, "first\r\n0123456789abcdef\r\nlast");
+        // Row 2 is the middle of the wrapped line starting at row 1
+        let lines = logical_lines(&state, 2, 3);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].text.trim_end(), "0123456789abcdef");
+    }
+
+    #[test]
+    fn matches_span_soft_wraps() {
+        let state = laid_out(8, "say hello world\r\nHELLO again");
+        let regex = build_regex("hello", false, false).unwrap();
+        let matches = find_matches(&state, &regex);
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].start, SelectionPoint::new(0, 4));
+        assert_eq!(matches[0].end, SelectionPoint::new(1, 0));
+        assert_eq!(matches[0].text(&state), "hello");
+        assert_eq!(matches[1].start, SelectionPoint::new(2, 0));
+
+        let case_sensitive = build_regex("hello", false, true).unwrap();
+        assert_eq!(find_matches(&state, &case_sensitive).len(), 1);
+    }
+
+    #[test]
+    fn queries_are_literal_unless_regex_is_on() {
+        let state = laid_out(20, "a.c abc 한글.txt");
+        let literal = build_regex("a.c", false, false).unwrap();
+        assert_eq!(find_matches(&state, &literal).len(), 1);
+
+        let regex = build_regex("a.c", true, false).unwrap();
+        assert_eq!(find_matches(&state, &regex).len(), 2);
+
+        let wide = build_regex(r"\p{Hangul}+\.txt", true, false).unwrap();
+        let matches = find_matches(&state, &wide);
+        assert_eq!(matches[0].start, SelectionPoint::new(0, 8));
+        assert_eq!(matches[0].end, SelectionPoint::new(0, 15));
+
+        // Empty matches are no hits
+        let empty = build_regex("q*", true, false).unwrap();
+        assert!(find_matches(&state, &empty).is_empty());
+    }
+
+    #[test]
+    fn matches_span_soft_wraps_in_live_output() {
+        // Laid out as output arrives, not by a resize: "see the er|ror here"
+        let mut terminal = Terminal::new(6, 10);
+        terminal.advance(b"see the error here\r\n");
+        let mut state = terminal.state().lock().unwrap();
+        state.update_render_buffer_if_dirty();
+        assert!(state.is_render_row_wrapped(0));
+
+        let regex = build_regex("error", false, false).unwrap();
+        let matches = find_matches(&state, &regex);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].start, SelectionPoint::new(0, 8));
+        assert_eq!(matches[0].end, SelectionPoint::new(1, 2));
+    }
+
+    #[test]
+    fn refresh_only_rescans_changed_lines_and_stays_exact() {
+        let mut terminal = Terminal::new(6, 20);
+        terminal.state().lock().unwrap().history_lines = 100;
+        let mut search = SearchState::new();
+        search.query = "error".to_string();
+
+        let check = |terminal: &Terminal, search: &mut SearchState| {
+            let mut state = terminal.state().lock().unwrap();
+            state.update_render_buffer_if_dirty();
+            search.refresh(&state);
+            let regex = build_regex(&search.query, false, search.case_sensitive).unwrap();
+            assert_eq!(search.matches, find_matches(&state, &regex));
+            search.matches.len()
+        };
+
+        terminal.advance(b"ok\r\nerror one\r\nok\r\n");
+        assert_eq!(check(&terminal, &mut search), 1);
+
+        // New output below, scrolled history above
+        for _ in 0..10 {
+            terminal.advance(b"error\r\nok\r\n");
+        }
+        assert_eq!(check(&terminal, &mut search), 11);
+
+        // A line overwritten in place loses its hit
+        terminal.advance(b"\x1b[2Aerror\rfixed\x1b[K\r\n\r\n");
+        assert_eq!(check(&terminal, &mut search), 10);
+
+        // Changing the flags searches everything again
+        search.case_sensitive = true;
+        terminal.advance(b"ERROR\r\n");
+        assert_eq!(check(&terminal, &mut search), 10);
+    }
+
+    #[test]
+    fn invalid_regex_is_reported_until_the_query_changes() {
+        let state = laid_out(20, "abc");
+        let mut search = SearchState::new();
+        search.use_regex = true;
+        search.query = "(".to_string();
+        search.refresh(&state);
+        assert!(search.error.is_some());
+        assert!(search.matches.is_empty());
+
+        search.query = "b".to_string();
+        search.query_changed(&state);
+        assert_eq!(search.error, None);
+        assert_eq!(search.status(), "1/1");
+    }
+}
@@ -0,0 +1,629 @@
+use crate::terminal::search::{build_regex, find_matches};
+use crate::terminal::selection::{
+    line_end_row, line_start_row, Selection, SelectionPoint, SelectionType,
+};
+use crate::terminal::state::TerminalState;
+
+// Cursor motions available in copy mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViMotion {
+    Left,         // h
+    Down,         // j
+    Up,           // k
+    Right,        // l
+    WordForward,  // w
+    WordBackward, // b
+    WordEnd,      // e
+    LineStart,    // 0
+    LineEnd,      // $
+    Top,          // gg
+    Bottom,       // G
+}
+
+// Input in copy mode, already translated from the GUI's key events
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViKey {
+    Char(char),
+    Motion(ViMotion), // Arrow keys
+    BlockSelection,   // Ctrl+V (Ctrl+Q where Ctrl+V is taken by paste)
+    Enter,
+    Backspace,
+    Escape,
+}
+
+// What the app should do after a key was handled in copy mode
+#[derive(Debug, PartialEq)]
+pub enum ViAction {
+    None,
+    Yank(String), // Copy to the clipboard and leave copy mode
+    Exit,
+}
+
+// `/` or `?` prompt being typed
+pub struct ViSearchPrompt {
+    pub backward: bool,
+    pub query: String,
+}
+
+// tmux-style copy mode: a cursor of its own over render_buffer (scrollback +
+// screen) that drives the regular selection. PTY input is suspended meanwhile.
+pub struct ViMode {
+    pub active: bool,
+    pub cursor: SelectionPoint,
+    pub search_prompt: Option<ViSearchPrompt>,
+    last_search: Option<(String, bool)>, // (query, backward) for n/N
+    pending_g: bool,                     // First `g` of `gg`
+}
+
+impl ViMode {
+    pub fn new() -> Self {
+        Self {
+            active: false,
+            cursor: SelectionPoint::new(0, 0),
+            search_prompt: None,
+            last_search: None,
+            pending_g: false,
+        }
+    }
+
+    // Start at the terminal cursor
+    pub fn enter(&mut self, state: &mut TerminalState) {
+        self.active = true;
+        self.search_prompt = None;
+        self.pending_g = false;
+        let last_row = state.render_buffer.len().saturating_sub(1);
+        self.cursor = SelectionPoint::new(
+            state.render_cursor_row.min(last_row),
+            state.render_cursor_col.min(state.cols.saturating_sub(1)),
+        );
+        state.clear_selection();
+    }
+
+    pub fn exit(&mut self, state: &mut TerminalState) {
+        self.active = false;
+        self.search_prompt = None;
+        self.pending_g = false;
+        state.clear_selection();
+        state.scroll_display_to_bottom();
+    }
+
+    // Label for the mode indicator
+    pub fn status(&self, state: &TerminalState) -> String {
+        if let Some(prompt) = &self.search_prompt {
+            let prefix = if prompt.backward { '?' } else { '/' };
+            return format!("{}{}", prefix, prompt.query);
+        }
+        let mode = match state.selection.as_ref().map(|selection| selection.ty) {
+            Some(SelectionType::Line) => "VISUAL LINE",
+            Some(SelectionType::Block) => "VISUAL BLOCK",
+            Some(_) => "VISUAL",
+            None => "COPY",
+        };
+        format!(
+            "-- {} -- {}:{}",
+            mode,
+            self.cursor.row + 1,
+            self.cursor.col + 1
+        )
+    }
+
+    pub fn handle_key(&mut self, state: &mut TerminalState, key: ViKey) -> ViAction {
+        match key {
+            ViKey::Char(ch) => return self.handle_char(state, ch),
+            ViKey::Motion(motion) if self.search_prompt.is_none() => self.motion(state, motion),
+            ViKey::BlockSelection if self.search_prompt.is_none() => {
+                self.toggle_selection(state, SelectionType::Block)
+            }
+            ViKey::Enter => self.confirm(state),
+            ViKey::Backspace => self.backspace(),
+            ViKey::Escape => return self.escape(state),
+            _ => {}
+        }
+        ViAction::None
+    }
+
+    // The render buffer was rebuilt: keep the cursor inside it
+    pub fn buffer_changed(&mut self, state: &TerminalState) {
+        let last_row = state.render_buffer.len().saturating_sub(1);
+        if self.cursor.row > last_row {
+            self.cursor = column_in_row(state, last_row, self.cursor.col);
+        }
+    }
+
+    // Printable key typed in copy mode
+    fn handle_char(&mut self, state: &mut TerminalState, ch: char) -> ViAction {
+        if let Some(prompt) = &mut self.search_prompt {
+            prompt.query.push(ch);
+            return ViAction::None;
+        }
+
+        if std::mem::take(&mut self.pending_g) {
+            if ch == 'g' {
+                self.motion(state, ViMotion::Top);
+            }
+            return ViAction::None;
+        }
+
+        match ch {
+            'h' => self.motion(state, ViMotion::Left),
+            'j' => self.motion(state, ViMotion::Down),
+            'k' => self.motion(state, ViMotion::Up),
+            'l' => self.motion(state, ViMotion::Right),
+            'w' => self.motion(state, ViMotion::WordForward),
+            'b' => self.motion(state, ViMotion::WordBackward),
+            'e' => self.motion(state, ViMotion::WordEnd),
+            '0' => self.motion(state, ViMotion::LineStart),
+            '$' => self.motion(state, ViMotion::LineEnd),
+            'G' => self.motion(state, ViMotion::Bottom),
+            'g' => self.pending_g = true,
+            'v' => self.toggle_selection(state, SelectionType::Simple),
+            'V' => self.toggle_selection(state, SelectionType::Line),
+            '/' | '?' => {
+                self.search_prompt = Some(ViSearchPrompt {
+                    backward: ch == '?',
+                    query: String::new(),
+                })
+            }
+            'n' => self.search_next(state, false),
+            'N' => self.search_next(state, true),
+            'y' => {
+                return match state.selection_to_string() {
+                    Some(text) => ViAction::Yank(text),
+                    None => ViAction::None,
+                };
+            }
+            'q' => return ViAction::Exit,
+            _ => {}
+        }
+        ViAction::None
+    }
+
+    // Enter: run the search prompt
+    fn confirm(&mut self, state: &mut TerminalState) {
+        if let Some(prompt) = self.search_prompt.take() {
+            if !prompt.query.is_empty() {
+                self.last_search = Some((prompt.query, prompt.backward));
+                self.search_next(state, false);
+            }
+        }
+    }
+
+    fn backspace(&mut self) {
+        if let Some(prompt) = &mut self.search_prompt {
+            if prompt.query.pop().is_none() {
+                self.search_prompt = None;
+            }
+        }
+    }
+
+    // Escape backs out one level: prompt, then selection, then copy mode itself
+    fn escape(&mut self, state: &mut TerminalState) -> ViAction {
+        self.pending_g = false;
+        if self.search_prompt.take().is_some() {
+            ViAction::None
+        } else if state.selection.is_some() {
+            state.clear_selection();
+            ViAction::None
+        } else {
+            ViAction::Exit
+        }
+    }
+
+    // v / V / Ctrl+V: start a selection of that kind, switch kinds, or drop it
+    fn toggle_selection(&mut self, state: &mut TerminalState, ty: SelectionType) {
+        match &mut state.selection {
+            Some(selection) if selection.ty == ty => state.selection = None,
+            Some(selection) => selection.ty = ty,
+            None => state.selection = Some(Selection::new(ty, self.cursor)),
+        }
+    }
+
+    fn motion(&mut self, state: &mut TerminalState, motion: ViMotion) {
+        let total_rows = state.render_buffer.len();
+        if total_rows == 0 {
+            return;
+        }
+        let cursor = self.cursor;
+
+        self.cursor = match motion {
+            ViMotion::Left => step_backward(state, cursor).map_or(cursor, |(p, _)| {
+                if p.row == cursor.row {
+                    p
+                } else {
+                    cursor
+                }
+            }),
+            ViMotion::Right => step_forward(state, cursor).map_or(cursor, |(p, _)| {
+                if p.row == cursor.row {
+                    p
+                } else {
+                    cursor
+                }
+            }),
+            ViMotion::Up => column_in_row(state, cursor.row.saturating_sub(1), cursor.col),
+            ViMotion::Down => {
+                column_in_row(state, (cursor.row + 1).min(total_rows - 1), cursor.col)
+            }
+            ViMotion::WordForward => word_forward(state, cursor),
+            ViMotion::WordBackward => word_backward(state, cursor),
+            ViMotion::WordEnd => word_end(state, cursor),
+            ViMotion::LineStart => SelectionPoint::new(line_start_row(state, cursor.row), 0),
+            ViMotion::LineEnd => line_end(state, cursor.row),
+            ViMotion::Top => SelectionPoint::new(0, 0),
+            ViMotion::Bottom => SelectionPoint::new(total_rows - 1, 0),
+        };
+
+        self.cursor_moved(state);
+    }
+
+    // Jump to the next hit of the last search; `reverse` flips its direction (N)
+    fn search_next(&mut self, state: &mut TerminalState, reverse: bool) {
+        let Some((query, backward)) = &self.last_search else {
+            return;
+        };
+        // Smart case: an upper-case letter in the query makes it case-sensitive
+        let case_sensitive = query.chars().any(char::is_uppercase);
+        let Ok(regex) = build_regex(query, false, case_sensitive) else {
+            return;
+        };
+        let matches = find_matches(state, &regex);
+
+        let cursor = self.cursor;
+        let target = if *backward != reverse {
+            matches
+                .iter()
+                .rev()
+                .find(|m| m.start < cursor)
+                .or(matches.last())
+        } else {
+            matches
+                .iter()
+                .find(|m| m.start > cursor)
+                .or(matches.first())
+        };
+        if let Some(m) = target {
+            self.cursor = m.start;
+            self.cursor_moved(state);
+        }
+    }
+
+    // Drag the selection along and keep the cursor on screen
+    fn cursor_moved(&mut self, state: &mut TerminalState) {
+        if let Some(selection) = &mut state.selection {
+            selection.update(self.cursor);
+        }
+        state.scroll_display_to_include(self.cursor.row);
+    }
+}
+
+fn cell_char(state: &TerminalState, point: SelectionPoint) -> Option<char> {
+    state
+        .render_buffer
+        .get(point.row)
+        .and_then(|row| row.get(point.col))
+        .map(|cell| cell.ch)
+}
+
+// 0: blank, 1: separator, 2: word character
+fn char_class(state: &TerminalState, point: SelectionPoint) -> u8 {
+    match cell_char(state, point) {
+        None | Some(' ') => 0,
+        Some(ch) if state.word_separators.contains(ch) => 1,
+        Some(_) => 2,
+    }
+}
+
+// Next character cell, crossing rows. The flag is set when a hard line
+// break (a row that did not soft-wrap) was crossed.
+fn step_forward(state: &TerminalState, point: SelectionPoint) -> Option<(SelectionPoint, bool)> {
+    let mut p = point;
+    let mut line_break = false;
+    loop {
+        let row_len = state.render_buffer.get(p.row)?.len();
+        if p.col + 1 < row_len {
+            p.col += 1;
+        } else if p.row + 1 < state.render_buffer.len() {
+            line_break |= !state.is_render_row_wrapped(p.row);
+            p = SelectionPoint::new(p.row + 1, 0);
+        } else {
+            return None;
+        }
+        // Skip the right half of wide characters
+        if cell_char(state, p) != Some('\u{0000}') {
+            return Some((p, line_break));
+        }
+    }
+}
+
+fn step_backward(state: &TerminalState, point: SelectionPoint) -> Option<(SelectionPoint, bool)> {
+    let mut p = point;
+    let mut line_break = false;
+    loop {
+        if p.col > 0 {
+            p.col -= 1;
+        } else if p.row > 0 {
+            line_break |= !state.is_render_row_wrapped(p.row - 1);
+            let row_len = state.render_buffer[p.row - 1].len();
+            p = SelectionPoint::new(p.row - 1, row_len.saturating_sub(1));
+        } else {
+            return None;
+        }
+        if cell_char(state, p) != Some('\u{0000}') {
+            return Some((p, line_break));
+        }
+    }
+}
+
+// Same column on another row, moved off the right half of a wide character
+fn column_in_row(state: &TerminalState, row: usize, col: usize) -> SelectionPoint {
+    let row_len = state.render_buffer.get(row).map_or(0, |r| r.len());
+    let mut point = SelectionPoint::new(row, col.min(row_len.saturating_sub(1)));
+    if cell_char(state, point) == Some('\u{0000}') && point.col > 0 {
+        point.col -= 1;
+    }
+    point
+}
+
+// Last non-blank cell of the logical line containing `row`
+fn line_end(state: &TerminalState, row: usize) -> SelectionPoint {
+    let first = line_start_row(state, row);
+    let last = line_end_row(state, row);
+    for r in (first..=last).rev() {
+        if let Some(col) = state.render_buffer[r]
+            .iter()
+            .rposition(|cell| cell.ch != ' ' && cell.ch != '\u{0000}')
+        {
+            return SelectionPoint::new(r, col);
+        }
+    }
+    SelectionPoint::new(first, 0)
+}
+
+// w: start of the next word
+fn word_forward(state: &TerminalState, point: SelectionPoint) -> SelectionPoint {
+    let start_class = char_class(state, point);
+    let mut seen_blank = start_class == 0;
+    let mut current = point;
+    let mut last_non_blank = point; // Where to stop if there is no next word
+    while let Some((next, line_break)) = step_forward(state, current) {
+        current = next;
+        seen_blank |= line_break;
+        let class = char_class(state, current);
+        if class == 0 {
+            seen_blank = true;
+        } else if seen_blank || class != start_class {
+            return current;
+        } else {
+            last_non_blank = current;
+        }
+    }
+    last_non_blank
+}
+
+// e: end of the current or next word
+fn word_end(state: &TerminalState, point: SelectionPoint) -> SelectionPoint {
+    let Some((mut current, _)) = step_forward(state, point) else {
+        return point;
+    };
+    while char_class(state, current) == 0 {
+        match step_forward(state, current) {
+            Some((next, _)) => current = next,
+            None => return current,
+        }
+    }
+    let class = char_class(state, current);
+    while let Some((next, false)) = step_forward(state, current) {
+        if char_class(state, next) != class {
+            break;
+        }
+        current = next;
+    }
+    current
+}
+
+// b: start of the current or previous word
+fn word_backward(state: &TerminalState, point: SelectionPoint) -> SelectionPoint {
+    let Some((mut current, _)) = step_backward(state, point) else {
+        return point;
+    };
+    while char_class(state, current) == 0 {
+        match step_backward(state, current) {
+            Some((prev, _)) => current = prev,
+            None => return current,
+        }
+    }
+    let class = char_class(state, current);
+    while let Some((prev, false)) = step_backward(state, current) {
+        if char_class(state, prev) != class {
+            break;
+        }
+        current = prev;
+    }
+    current
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::laid_out;
+
+    fn at(state: &mut TerminalState, row: usize, col: usize) -> ViMode {
+        let mut vi = ViMode::new();
+        vi.enter(state);
+        vi.cursor = SelectionPoint::new(row, col);
+        vi
+    }
+
+    // Type `keys` in copy mode; returns the action of the last one
+    fn press(vi: &mut ViMode, state: &mut TerminalState, keys: &str) -> ViAction {
+        let mut action = ViAction::None;
+        for ch in keys.chars() {
+            let key = match ch {
+                '\n' => ViKey::Enter,
+                '\x16' => ViKey::BlockSelection,
+                _ => ViKey::Char(ch),
+            };
+            action = vi.handle_key(state, key);
+        }
+        action
+    }
+
+    fn cursor(vi: &ViMode) -> (usize, usize) {
+        (vi.cursor.row, vi.cursor.col)
+    }
+
+    #[test]
+    fn word_motions_treat_wide_characters_as_one_cell() {
+        // echo: 0-3, 안녕: 5-8, world: 10-14
+        let mut state = laid_out(20, "echo 안녕 world");
+        let mut vi = at(&mut state, 0, 0);
+
+        press(&mut vi, &mut state, "w");
+        assert_eq!(cursor(&vi), (0, 5));
+        press(&mut vi, &mut state, "e");
+        assert_eq!(cursor(&vi), (0, 7)); // Left half of 녕
+        press(&mut vi, &mut state, "w");
+        assert_eq!(cursor(&vi), (0, 10));
+        press(&mut vi, &mut state, "b");
+        assert_eq!(cursor(&vi), (0, 5));
+        press(&mut vi, &mut state, "b");
+        assert_eq!(cursor(&vi), (0, 0));
+        press(&mut vi, &mut state, "e");
+        assert_eq!(cursor(&vi), (0, 3));
+    }
+
+    #[test]
+    fn word_motions_cross_soft_wraps_as_one_line() {
+        // "hello worl|d foo": world continues on the next row
+        let mut state = laid_out(10, "hello world foo\r\nbar");
+        let mut vi = at(&mut state, 0, 0);
+
+        press(&mut vi, &mut state, "w");
+        assert_eq!(cursor(&vi), (0, 6));
+        press(&mut vi, &mut state, "e");
+        assert_eq!(cursor(&vi), (1, 0));
+        press(&mut vi, &mut state, "w");
+        assert_eq!(cursor(&vi), (1, 2));
+        press(&mut vi, &mut state, "b");
+        assert_eq!(cursor(&vi), (0, 6));
+
+        // A hard line break ends a word even without a blank
+        let mut vi = at(&mut state, 1, 2);
+        press(&mut vi, &mut state, "w");
+        assert_eq!(cursor(&vi), (2, 0));
+        press(&mut vi, &mut state, "b");
+        assert_eq!(cursor(&vi), (1, 2));
+    }
+
+    #[test]
+    fn line_and_buffer_motions() {
+        let mut state = laid_out(10, "first\r\nhello world foo\r\nlast");
+        let mut vi = at(&mut state, 2, 3);
+
+        press(&mut vi, &mut state, "0");
+        assert_eq!(cursor(&vi), (1, 0)); // Start of the logical line
+        press(&mut vi, &mut state, "$");
+        assert_eq!(cursor(&vi), (2, 4)); // Last non-blank, after the wrap
+        press(&mut vi, &mut state, "gg");
+        assert_eq!(cursor(&vi), (0, 0));
+        press(&mut vi, &mut state, "G");
+        assert_eq!(cursor(&vi), (state.render_buffer.len() - 1, 0));
+
+        // A lone g waits for the second one; anything else cancels it
+        let mut vi = at(&mut state, 2, 3);
+        press(&mut vi, &mut state, "gl");
+        assert_eq!(cursor(&vi), (2, 3));
+    }
+
+    #[test]
+    fn search_forward_and_backward_with_n_and_n_reversed() {
+        let mut state = laid_out(20, "error a\r\nok\r\nerror b\r\nerror c");
+        let mut vi = at(&mut state, 0, 0);
+
+        press(&mut vi, &mut state, "/error\n");
+        assert_eq!(cursor(&vi), (2, 0));
+        press(&mut vi, &mut state, "n");
+        assert_eq!(cursor(&vi), (3, 0));
+        press(&mut vi, &mut state, "n");
+        assert_eq!(cursor(&vi), (0, 0)); // Wraps around
+        press(&mut vi, &mut state, "N");
+        assert_eq!(cursor(&vi), (3, 0));
+
+        press(&mut vi, &mut state, "?error\n");
+        assert_eq!(cursor(&vi), (2, 0));
+        press(&mut vi, &mut state, "n");
+        assert_eq!(cursor(&vi), (0, 0));
+        press(&mut vi, &mut state, "N");
+        assert_eq!(cursor(&vi), (2, 0));
+    }
+
+    #[test]
+    fn search_is_smart_case() {
+        let mut state = laid_out(20, "Error\r\nerror");
+        let mut vi = at(&mut state, 1, 3);
+        press(&mut vi, &mut state, "/Error\n");
+        assert_eq!(cursor(&vi), (0, 0));
+        press(&mut vi, &mut state, "n");
+        assert_eq!(cursor(&vi), (0, 0)); // The only case-sensitive hit
+
+        press(&mut vi, &mut state, "/error\n");
+        assert_eq!(cursor(&vi), (1, 0));
+        press(&mut vi, &mut state, "n");
+        assert_eq!(cursor(&vi), (0, 0));
+    }
+
+    #[test]
+    fn visual_keys_map_to_selection_types() {
+        let mut state = laid_out(20, "abc");
+        let mut vi = at(&mut state, 0, 1);
+        let ty = |state: &TerminalState| state.selection.as_ref().map(|s| s.ty);
+
+        press(&mut vi, &mut state, "v");
+        assert_eq!(ty(&state), Some(SelectionType::Simple));
+        assert_eq!(state.selection.as_ref().unwrap().anchor, vi.cursor);
+        assert!(vi.status(&state).starts_with("-- VISUAL --"));
+
+        press(&mut vi, &mut state, "V");
+        assert_eq!(ty(&state), Some(SelectionType::Line));
+        press(&mut vi, &mut state, "\x16");
+        assert_eq!(ty(&state), Some(SelectionType::Block));
+        assert!(vi.status(&state).starts_with("-- VISUAL BLOCK --"));
+
+        // The same key again drops the selection
+        press(&mut vi, &mut state, "\x16");
+        assert_eq!(ty(&state), None);
+        assert!(vi.status(&state).starts_with("-- COPY --"));
+    }
+
+    #[test]
+    fn yank_copies_the_selection() {
+        let mut state = laid_out(20, "hello world\r\nsecond line\r\nabcdef\r\nghijkl");
+
+        let mut vi = at(&mut state, 0, 6);
+        assert_eq!(
+            press(&mut vi, &mut state, "ve"),
+            ViAction::None,
+            "motions don't yank"
+        );
+        assert_eq!(
+            press(&mut vi, &mut state, "y"),
+            ViAction::Yank("world".to_string())
+        );
+
+        let mut vi = at(&mut state, 1, 3);
+        assert_eq!(
+            press(&mut vi, &mut state, "Vy"),
+            ViAction::Yank("second line".to_string())
+        );
+
+        let mut vi = at(&mut state, 2, 1);
+        assert_eq!(
+            press(&mut vi, &mut state, "\x16jlly"),
+            ViAction::Yank("bcd\nhij".to_string())
+        );
+
+        // Nothing selected: nothing to yank
+        let mut vi = at(&mut state, 0, 0);
+        assert_eq!(press(&mut vi, &mut state, "y"), ViAction::None);
+    }
+}
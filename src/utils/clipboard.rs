@@ -0,0 +1,162 @@
+// PRIMARY selection (select-to-copy, middle-click to paste) as found on X11/Wayland.
+// The trait keeps the app independent of the platform backend; the tests below
+// go through LocalPrimarySelection.
+pub trait PrimarySelection {
+    fn set_text(&mut self, text: &str);
+    fn get_text(&mut self) -> Option<String>;
+}
+
+// In-process primary selection, not shared with other applications.
+// Used when the system selection can't be reached.
+#[derive(Default)]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub struct LocalPrimarySelection {
+    text: Option<String>,
+}
+
+impl PrimarySelection for LocalPrimarySelection {
+    fn set_text(&mut self, text: &str) {
+        self.text = Some(text.to_string());
+    }
+
+    fn get_text(&mut self) -> Option<String> {
+        self.text.clone()
+    }
+}
+
+// System PRIMARY selection backed by arboard (X11, or Wayland with data-control)
+#[cfg(target_os = "linux")]
+pub struct SystemPrimarySelection {
+    // Kept alive for as long as we own the selection: on X11 the contents are
+    // served to other clients from this handle.
+    clipboard: arboard::Clipboard,
+}
+
+#[cfg(target_os = "linux")]
+impl SystemPrimarySelection {
+    pub fn new() -> Option<Self> {
+        match arboard::Clipboard::new() {
+            Ok(clipboard) => Some(Self { clipboard }),
+            Err(e) => {
+                eprintln!("⚠️ Primary selection unavailable: {}", e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl PrimarySelection for SystemPrimarySelection {
+    fn set_text(&mut self, text: &str) {
+        use arboard::{LinuxClipboardKind, SetExtLinux};
+
+        if let Err(e) = self
+            .clipboard
+            .set()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text(text.to_string())
+        {
+            eprintln!("⚠️ Failed to set primary selection: {}", e);
+        }
+    }
+
+    fn get_text(&mut self) -> Option<String> {
+        use arboard::{GetExtLinux, LinuxClipboardKind};
+
+        self.clipboard
+            .get()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text()
+            .ok()
+    }
+}
+
+// Bracketed paste markers (mode 2004)
+const PASTE_START: &str = "\x1b[200~";
+const PASTE_END: &str = "\x1b[201~";
+
+// What pasting `text` sends to the program: wrapped in bracketed paste markers
+// when it enabled mode 2004. None for an empty paste.
+pub fn paste_sequence(text: &str, bracketed: bool) -> Option<String> {
+    if text.is_empty() {
+        return None;
+    }
+    if !bracketed {
+        return Some(text.to_string());
+    }
+    // Drop any end marker inside the text so it can't terminate the paste early;
+    // repeated because removing one can join the halves of another
+    let mut text = text.to_string();
+    while text.contains(PASTE_END) {
+        text = text.replace(PASTE_END, "");
+    }
+    Some(format!("{}{}{}", PASTE_START, text, PASTE_END))
+}
+
+// Primary selection for the current platform; None where the concept doesn't exist
+pub fn platform_primary_selection() -> Option<Box<dyn PrimarySelection>> {
+    #[cfg(target_os = "linux")]
+    {
+        match SystemPrimarySelection::new() {
+            Some(system) => Some(Box::new(system)),
+            None => Some(Box::new(LocalPrimarySelection::default())),
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_paste_is_sent_as_is() {
+        assert_eq!(
+            paste_sequence("ls\x1b[201~", false).as_deref(),
+            Some("ls\x1b[201~")
+        );
+        assert_eq!(paste_sequence("", false), None);
+        assert_eq!(paste_sequence("", true), None);
+    }
+
+    #[test]
+    fn bracketed_paste_is_wrapped() {
+        assert_eq!(
+            paste_sequence("echo 안녕\n", true).as_deref(),
+            Some("\x1b[200~echo 안녕\n\x1b[201~")
+        );
+    }
+
+    #[test]
+    fn embedded_end_markers_are_stripped() {
+        assert_eq!(
+            paste_sequence("a\x1b[201~rm -rf ~\n", true).as_deref(),
+            Some("\x1b[200~arm -rf ~\n\x1b[201~")
+        );
+        // Removing the inner marker must not leave a new one behind
+        assert_eq!(
+            paste_sequence("\x1b[20\x1b[201~1~x", true).as_deref(),
+            Some("\x1b[200~x\x1b[201~")
+        );
+    }
+
+    #[test]
+    fn middle_click_pastes_the_local_primary_selection() {
+        let mut primary: Box<dyn PrimarySelection> = Box::new(LocalPrimarySelection::default());
+        assert_eq!(primary.get_text(), None);
+
+        primary.set_text("cargo test");
+        let text = primary.get_text().unwrap();
+        assert_eq!(
+            paste_sequence(&text, true).as_deref(),
+            Some("\x1b[200~cargo test\x1b[201~")
+        );
+
+        // A new selection replaces the old one
+        primary.set_text("git status");
+        assert_eq!(primary.get_text().as_deref(), Some("git status"));
+    }
+}
@@ -1 +1,2 @@
+pub mod clipboard;
 pub mod color;
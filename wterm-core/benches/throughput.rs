@@ -0,0 +1,75 @@
+// Output throughput of the emulator, fed the way the PTY reader thread feeds
+// it: read-sized chunks into Terminal::advance.
+//
+// cargo bench -p wterm-core --bench throughput
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use wterm_core::Terminal;
+
+const CHUNK: usize = 4096; // PTY reads rarely return more
+
+// About 1 MiB of `cat` output: 80-column lines
+fn cat_output() -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut line = 0;
+    while out.len() < 1 << 20 {
+        out.extend_from_slice(
+            format!(
+                "{:06} drwxr-xr-x  12 user  staff   384  7 16 17:01 some/longer/path/name.rs\r\n",
+                line
+            )
+            .as_bytes(),
+        );
+        line += 1;
+    }
+    out
+}
+
+fn feed(terminal: &mut Terminal, bytes: &[u8]) {
+    for chunk in bytes.chunks(CHUNK) {
+        terminal.advance(chunk);
+    }
+}
+
+fn throughput(c: &mut Criterion) {
+    let output = cat_output();
+    let mut group = c.benchmark_group("throughput");
+    group.throughput(Throughput::Bytes(output.len() as u64));
+
+    group.bench_function("cat", |b| {
+        let mut terminal = Terminal::new(50, 120);
+        b.iter(|| feed(&mut terminal, black_box(&output)));
+    });
+
+    // The same while another thread keeps taking the state lock to paint, as
+    // the UI thread does every frame
+    group.bench_function("cat_while_painting", |b| {
+        let mut terminal = Terminal::new(50, 120);
+        let state = terminal.state().clone();
+        let running = Arc::new(AtomicBool::new(true));
+        let painter = {
+            let running = running.clone();
+            thread::spawn(move || {
+                while running.load(Ordering::Relaxed) {
+                    if let Ok(mut state) = state.lock() {
+                        state.update_render_buffer_if_dirty();
+                        black_box(state.render_buffer.len());
+                    }
+                    thread::sleep(Duration::from_millis(1));
+                }
+            })
+        };
+        b.iter(|| feed(&mut terminal, black_box(&output)));
+        running.store(false, Ordering::Relaxed);
+        painter.join().unwrap();
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, throughput);
+criterion_main!(benches);
@@ -0,0 +1,191 @@
+// Emulator cost on the kinds of output a terminal actually sees. Every input
+// is generated deterministically, so numbers from different commits compare:
+//
+// git checkout main   && cargo bench -p wterm-core --bench workloads -- --save-baseline main
+// git checkout branch && cargo bench -p wterm-core --bench workloads -- --baseline main
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::hint::black_box;
+use wterm_core::Terminal;
+
+const ROWS: usize = 50;
+const COLS: usize = 120;
+const CHUNK: usize = 4096; // What one PTY read typically returns
+const TARGET: usize = 1 << 20; // About 1 MiB per workload
+
+// Small LCG so the "random" parts are the same on every run
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, bound: u64) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) % bound
+    }
+}
+
+// Plain ASCII, long lines that wrap
+fn ascii_flood() -> Vec<u8> {
+    let line: String = (b' '..=b'~').map(char::from).cycle().take(200).collect();
+    let mut out = Vec::new();
+    while out.len() < TARGET {
+        out.extend_from_slice(line.as_bytes());
+        out.extend_from_slice(b"\r\n");
+    }
+    out
+}
+
+// `ls --color` and cargo: short coloured runs, bold, 256-colour and truecolour
+fn sgr_heavy() -> Vec<u8> {
+    let names = [
+        "src",
+        "target",
+        "Cargo.toml",
+        "README.md",
+        "main.rs",
+        "build.sh",
+    ];
+    let colours = [
+        "\x1b[01;34m",
+        "\x1b[01;32m",
+        "\x1b[38;5;208m",
+        "\x1b[38;2;250;189;47m",
+    ];
+    let mut rng = Lcg(1);
+    let mut out = Vec::new();
+    while out.len() < TARGET {
+        for _ in 0..6 {
+            let name = names[rng.next(names.len() as u64) as usize];
+            let colour = colours[rng.next(colours.len() as u64) as usize];
+            out.extend_from_slice(format!("{}{}\x1b[0m  ", colour, name).as_bytes());
+        }
+        out.extend_from_slice(b"\r\n");
+        out.extend_from_slice(
+            b"\x1b[1m\x1b[32m   Compiling\x1b[0m wterm-core v0.1.0 (/home/user/wterm/wterm-core)\r\n",
+        );
+        out.extend_from_slice(
+            b"\x1b[0m\x1b[1m\x1b[33mwarning\x1b[0m\x1b[0m\x1b[1m: unused variable: `x`\x1b[0m\r\n",
+        );
+    }
+    out
+}
+
+// Wide characters: every glyph takes two cells
+fn hangul() -> Vec<u8> {
+    let line = "다람쥐 헌 쳇바퀴에 타고파. 키스의 고유조건은 입술끼리 만나야 하고 특별한 기술은 필요치 않다. ";
+    let mut out = Vec::new();
+    while out.len() < TARGET {
+        out.extend_from_slice(line.as_bytes());
+        out.extend_from_slice(b"\r\n");
+    }
+    out
+}
+
+// htop-like frames on the alternate screen: home, then every row rewritten
+// through cursor addressing, colour and erase-to-end-of-line
+fn htop_redraw() -> Vec<u8> {
+    let mut rng = Lcg(2);
+    let mut out = b"\x1b[?1049h\x1b[?25l".to_vec();
+    while out.len() < TARGET {
+        out.extend_from_slice(b"\x1b[H");
+        for row in 1..=ROWS {
+            let cpu = rng.next(1000);
+            let mem = rng.next(1000);
+            out.extend_from_slice(
+                format!(
+                    "\x1b[{};1H\x1b[30;42m{:>7}\x1b[0m user      20   0 \x1b[1m{:>3}.{}\x1b[0m \x1b[36m{:>3}.{}\x1b[0m  0:{:02}.{:02} /usr/bin/process --flag\x1b[K",
+                    row,
+                    rng.next(99999),
+                    cpu / 10,
+                    cpu % 10,
+                    mem / 10,
+                    mem % 10,
+                    rng.next(60),
+                    rng.next(100),
+                )
+                .as_bytes(),
+            );
+        }
+    }
+    out.extend_from_slice(b"\x1b[?25h\x1b[?1049l");
+    out
+}
+
+fn feed(terminal: &mut Terminal, bytes: &[u8]) {
+    for chunk in bytes.chunks(CHUNK) {
+        terminal.advance(chunk);
+    }
+}
+
+fn output(c: &mut Criterion) {
+    let workloads = [
+        ("ascii_flood", ascii_flood()),
+        ("sgr_heavy", sgr_heavy()),
+        ("hangul", hangul()),
+        ("htop_redraw", htop_redraw()),
+    ];
+
+    let mut group = c.benchmark_group("output");
+    for (name, bytes) in &workloads {
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), bytes, |b, bytes| {
+            let mut terminal = Terminal::new(ROWS, COLS);
+            b.iter(|| feed(&mut terminal, black_box(bytes)));
+        });
+    }
+    group.finish();
+}
+
+// Dragging the window edge: each step reflows the scrollback and rebuilds
+// the render buffer
+fn resize_storm(c: &mut Criterion) {
+    let sizes: Vec<(usize, usize)> = (0..40).map(|i| (30 + i % 25, 60 + (i * 7) % 100)).collect();
+
+    let mut group = c.benchmark_group("resize_storm");
+    group.throughput(Throughput::Elements(sizes.len() as u64 + 1)); // + the way back
+    for (name, bytes) in [("ascii", ascii_flood()), ("hangul", hangul())] {
+        group.bench_function(name, |b| {
+            let mut terminal = Terminal::new(ROWS, COLS);
+            feed(&mut terminal, &bytes);
+            b.iter(|| {
+                for &(rows, cols) in &sizes {
+                    terminal.resize(rows, cols);
+                }
+                terminal.resize(ROWS, COLS);
+            });
+        });
+    }
+    group.finish();
+}
+
+// A progress bar redrawing one line under a long scrollback: the rebuild
+// after each update, against laying out every line again
+fn render(c: &mut Criterion) {
+    let mut terminal = Terminal::new(ROWS, COLS);
+    terminal.state().lock().unwrap().history_lines = 5000;
+    feed(&mut terminal, &sgr_heavy()[..TARGET / 4]);
+
+    let mut group = c.benchmark_group("render");
+    for (name, full) in [("progress_line", false), ("full_rebuild", true)] {
+        group.bench_function(name, |b| {
+            let mut percent = 0;
+            b.iter(|| {
+                percent = (percent + 1) % 100;
+                terminal.advance(
+                    format!("\r[{:<50}] {:>2}%", "#".repeat(percent / 2), percent).as_bytes(),
+                );
+                let mut state = terminal.state().lock().unwrap();
+                if full {
+                    state.invalidate_render_buffer();
+                }
+                state.force_update_render_buffer();
+                black_box(state.render_buffer.len());
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, output, resize_storm, render);
+criterion_main!(benches);
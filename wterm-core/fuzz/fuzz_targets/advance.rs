@@ -0,0 +1,13 @@
+#![no_main]
+// Arbitrary program output on a 24x80 terminal
+use libfuzzer_sys::fuzz_target;
+use wterm_core::Terminal;
+use wterm_core_fuzz::{run, Step};
+
+fuzz_target!(|data: &[u8]| {
+    let mut terminal = Terminal::new(24, 80);
+    for chunk in data.chunks(64) {
+        run(&mut terminal, &Step::Output(chunk.to_vec()));
+    }
+    run(&mut terminal, &Step::Render);
+});
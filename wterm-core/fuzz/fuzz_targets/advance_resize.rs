@@ -0,0 +1,13 @@
+#![no_main]
+// Output interleaved with resizes (including to 0), repaints and scrolling
+use libfuzzer_sys::fuzz_target;
+use wterm_core::Terminal;
+use wterm_core_fuzz::{run, Step};
+
+fuzz_target!(|input: (u8, u8, Vec<Step>)| {
+    let (rows, cols, steps) = input;
+    let mut terminal = Terminal::new(rows as usize, cols as usize);
+    for step in &steps {
+        run(&mut terminal, step);
+    }
+});
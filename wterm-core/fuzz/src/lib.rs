@@ -0,0 +1,103 @@
+// Shared by the fuzz targets: what the UI does with a terminal between reads,
+// and the invariants that must hold after every step
+use arbitrary::Arbitrary;
+use wterm_core::state::TerminalState;
+use wterm_core::Terminal;
+
+#[derive(Arbitrary, Debug)]
+pub enum Step {
+    Output(Vec<u8>),
+    Resize { rows: u8, cols: u8 },
+    // What painting a frame does with the state
+    Render,
+    ScrollDisplay(i8),
+}
+
+pub fn run(terminal: &mut Terminal, step: &Step) {
+    match step {
+        Step::Output(bytes) => terminal.advance(bytes),
+        Step::Resize { rows, cols } => terminal.resize(*rows as usize, *cols as usize),
+        Step::Render => {
+            let mut state = terminal.state().lock().unwrap();
+            state.force_update_render_buffer();
+            let _ = wterm_core::snapshot::snapshot(&state);
+            check_render_buffer(&state);
+        }
+        Step::ScrollDisplay(lines) => {
+            let mut state = terminal.state().lock().unwrap();
+            if *lines < 0 {
+                state.scroll_display_down(lines.unsigned_abs() as usize);
+            } else {
+                state.scroll_display_up(*lines as usize);
+            }
+        }
+    }
+    check_invariants(&terminal.state().lock().unwrap());
+}
+
+pub fn check_invariants(state: &TerminalState) {
+    assert!(
+        state.rows > 0 && state.cols > 0,
+        "{}x{}",
+        state.rows,
+        state.cols
+    );
+    assert!(!state.main_buffer.is_empty(), "main_buffer is empty");
+    assert_eq!(
+        state.line_generations.len(),
+        state.main_buffer.len(),
+        "line_generations out of step with main_buffer"
+    );
+    assert!(
+        state.cursor_row < state.main_buffer.len(),
+        "cursor row {} outside main_buffer of {}",
+        state.cursor_row,
+        state.main_buffer.len()
+    );
+    assert!(
+        state.cursor_col <= state.main_buffer[state.cursor_row].len(),
+        "cursor col {} past its row of {}",
+        state.cursor_col,
+        state.main_buffer[state.cursor_row].len()
+    );
+    if state.is_alt_screen {
+        assert!(
+            state.cursor_row >= state.screen_start(),
+            "alt screen cursor row {} above the screen at {}",
+            state.cursor_row,
+            state.screen_start()
+        );
+    }
+    assert!(
+        state.scroll_region_top <= state.scroll_region_bottom
+            && state.scroll_region_bottom < state.rows,
+        "scroll region {}-{} on {} rows",
+        state.scroll_region_top,
+        state.scroll_region_bottom,
+        state.rows
+    );
+    assert!(
+        state.display_offset <= state.max_display_offset(),
+        "display offset {} past {}",
+        state.display_offset,
+        state.max_display_offset()
+    );
+}
+
+// A rebuild that reused unchanged lines must match laying out every line again;
+// a line changed without being stamped shows up here as stale rows
+pub fn check_render_buffer(state: &TerminalState) {
+    let mut fresh = state.clone();
+    fresh.invalidate_render_buffer();
+    fresh.force_update_render_buffer();
+    assert!(
+        fresh.render_buffer == state.render_buffer,
+        "reused render rows differ from a full rebuild"
+    );
+    assert_eq!(fresh.render_row_wrapped, state.render_row_wrapped);
+    assert_eq!(fresh.render_row_padded, state.render_row_padded);
+    assert_eq!(
+        (fresh.render_cursor_row, fresh.render_cursor_col),
+        (state.render_cursor_row, state.render_cursor_col)
+    );
+}
@@ -0,0 +1,48 @@
+// ANSI 256색 인덱스를 RGB로 변환 - macOS Terminal 호환
+pub fn ansi_256_to_rgb(color_idx: u8) -> (u8, u8, u8) {
+    match color_idx {
+        // Standard colors (0-15) - macOS Terminal compatible colors
+        0 => (0, 0, 0),        // Black
+        1 => (194, 54, 33),    // Red
+        2 => (37, 188, 36),    // Green
+        3 => (173, 173, 39),   // Yellow
+        4 => (73, 46, 225),    // Blue
+        5 => (211, 56, 211),   // Magenta
+        6 => (51, 187, 200),   // Cyan
+        7 => (203, 204, 205),  // White
+        8 => (129, 131, 131),  // Bright Black (Gray)
+        9 => (252, 57, 31),    // Bright Red
+        10 => (49, 231, 34),   // Bright Green
+        11 => (234, 236, 35),  // Bright Yellow
+        12 => (88, 51, 255),   // Bright Blue
+        13 => (249, 53, 248),  // Bright Magenta
+        14 => (20, 240, 240),  // Bright Cyan
+        15 => (233, 235, 235), // Bright White
+        // 216 color cube (16-231)
+        16..=231 => {
+            let idx = color_idx - 16;
+            let r = (idx / 36) % 6;
+            let g = (idx / 6) % 6;
+            let b = idx % 6;
+            let r = if r == 0 { 0 } else { 55 + r * 40 };
+            let g = if g == 0 { 0 } else { 55 + g * 40 };
+            let b = if b == 0 { 0 } else { 55 + b * 40 };
+            (r, g, b)
+        }
+        // Grayscale (232-255)
+        232..=255 => {
+            let gray = 8 + (color_idx - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
+
+// Cell color as set by SGR. Palette colors stay symbolic until painting so a
+// palette change also recolors text that is already on screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TermColor {
+    Foreground,  // Default foreground (SGR 39)
+    Background,  // Default background (SGR 49)
+    Indexed(u8), // 256-color index; 0-15 come from the palette
+    Rgb(u8, u8, u8),
+}
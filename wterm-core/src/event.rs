@@ -0,0 +1,22 @@
+// What the emulator tells whoever shows it. Called from the thread that
+// feeds output into the terminal, without the state lock held.
+pub trait EventListener: Send + Sync {
+    // OSC 0/2 set or cleared the title
+    fn title_changed(&self, _title: Option<&str>) {}
+
+    // BEL
+    fn bell(&self) {}
+
+    // Answers to queries (DECRQM), to be written back to the program
+    fn pty_write(&self, _bytes: &[u8]) {}
+
+    // The render buffer went from clean to dirty, or a synchronized update
+    // ended. Output arriving before the next frame's update_render_buffer_if_dirty
+    // does not call this again, so the UI paints at most once per frame.
+    fn repaint(&self) {}
+}
+
+// For callers that only look at the grid (tests, tools)
+pub struct NoopListener;
+
+impl EventListener for NoopListener {}
@@ -0,0 +1,25 @@
+//! Terminal emulator core of wterm: VT parsing, the grid with its scrollback
+//! and the terminal modes, without any GUI.
+//!
+//! ```
+//! use wterm_core::Terminal;
+//!
+//! let mut terminal = Terminal::new(24, 80);
+//! terminal.advance(b"hello\r\n\x1b[1mworld");
+//! assert_eq!(terminal.row_text(0), "hello");
+//! assert_eq!(terminal.cursor(), (1, 5));
+//! assert!(terminal.cell(1, 0).unwrap().color.bold);
+//! ```
+
+pub mod color;
+pub mod event;
+pub mod performer;
+pub mod selection;
+pub mod snapshot;
+pub mod state;
+mod terminal;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+pub use event::{EventListener, NoopListener};
+pub use terminal::Terminal;
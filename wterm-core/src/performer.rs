@@ -1,156 +1,128 @@
-use crate::terminal::state::{AnsiColor, TerminalCell, TerminalState};
-use crate::utils::color::ansi_256_to_rgb;
-use eframe::egui;
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use crate::color::TermColor;
+use crate::state::{AnsiColor, TerminalCell, TerminalState};
+use std::path::PathBuf;
+use std::time::Instant;
 use vte::{Params, Perform};
 
-// VTE Performer implementation
-pub struct TerminalPerformer {
-    state: Arc<Mutex<TerminalState>>,
-    egui_ctx: egui::Context,
-    last_repaint_time: Instant,
-    repaint_interval: Duration,
-    initial_repaints: u32, // Track initial repaints to skip throttling
+// What a chunk of output did, reported once the state lock is released
+#[derive(Default)]
+pub struct Damage {
+    pub changed: bool, // The grid, cursor or modes changed
+    pub bell: bool,
+    pub title: Option<Option<String>>, // Set by OSC 0/2; the inner None clears it
+    pub replies: Vec<u8>,              // Answers to queries, for the program
 }
 
-impl TerminalPerformer {
-    pub fn new(state: Arc<Mutex<TerminalState>>, egui_ctx: egui::Context) -> Self {
-        Self {
-            state,
-            egui_ctx,
-            last_repaint_time: Instant::now(),
-            repaint_interval: Duration::from_millis(33), // ~30fps limit for much better performance
-            initial_repaints: 0,                         // Start counting initial repaints
-        }
+// Whether a DEC private mode is set, for the modes that are tracked
+fn private_mode(state: &TerminalState, mode: u16) -> Option<bool> {
+    match mode {
+        25 => Some(state.cursor_visible),
+        1049 => Some(state.is_alt_screen),
+        2004 => Some(state.bracketed_paste),
+        2026 => Some(state.synchronized_update_remaining().is_some()),
+        _ => None,
     }
+}
 
-    // Request repaint only if enough time has passed (throttled)
-    fn request_repaint_throttled(&mut self) {
-        // Skip throttling for the first few repaints to ensure immediate initial rendering
-        if self.initial_repaints < 10 {
-            self.egui_ctx.request_repaint();
-            self.initial_repaints += 1;
-            self.last_repaint_time = Instant::now();
-            return;
-        }
-
-        let now = Instant::now();
-        let elapsed = now.duration_since(self.last_repaint_time);
-
-        // Use adaptive throttling - if we're getting lots of output, throttle more aggressively
-        let throttle_interval = if elapsed < Duration::from_millis(5) {
-            // Very frequent updates - throttle extremely aggressively for performance
-            Duration::from_millis(200) // ~5fps for extremely heavy output
-        } else if elapsed < Duration::from_millis(16) {
-            Duration::from_millis(100) // ~10fps for heavy output
-        } else {
-            self.repaint_interval // Normal throttling
-        };
+// VTE Performer implementation: applies a chunk of output to the state, which
+// the caller keeps locked for the whole chunk
+pub struct TerminalPerformer<'a> {
+    state: &'a mut TerminalState,
+    damage: Damage,
+}
 
-        if elapsed >= throttle_interval {
-            self.egui_ctx.request_repaint();
-            self.last_repaint_time = now;
+impl<'a> TerminalPerformer<'a> {
+    pub fn new(state: &'a mut TerminalState) -> Self {
+        Self {
+            state,
+            damage: Damage::default(),
         }
     }
 
-    // Request immediate repaint for important terminal events
-    fn request_repaint_immediate(&mut self) {
-        self.egui_ctx.request_repaint();
-        self.last_repaint_time = Instant::now();
+    // Flag the render buffer for the UI's next frame and hand back the damage
+    pub fn finish(self) -> Damage {
+        if self.damage.changed {
+            self.state.mark_render_dirty();
+        }
+        self.damage
     }
 }
 
-impl Perform for TerminalPerformer {
+impl Perform for TerminalPerformer<'_> {
     fn print(&mut self, c: char) {
-        if let Ok(mut state) = self.state.lock() {
-            // Don't filter leading spaces - let them through normally
-            // The PROMPT_EOL_MARK="" setting should handle the root cause
-
-            state.put_char(c);
-        } // Drop state lock before repaint
-
-        // Use throttled repaint for better performance
-        self.request_repaint_throttled();
+        // Don't filter leading spaces - let them through normally
+        // The PROMPT_EOL_MARK="" setting should handle the root cause
+        self.state.put_char(c);
+        self.damage.changed = true;
     }
 
     fn execute(&mut self, byte: u8) {
-        let (state_changed, needs_immediate_repaint) = if let Ok(mut state) = self.state.lock() {
+        let state_changed = {
+            let state = &mut *self.state;
             let mut changed = false;
-            let mut immediate = false;
 
             match byte {
                 b'\n' => {
                     state.newline();
                     changed = true;
-                    immediate = true; // Important event - repaint immediately
                 }
                 b'\r' => {
                     // Process carriage return but don't trigger newline
                     state.carriage_return();
                     changed = true;
-                    immediate = true; // Important event - repaint immediately
                 }
-                b'\x08' => {
-                    if !state.should_protect_from_arrow_key() {
-                        state.backspace();
-                        changed = true;
-                    }
+                b'\x08' if !state.should_protect_from_arrow_key() => {
+                    state.backspace();
+                    changed = true;
                 }
                 b'\x09' => {
                     let next_tab_stop = ((state.cursor_col / 8) + 1) * 8;
                     if next_tab_stop < state.cols {
                         state.cursor_col = next_tab_stop;
                     } else {
-                        state.cursor_col = state.cols - 1;
+                        state.cursor_col = state.cols.saturating_sub(1);
                     }
                     changed = true;
                 }
+                b'\x07' => {
+                    // BEL: the UI shows it on the tab
+                    state.bell = true;
+                    changed = true;
+                }
                 b'\x0c' => {
                     state.clear_arrow_key_protection();
                     state.clear_screen();
                     changed = true;
-                    immediate = true; // Clear screen - repaint immediately
                 }
-                b'\x7f' => {
-                    if !state.should_protect_from_arrow_key() {
-                        state.backspace();
-                        changed = true;
-                    }
+                b'\x7f' if !state.should_protect_from_arrow_key() => {
+                    state.backspace();
+                    changed = true;
                 }
                 b'\x84' => {
                     // IND (Index) - Move cursor down one line, scroll if at bottom
                     state.index_down();
                     changed = true;
-                    immediate = true;
                 }
                 b'\x85' => {
                     // NEL (Next Line) - Move to beginning of next line, scroll if at bottom
                     state.next_line();
                     changed = true;
-                    immediate = true;
                 }
                 b'\x8D' => {
                     // RI (Reverse Index) - Move cursor up one line, scroll if at top
                     state.reverse_index();
                     changed = true;
-                    immediate = true;
                 }
                 _ => {}
             }
 
-            (changed, immediate)
-        } else {
-            (false, false)
-        }; // Drop state lock before repaint
+            changed
+        };
 
-        if state_changed {
-            if needs_immediate_repaint {
-                self.request_repaint_immediate();
-            } else {
-                self.request_repaint_throttled();
-            }
+        if byte == b'\x07' {
+            self.damage.bell = true;
         }
+        self.damage.changed |= state_changed;
     }
 
     fn hook(&mut self, _params: &Params, _intermediates: &[u8], _ignore: bool, _c: char) {
@@ -165,7 +137,7 @@ impl Perform for TerminalPerformer {
         // No-op
     }
 
-    fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
+    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
         // println!(
         //     "🖥️ DEBUG: VTE osc_dispatch - bell_terminated: {}, params: {:?}",
         //     bell_terminated,
@@ -174,11 +146,29 @@ impl Perform for TerminalPerformer {
         //         .map(|p| String::from_utf8_lossy(p))
         //         .collect::<Vec<_>>()
         // );
-        // No-op
+        match params.first() {
+            // OSC 0 / OSC 2: window title (OSC 1, the icon name, is ignored)
+            Some(&b"0") | Some(&b"2") if params.len() > 1 => {
+                let title = String::from_utf8_lossy(&params[1..].join(&b';')).into_owned();
+                let title = (!title.is_empty()).then_some(title);
+                self.state.title = title.clone();
+                self.damage.title = Some(title);
+                self.damage.changed = true;
+            }
+            // OSC 7: current working directory as a file:// URL
+            Some(&b"7") if params.len() > 1 => {
+                let url = params[1..].join(&b';');
+                if let Some(dir) = parse_osc7_directory(&url) {
+                    self.state.working_directory = Some(dir);
+                }
+            }
+            _ => {}
+        }
     }
 
     fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], _ignore: bool, c: char) {
-        let state_changed = if let Ok(mut state) = self.state.lock() {
+        let state_changed = {
+            let state = &mut *self.state;
             let mut state_changed = false;
 
             match c {
@@ -186,7 +176,7 @@ impl Perform for TerminalPerformer {
                     // CUP (Cursor Position) or HVP (Horizontal and Vertical Position)
                     let row = params.iter().next().unwrap_or(&[1])[0].saturating_sub(1) as usize;
                     let col = params.iter().nth(1).unwrap_or(&[1])[0].saturating_sub(1) as usize;
-                    state.move_cursor_to(row, col);
+                    state.move_cursor_on_screen(row, col);
                     state_changed = true;
                 }
                 'J' => {
@@ -200,10 +190,9 @@ impl Perform for TerminalPerformer {
 
                             // Clear from cursor position to end of current row
                             if cursor_row < state.main_buffer.len() {
-                                let row = &mut state.main_buffer[cursor_row];
-                                for col_idx in cursor_col..row.len() {
-                                    row[col_idx] = TerminalCell::default();
-                                }
+                                let row = state.line_mut(cursor_row);
+                                let start = cursor_col.min(row.len());
+                                row[start..].fill(TerminalCell::default());
                             }
 
                             // Clear all rows after current row
@@ -215,7 +204,7 @@ impl Perform for TerminalPerformer {
 
                             for row_idx in (cursor_row + 1)..end_row {
                                 if row_idx < state.main_buffer.len() {
-                                    state.main_buffer[row_idx].fill(TerminalCell::default());
+                                    state.line_mut(row_idx).fill(TerminalCell::default());
                                 }
                             }
                             state.mark_render_dirty();
@@ -227,21 +216,17 @@ impl Perform for TerminalPerformer {
                             let cursor_col = state.cursor_col;
 
                             // Clear all rows before cursor row
-                            let start_row = if state.is_alt_screen { 0 } else { 0 };
-                            for row_idx in start_row..cursor_row {
+                            for row_idx in 0..cursor_row {
                                 if row_idx < state.main_buffer.len() {
-                                    state.main_buffer[row_idx].fill(TerminalCell::default());
+                                    state.line_mut(row_idx).fill(TerminalCell::default());
                                 }
                             }
 
                             // Clear from start of cursor row to cursor position
                             if cursor_row < state.main_buffer.len() {
-                                let row = &mut state.main_buffer[cursor_row];
-                                for col_idx in 0..=cursor_col {
-                                    if col_idx < row.len() {
-                                        row[col_idx] = TerminalCell::default();
-                                    }
-                                }
+                                let row = state.line_mut(cursor_row);
+                                let end = (cursor_col + 1).min(row.len());
+                                row[..end].fill(TerminalCell::default());
                             }
                             state.mark_render_dirty();
                             state_changed = true;
@@ -253,16 +238,13 @@ impl Perform for TerminalPerformer {
                                 let visible_rows = state.rows.min(state.main_buffer.len());
                                 for row_idx in 0..visible_rows {
                                     if row_idx < state.main_buffer.len() {
-                                        state.main_buffer[row_idx].fill(TerminalCell::default());
+                                        state.line_mut(row_idx).fill(TerminalCell::default());
                                     }
                                 }
                             } else {
                                 // In main screen mode, clear everything
-                                let cols = state.cols;
-                                state.main_buffer.clear();
-                                state
-                                    .main_buffer
-                                    .push_back(vec![TerminalCell::default(); cols]);
+                                state.clear_lines();
+                                state.push_line();
                             }
                             state.move_cursor_to(0, 0);
                             state.mark_render_dirty();
@@ -271,11 +253,8 @@ impl Perform for TerminalPerformer {
                         3 => {
                             // Clear entire screen and scrollback buffer
                             // Both modes: clear everything
-                            let cols = state.cols;
-                            state.main_buffer.clear();
-                            state
-                                .main_buffer
-                                .push_back(vec![TerminalCell::default(); cols]);
+                            state.clear_lines();
+                            state.push_line();
                             state.move_cursor_to(0, 0);
                             state.mark_render_dirty();
                             state_changed = true;
@@ -293,22 +272,18 @@ impl Perform for TerminalPerformer {
                     if cursor_row >= state.main_buffer.len() {
                         return;
                     }
-                    let line = &mut state.main_buffer[cursor_row];
+                    let line = state.line_mut(cursor_row);
 
                     match param {
                         0 => {
                             // Clear from cursor to end of line
-                            for col in cursor_col..line.len() {
-                                line[col] = TerminalCell::default();
-                            }
+                            let start = cursor_col.min(line.len());
+                            line[start..].fill(TerminalCell::default());
                         }
                         1 => {
                             // Clear from start of line to cursor
-                            for col in 0..=cursor_col {
-                                if col < line.len() {
-                                    line[col] = TerminalCell::default();
-                                }
-                            }
+                            let end = (cursor_col + 1).min(line.len());
+                            line[..end].fill(TerminalCell::default());
                         }
                         2 => {
                             // Clear entire line
@@ -330,26 +305,29 @@ impl Perform for TerminalPerformer {
                 'B' => {
                     // CUD (Cursor Down) - ALWAYS ALLOW cursor movement
                     let count = params.iter().next().unwrap_or(&[1])[0] as usize;
+                    let count = if count == 0 { 1 } else { count }; // ANSI standard: 0 means 1
                     let rows = if state.is_alt_screen {
                         state.rows.min(state.main_buffer.len())
                     } else {
                         state.main_buffer.len()
                     };
-                    state.cursor_row = (state.cursor_row + count).min(rows - 1);
+                    state.cursor_row = (state.cursor_row + count).min(rows.saturating_sub(1));
                     state.set_arrow_key_protection();
                     state_changed = true;
                 }
                 'C' => {
                     // CUF (Cursor Forward) - ALWAYS ALLOW cursor movement
                     let count = params.iter().next().unwrap_or(&[1])[0] as usize;
+                    let count = if count == 0 { 1 } else { count }; // ANSI standard: 0 means 1
                     let cols = state.cols;
-                    state.cursor_col = (state.cursor_col + count).min(cols - 1);
+                    state.cursor_col = (state.cursor_col + count).min(cols.saturating_sub(1));
                     state.set_arrow_key_protection();
                     state_changed = true;
                 }
                 'D' => {
                     // CUB (Cursor Backward) - ALWAYS ALLOW cursor movement
                     let count = params.iter().next().unwrap_or(&[1])[0] as usize;
+                    let count = if count == 0 { 1 } else { count }; // ANSI standard: 0 means 1
                     state.cursor_col = state.cursor_col.saturating_sub(count);
                     state.set_arrow_key_protection();
                     state_changed = true;
@@ -376,106 +354,89 @@ impl Perform for TerminalPerformer {
                                     24 => state.current_color.underline = false, // Not underlined
                                     27 => state.current_color.reverse = false, // Not reversed
                                     // Foreground colors (8-color) - macOS Terminal compatible
-                                    30 => state.current_color.foreground = ansi_256_to_rgb(0), // Black
-                                    31 => state.current_color.foreground = ansi_256_to_rgb(1), // Red
-                                    32 => state.current_color.foreground = ansi_256_to_rgb(2), // Green
-                                    33 => state.current_color.foreground = ansi_256_to_rgb(3), // Yellow
-                                    34 => state.current_color.foreground = ansi_256_to_rgb(4), // Blue
-                                    35 => state.current_color.foreground = ansi_256_to_rgb(5), // Magenta
-                                    36 => state.current_color.foreground = ansi_256_to_rgb(6), // Cyan
-                                    37 => state.current_color.foreground = ansi_256_to_rgb(7), // White
+                                    30 => state.current_color.foreground = TermColor::Indexed(0), // Black
+                                    31 => state.current_color.foreground = TermColor::Indexed(1), // Red
+                                    32 => state.current_color.foreground = TermColor::Indexed(2), // Green
+                                    33 => state.current_color.foreground = TermColor::Indexed(3), // Yellow
+                                    34 => state.current_color.foreground = TermColor::Indexed(4), // Blue
+                                    35 => state.current_color.foreground = TermColor::Indexed(5), // Magenta
+                                    36 => state.current_color.foreground = TermColor::Indexed(6), // Cyan
+                                    37 => state.current_color.foreground = TermColor::Indexed(7), // White
                                     // Bright foreground colors
-                                    90 => state.current_color.foreground = ansi_256_to_rgb(8), // Bright Black
-                                    91 => state.current_color.foreground = ansi_256_to_rgb(9), // Bright Red
-                                    92 => state.current_color.foreground = ansi_256_to_rgb(10), // Bright Green
-                                    93 => state.current_color.foreground = ansi_256_to_rgb(11), // Bright Yellow
-                                    94 => state.current_color.foreground = ansi_256_to_rgb(12), // Bright Blue
-                                    95 => state.current_color.foreground = ansi_256_to_rgb(13), // Bright Magenta
-                                    96 => state.current_color.foreground = ansi_256_to_rgb(14), // Bright Cyan
-                                    97 => state.current_color.foreground = ansi_256_to_rgb(15), // Bright White
+                                    90 => state.current_color.foreground = TermColor::Indexed(8), // Bright Black
+                                    91 => state.current_color.foreground = TermColor::Indexed(9), // Bright Red
+                                    92 => state.current_color.foreground = TermColor::Indexed(10), // Bright Green
+                                    93 => state.current_color.foreground = TermColor::Indexed(11), // Bright Yellow
+                                    94 => state.current_color.foreground = TermColor::Indexed(12), // Bright Blue
+                                    95 => state.current_color.foreground = TermColor::Indexed(13), // Bright Magenta
+                                    96 => state.current_color.foreground = TermColor::Indexed(14), // Bright Cyan
+                                    97 => state.current_color.foreground = TermColor::Indexed(15), // Bright White
                                     // Background colors (40-47)
-                                    40 => state.current_color.background = ansi_256_to_rgb(0), // Black
-                                    41 => state.current_color.background = ansi_256_to_rgb(1), // Red
-                                    42 => state.current_color.background = ansi_256_to_rgb(2), // Green
-                                    43 => state.current_color.background = ansi_256_to_rgb(3), // Yellow
-                                    44 => state.current_color.background = ansi_256_to_rgb(4), // Blue
-                                    45 => state.current_color.background = ansi_256_to_rgb(5), // Magenta
-                                    46 => state.current_color.background = ansi_256_to_rgb(6), // Cyan
-                                    47 => state.current_color.background = ansi_256_to_rgb(7), // White
+                                    40 => state.current_color.background = TermColor::Indexed(0), // Black
+                                    41 => state.current_color.background = TermColor::Indexed(1), // Red
+                                    42 => state.current_color.background = TermColor::Indexed(2), // Green
+                                    43 => state.current_color.background = TermColor::Indexed(3), // Yellow
+                                    44 => state.current_color.background = TermColor::Indexed(4), // Blue
+                                    45 => state.current_color.background = TermColor::Indexed(5), // Magenta
+                                    46 => state.current_color.background = TermColor::Indexed(6), // Cyan
+                                    47 => state.current_color.background = TermColor::Indexed(7), // White
                                     // Bright background colors (100-107)
-                                    100 => state.current_color.background = ansi_256_to_rgb(8), // Bright Black
-                                    101 => state.current_color.background = ansi_256_to_rgb(9), // Bright Red
-                                    102 => state.current_color.background = ansi_256_to_rgb(10), // Bright Green
-                                    103 => state.current_color.background = ansi_256_to_rgb(11), // Bright Yellow
-                                    104 => state.current_color.background = ansi_256_to_rgb(12), // Bright Blue
-                                    105 => state.current_color.background = ansi_256_to_rgb(13), // Bright Magenta
-                                    106 => state.current_color.background = ansi_256_to_rgb(14), // Bright Cyan
-                                    107 => state.current_color.background = ansi_256_to_rgb(15), // Bright White
+                                    100 => state.current_color.background = TermColor::Indexed(8), // Bright Black
+                                    101 => state.current_color.background = TermColor::Indexed(9), // Bright Red
+                                    102 => state.current_color.background = TermColor::Indexed(10), // Bright Green
+                                    103 => state.current_color.background = TermColor::Indexed(11), // Bright Yellow
+                                    104 => state.current_color.background = TermColor::Indexed(12), // Bright Blue
+                                    105 => state.current_color.background = TermColor::Indexed(13), // Bright Magenta
+                                    106 => state.current_color.background = TermColor::Indexed(14), // Bright Cyan
+                                    107 => state.current_color.background = TermColor::Indexed(15), // Bright White
                                     // Default colors
-                                    39 => {
-                                        state.current_color.foreground =
-                                            egui::Color32::from_rgb(203, 204, 205)
-                                    } // Default foreground
-                                    49 => {
-                                        state.current_color.background = egui::Color32::TRANSPARENT
-                                    } // Default background
+                                    39 => state.current_color.foreground = TermColor::Foreground, // Default foreground
+                                    49 => state.current_color.background = TermColor::Background, // Default background
                                     // Extended color sequences
-                                    38 => {
+                                    38 if i + 2 < param_vec.len() => {
                                         // Foreground color: 38;5;n or 38;2;r;g;b
-                                        if i + 2 < param_vec.len() {
-                                            if let Some(&subtype) = param_vec[i + 1].first() {
-                                                if subtype == 5 && i + 2 < param_vec.len() {
-                                                    // 256-color: ESC[38;5;nm
-                                                    if let Some(&color_idx) =
-                                                        param_vec[i + 2].first()
-                                                    {
-                                                        state.current_color.foreground =
-                                                            ansi_256_to_rgb(color_idx as u8);
-                                                        i += 2; // Skip the next 2 parameters
-                                                    }
-                                                } else if subtype == 2 && i + 4 < param_vec.len() {
-                                                    // RGB: ESC[38;2;r;g;bm
-                                                    if let (Some(&r), Some(&g), Some(&b)) = (
-                                                        param_vec[i + 2].first(),
-                                                        param_vec[i + 3].first(),
-                                                        param_vec[i + 4].first(),
-                                                    ) {
-                                                        state.current_color.foreground =
-                                                            egui::Color32::from_rgb(
-                                                                r as u8, g as u8, b as u8,
-                                                            );
-                                                        i += 4; // Skip the next 4 parameters
-                                                    }
+                                        if let Some(&subtype) = param_vec[i + 1].first() {
+                                            if subtype == 5 && i + 2 < param_vec.len() {
+                                                // 256-color: ESC[38;5;nm
+                                                if let Some(&color_idx) = param_vec[i + 2].first() {
+                                                    state.current_color.foreground =
+                                                        TermColor::Indexed(color_idx as u8);
+                                                    i += 2; // Skip the next 2 parameters
+                                                }
+                                            } else if subtype == 2 && i + 4 < param_vec.len() {
+                                                // RGB: ESC[38;2;r;g;bm
+                                                if let (Some(&r), Some(&g), Some(&b)) = (
+                                                    param_vec[i + 2].first(),
+                                                    param_vec[i + 3].first(),
+                                                    param_vec[i + 4].first(),
+                                                ) {
+                                                    state.current_color.foreground =
+                                                        TermColor::Rgb(r as u8, g as u8, b as u8);
+                                                    i += 4; // Skip the next 4 parameters
                                                 }
                                             }
                                         }
                                     }
-                                    48 => {
+                                    48 if i + 2 < param_vec.len() => {
                                         // Background color: 48;5;n or 48;2;r;g;b
-                                        if i + 2 < param_vec.len() {
-                                            if let Some(&subtype) = param_vec[i + 1].first() {
-                                                if subtype == 5 && i + 2 < param_vec.len() {
-                                                    // 256-color: ESC[48;5;nm
-                                                    if let Some(&color_idx) =
-                                                        param_vec[i + 2].first()
-                                                    {
-                                                        state.current_color.background =
-                                                            ansi_256_to_rgb(color_idx as u8);
-                                                        i += 2; // Skip the next 2 parameters
-                                                    }
-                                                } else if subtype == 2 && i + 4 < param_vec.len() {
-                                                    // RGB: ESC[48;2;r;g;bm
-                                                    if let (Some(&r), Some(&g), Some(&b)) = (
-                                                        param_vec[i + 2].first(),
-                                                        param_vec[i + 3].first(),
-                                                        param_vec[i + 4].first(),
-                                                    ) {
-                                                        state.current_color.background =
-                                                            egui::Color32::from_rgb(
-                                                                r as u8, g as u8, b as u8,
-                                                            );
-                                                        i += 4; // Skip the next 4 parameters
-                                                    }
+                                        if let Some(&subtype) = param_vec[i + 1].first() {
+                                            if subtype == 5 && i + 2 < param_vec.len() {
+                                                // 256-color: ESC[48;5;nm
+                                                if let Some(&color_idx) = param_vec[i + 2].first() {
+                                                    state.current_color.background =
+                                                        TermColor::Indexed(color_idx as u8);
+                                                    i += 2; // Skip the next 2 parameters
+                                                }
+                                            } else if subtype == 2 && i + 4 < param_vec.len() {
+                                                // RGB: ESC[48;2;r;g;bm
+                                                if let (Some(&r), Some(&g), Some(&b)) = (
+                                                    param_vec[i + 2].first(),
+                                                    param_vec[i + 3].first(),
+                                                    param_vec[i + 4].first(),
+                                                ) {
+                                                    state.current_color.background =
+                                                        TermColor::Rgb(r as u8, g as u8, b as u8);
+                                                    i += 4; // Skip the next 4 parameters
                                                 }
                                             }
                                         }
@@ -505,10 +466,24 @@ impl Perform for TerminalPerformer {
                                 }
                                 25 => {
                                     // Cursor visibility mode
+                                    state.cursor_visible = c == 'h';
+                                    state_changed = true;
+                                }
+                                2004 => {
+                                    // Bracketed paste mode
+                                    state.bracketed_paste = c == 'h';
+                                }
+                                2026 => {
+                                    // Synchronized output: the UI keeps showing the
+                                    // last frame until the update ends
                                     if c == 'h' {
-                                        state.cursor_visible = true;
+                                        // A new one unless one is still running; one
+                                        // that timed out doesn't count
+                                        if state.synchronized_update_remaining().is_none() {
+                                            state.synchronized_update = Some(Instant::now());
+                                        }
                                     } else {
-                                        state.cursor_visible = false;
+                                        state.synchronized_update = None;
                                     }
                                     state_changed = true;
                                 }
@@ -528,15 +503,7 @@ impl Perform for TerminalPerformer {
                                 }
                             }
                         } else {
-                            // Standard mode sequences (ESC[...h/l)
-                            match mode {
-                                2004 => {
-                                    // Bracketed paste mode - silently ignore
-                                }
-                                _ => {
-                                    // Silently ignore other standard modes
-                                }
-                            }
+                            // Standard mode sequences (ESC[...h/l) - silently ignore
                         }
                     }
                 }
@@ -548,14 +515,14 @@ impl Perform for TerminalPerformer {
                     } else {
                         state.main_buffer.len()
                     };
-                    state.cursor_row = row.min(rows - 1);
+                    state.cursor_row = row.min(rows.saturating_sub(1));
                     state_changed = true;
                 }
                 'G' => {
                     // CHA (Cursor Horizontal Absolute)
                     let col = params.iter().next().unwrap_or(&[1])[0].saturating_sub(1) as usize;
                     let cols = state.cols;
-                    state.cursor_col = col.min(cols - 1);
+                    state.cursor_col = col.min(cols.saturating_sub(1));
                     state_changed = true;
                 }
                 't' => {
@@ -569,50 +536,47 @@ impl Perform for TerminalPerformer {
                 }
                 'r' => {
                     // DECSTBM (DEC Set Top and Bottom Margins) - Set scrolling region
-                    let top = params.iter().next().and_then(|p| p.get(0)).unwrap_or(&1);
+                    let top = params.iter().next().and_then(|p| p.first()).unwrap_or(&1);
                     let default_bottom = state.rows as u16;
                     let bottom = params
                         .iter()
                         .nth(1)
-                        .and_then(|p| p.get(0))
+                        .and_then(|p| p.first())
                         .unwrap_or(&default_bottom);
 
-                    println!(
-                        "🎯 DECSTBM: Setting scroll region top={}, bottom={} (terminal size={}x{})",
-                        top, bottom, state.rows, state.cols
-                    );
                     state.set_scroll_region(*top as usize, *bottom as usize);
                     state_changed = true;
                 }
                 'S' => {
                     // SU (Scroll Up) - Scroll up N lines in scrolling region
-                    let lines = params.iter().next().and_then(|p| p.get(0)).unwrap_or(&1);
+                    let lines = params.iter().next().and_then(|p| p.first()).unwrap_or(&1);
+                    let lines = (*lines as usize).max(1); // 0 means 1
 
-                    state.scroll_up_in_region(*lines as usize);
+                    state.scroll_up_in_region(lines);
                     state_changed = true;
                 }
                 'T' => {
                     // SD (Scroll Down) - Scroll down N lines in scrolling region
-                    let lines = params.iter().next().and_then(|p| p.get(0)).unwrap_or(&1);
+                    let lines = params.iter().next().and_then(|p| p.first()).unwrap_or(&1);
+                    let lines = (*lines as usize).max(1); // 0 means 1
 
-                    state.scroll_down_in_region(*lines as usize);
+                    state.scroll_down_in_region(lines);
                     state_changed = true;
                 }
                 'X' => {
                     // ECH (Erase Character) - Erase N characters from cursor position
                     let count = params.iter().next().unwrap_or(&[1])[0] as usize;
+                    let count = if count == 0 { 1 } else { count }; // ANSI standard: 0 means 1
                     let cursor_col = state.cursor_col;
                     let cols = state.cols;
                     let row_idx = state.cursor_row;
 
                     if row_idx < state.main_buffer.len() {
-                        let line = &mut state.main_buffer[row_idx];
+                        let line = state.line_mut(row_idx);
 
                         for i in 0..count {
-                            if cursor_col + i < cols {
-                                if (cursor_col + i) < line.len() {
-                                    line[cursor_col + i] = TerminalCell::default();
-                                }
+                            if cursor_col + i < cols && (cursor_col + i) < line.len() {
+                                line[cursor_col + i] = TerminalCell::default();
                             }
                         }
                     }
@@ -633,10 +597,6 @@ impl Perform for TerminalPerformer {
                 }
                 's' => {
                     // Save cursor position (ANSI.SYS compatible)
-                    println!(
-                        "💾 CSI s: Saving cursor ({}, {})",
-                        state.cursor_row, state.cursor_col
-                    );
                     if state.is_alt_screen {
                         state.saved_cursor_alt = (state.cursor_row, state.cursor_col);
                     } else {
@@ -651,10 +611,26 @@ impl Perform for TerminalPerformer {
                     } else {
                         state.saved_cursor_main
                     };
-                    println!("🔄 CSI u: Restoring cursor to ({}, {})", row, col);
                     state.move_cursor_to(row, col);
                     state_changed = true;
                 }
+                'p' if intermediates.contains(&b'$') => {
+                    // DECRQM (Request Mode): reply CSI [?] mode ; value $ y with
+                    // 1 = set, 2 = reset, 0 = not recognized
+                    let mode = params.iter().next().map_or(0, |param| param[0]);
+                    if intermediates.contains(&b'?') {
+                        let value =
+                            private_mode(state, mode).map_or(0, |set| if set { 1 } else { 2 });
+                        self.damage
+                            .replies
+                            .extend_from_slice(format!("\x1b[?{};{}$y", mode, value).as_bytes());
+                    } else {
+                        // Standard modes are all ignored
+                        self.damage
+                            .replies
+                            .extend_from_slice(format!("\x1b[{};0$y", mode).as_bytes());
+                    }
+                }
                 _ => {
                     // Silently ignore unknown CSI sequences
                     // This helps with compatibility with complex prompts
@@ -662,18 +638,14 @@ impl Perform for TerminalPerformer {
             }
 
             state_changed
-        } else {
-            false
-        }; // Drop state lock before repaint
+        };
 
-        // Signal repaint if state changed
-        if state_changed {
-            self.request_repaint_throttled();
-        }
+        self.damage.changed |= state_changed;
     }
 
     fn esc_dispatch(&mut self, _intermediates: &[u8], _ignore: bool, byte: u8) {
-        let state_changed = if let Ok(mut state) = self.state.lock() {
+        let state_changed = {
+            let state = &mut *self.state;
             let mut changed = false;
             match byte {
                 b'7' => {
@@ -709,12 +681,35 @@ impl Perform for TerminalPerformer {
             }
 
             changed
-        } else {
-            false
-        }; // Drop state lock before repaint
+        };
 
-        if state_changed {
-            self.request_repaint_throttled();
+        self.damage.changed |= state_changed;
+    }
+}
+
+// file://host/path with percent-encoding -> /path (the host is not checked)
+fn parse_osc7_directory(url: &[u8]) -> Option<PathBuf> {
+    let rest = url.strip_prefix(b"file://")?;
+    let path_start = rest.iter().position(|&b| b == b'/')?;
+    let path = &rest[path_start..];
+
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut i = 0;
+    while i < path.len() {
+        let hex = path
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (path[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
         }
     }
+    Some(PathBuf::from(String::from_utf8(decoded).ok()?))
 }
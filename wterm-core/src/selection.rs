@@ -0,0 +1,312 @@
+use crate::state::TerminalState;
+
+// Characters that end a word for double-click selection
+pub const DEFAULT_WORD_SEPARATORS: &str = ",│`|:\"' ()[]{}<>\t";
+
+// A cell position in render_buffer coordinates (scrollback + screen)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SelectionPoint {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl SelectionPoint {
+    pub fn new(row: usize, col: usize) -> Self {
+        Self { row, col }
+    }
+}
+
+// Selection granularity
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectionType {
+    Simple, // Character-wise (drag)
+    Word,   // Double-click
+    Line,   // Triple-click
+    Block,  // Alt-drag: rectangular region over the cell grid
+}
+
+// Selection as made by the user: where it started and where the pointer is now
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selection {
+    pub ty: SelectionType,
+    pub anchor: SelectionPoint,
+    pub head: SelectionPoint,
+}
+
+// Selection resolved against the buffer contents (inclusive on both ends)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SelectionRange {
+    pub start: SelectionPoint,
+    pub end: SelectionPoint,
+    pub is_block: bool, // start/end are the top-left and bottom-right corners
+}
+
+impl Selection {
+    pub fn new(ty: SelectionType, point: SelectionPoint) -> Self {
+        Self {
+            ty,
+            anchor: point,
+            head: point,
+        }
+    }
+
+    // Move the free end of the selection (pointer drag)
+    pub fn update(&mut self, point: SelectionPoint) {
+        self.head = point;
+    }
+
+    // Shift the selection up when lines are dropped from the top of the buffer.
+    // Returns false if the selection scrolled out entirely.
+    pub fn rotate_up(&mut self, lines: usize) -> bool {
+        if self.anchor.row.max(self.head.row) < lines {
+            return false;
+        }
+        for point in [&mut self.anchor, &mut self.head] {
+            if point.row < lines {
+                *point = SelectionPoint::new(0, 0);
+            } else {
+                point.row -= lines;
+            }
+        }
+        true
+    }
+
+    fn ordered(&self) -> (SelectionPoint, SelectionPoint) {
+        if self.anchor <= self.head {
+            (self.anchor, self.head)
+        } else {
+            (self.head, self.anchor)
+        }
+    }
+
+    // Expand the selection to word/line boundaries and clamp it to the buffer
+    pub fn to_range(&self, state: &TerminalState) -> Option<SelectionRange> {
+        let total_rows = state.render_buffer.len();
+        if total_rows == 0 || state.cols == 0 {
+            return None;
+        }
+
+        let clamp = |p: SelectionPoint| SelectionPoint {
+            row: p.row.min(total_rows - 1),
+            col: p.col.min(state.cols - 1),
+        };
+
+        let (start, end) = self.ordered();
+        let (mut start, mut end) = (clamp(start), clamp(end));
+
+        match self.ty {
+            SelectionType::Simple => {}
+            SelectionType::Word => {
+                start = word_start(state, start);
+                end = word_end(state, end);
+            }
+            SelectionType::Line => {
+                start = SelectionPoint::new(line_start_row(state, start.row), 0);
+                end = SelectionPoint::new(line_end_row(state, end.row), state.cols - 1);
+            }
+            SelectionType::Block => {
+                // Rectangle spanned by the two corners; wide characters that
+                // straddle its edges are handled when extracting text.
+                return Some(SelectionRange {
+                    start: SelectionPoint::new(start.row, start.col.min(end.col)),
+                    end: SelectionPoint::new(end.row, start.col.max(end.col)),
+                    is_block: true,
+                });
+            }
+        }
+
+        // Never start on the right half of a wide character, and always
+        // include the right half when the left half is selected.
+        if cell_char(state, start) == Some('\u{0000}') && start.col > 0 {
+            start.col -= 1;
+        }
+        if cell_char(state, SelectionPoint::new(end.row, end.col + 1)) == Some('\u{0000}') {
+            end.col += 1;
+        }
+
+        Some(SelectionRange {
+            start,
+            end,
+            is_block: false,
+        })
+    }
+}
+
+impl SelectionRange {
+    // Selected column span (inclusive) of a row, if the row is part of the selection
+    pub fn columns_in_row(&self, row: usize, row_len: usize) -> Option<(usize, usize)> {
+        if row < self.start.row || row > self.end.row || row_len == 0 {
+            return None;
+        }
+        if self.is_block {
+            let last = self.end.col.min(row_len - 1);
+            return (self.start.col <= last).then_some((self.start.col, last));
+        }
+        let first = if row == self.start.row {
+            self.start.col
+        } else {
+            0
+        };
+        let last = if row == self.end.row {
+            self.end.col
+        } else {
+            row_len - 1
+        };
+        let last = last.min(row_len - 1);
+        (first <= last).then_some((first, last))
+    }
+
+    // Extract the selected text, joining soft-wrapped rows and skipping
+    // wide character continuation cells and the padding before a wrapped
+    // wide character.
+    pub fn text(&self, state: &TerminalState) -> String {
+        if self.is_block {
+            return self.block_text(state);
+        }
+
+        let mut text = String::new();
+        let last_row = self
+            .end
+            .row
+            .min(state.render_buffer.len().saturating_sub(1));
+
+        for row_idx in self.start.row..=last_row {
+            let row = &state.render_buffer[row_idx];
+            let mut line = String::new();
+            if let Some((first, last)) = self.columns_in_row(row_idx, row.len()) {
+                for (col, cell) in row.iter().enumerate().take(last + 1).skip(first) {
+                    if cell.ch != '\u{0000}' && !state.is_wrap_padding(row_idx, col) {
+                        line.push(cell.ch);
+                    }
+                }
+            }
+
+            let wrapped = state.is_render_row_wrapped(row_idx);
+            if wrapped && row_idx != last_row {
+                text.push_str(&line);
+            } else {
+                text.push_str(line.trim_end_matches(' '));
+                if row_idx != last_row {
+                    text.push('\n');
+                }
+            }
+        }
+
+        text
+    }
+
+    // Block text keeps column alignment: every line covers the same columns,
+    // and halves of wide characters cut by the block edge become spaces.
+    fn block_text(&self, state: &TerminalState) -> String {
+        let last_row = self
+            .end
+            .row
+            .min(state.render_buffer.len().saturating_sub(1));
+        let mut lines = Vec::new();
+
+        for row_idx in self.start.row..=last_row {
+            let row = &state.render_buffer[row_idx];
+            let mut line = String::new();
+            if let Some((first, last)) = self.columns_in_row(row_idx, row.len()) {
+                for col in first..=last {
+                    let ch = row[col].ch;
+                    if ch == '\u{0000}' {
+                        // Right half of a wide character starting left of the block
+                        if col == first {
+                            line.push(' ');
+                        }
+                        continue;
+                    }
+                    let continues_past_edge =
+                        col == last && row.get(col + 1).is_some_and(|c| c.ch == '\u{0000}');
+                    line.push(if continues_past_edge { ' ' } else { ch });
+                }
+            }
+            lines.push(line.trim_end_matches(' ').to_string());
+        }
+
+        lines.join("\n")
+    }
+}
+
+fn cell_char(state: &TerminalState, point: SelectionPoint) -> Option<char> {
+    state
+        .render_buffer
+        .get(point.row)
+        .and_then(|row| row.get(point.col))
+        .map(|cell| cell.ch)
+}
+
+fn is_word_char(state: &TerminalState, point: SelectionPoint) -> bool {
+    match cell_char(state, point) {
+        Some('\u{0000}') => true, // Right half of a wide character
+        Some(ch) => !state.word_separators.contains(ch),
+        None => false,
+    }
+}
+
+// Previous cell, following soft wraps back onto the previous row
+fn prev_point(state: &TerminalState, point: SelectionPoint) -> Option<SelectionPoint> {
+    if point.col > 0 {
+        Some(SelectionPoint::new(point.row, point.col - 1))
+    } else if point.row > 0 && state.is_render_row_wrapped(point.row - 1) {
+        Some(SelectionPoint::new(point.row - 1, state.cols - 1))
+    } else {
+        None
+    }
+}
+
+// Next cell, following soft wraps onto the next row
+fn next_point(state: &TerminalState, point: SelectionPoint) -> Option<SelectionPoint> {
+    if point.col + 1 < state.cols {
+        Some(SelectionPoint::new(point.row, point.col + 1))
+    } else if state.is_render_row_wrapped(point.row) && point.row + 1 < state.render_buffer.len() {
+        Some(SelectionPoint::new(point.row + 1, 0))
+    } else {
+        None
+    }
+}
+
+fn word_start(state: &TerminalState, point: SelectionPoint) -> SelectionPoint {
+    if !is_word_char(state, point) {
+        return point;
+    }
+    let mut current = point;
+    while let Some(prev) = prev_point(state, current) {
+        if !is_word_char(state, prev) {
+            break;
+        }
+        current = prev;
+    }
+    current
+}
+
+fn word_end(state: &TerminalState, point: SelectionPoint) -> SelectionPoint {
+    if !is_word_char(state, point) {
+        return point;
+    }
+    let mut current = point;
+    while let Some(next) = next_point(state, current) {
+        if !is_word_char(state, next) {
+            break;
+        }
+        current = next;
+    }
+    current
+}
+
+// First render row of the logical line containing `row`
+pub fn line_start_row(state: &TerminalState, mut row: usize) -> usize {
+    while row > 0 && state.is_render_row_wrapped(row - 1) {
+        row -= 1;
+    }
+    row
+}
+
+// Last render row of the logical line containing `row`
+pub fn line_end_row(state: &TerminalState, mut row: usize) -> usize {
+    while state.is_render_row_wrapped(row) && row + 1 < state.render_buffer.len() {
+        row += 1;
+    }
+    row
+}
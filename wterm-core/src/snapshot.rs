@@ -0,0 +1,124 @@
+use crate::color::TermColor;
+use crate::state::{AnsiColor, TerminalState};
+use std::fmt::Write;
+use unicode_width::UnicodeWidthChar;
+
+// Plain-text dump of the screen, for golden files and bug reports:
+//
+//   size 3x10 cursor 1,5
+//   modes: alt-screen bracketed-paste
+//   title: build
+//   |hello     |
+//   |한글      |
+//   |          |
+//   0:0-4 bold fg=1
+//
+// Rows are padded to the width; a wide character covers two columns. The
+// lines after the grid are runs of cells with non-default attributes,
+// as row:first-last (inclusive).
+pub fn snapshot(state: &TerminalState) -> String {
+    let mut out = String::new();
+    let screen_start = state.screen_start();
+    let _ = writeln!(
+        out,
+        "size {}x{} cursor {},{}",
+        state.rows,
+        state.cols,
+        state.cursor_row.saturating_sub(screen_start),
+        state.cursor_col
+    );
+
+    let mut modes = Vec::new();
+    if state.is_alt_screen {
+        modes.push("alt-screen".to_string());
+    }
+    if !state.cursor_visible {
+        modes.push("cursor-hidden".to_string());
+    }
+    if state.bracketed_paste {
+        modes.push("bracketed-paste".to_string());
+    }
+    if state.synchronized_update.is_some() {
+        modes.push("synchronized-update".to_string());
+    }
+    if state.scroll_region_top != 0 || state.scroll_region_bottom + 1 != state.rows {
+        modes.push(format!(
+            "scroll-region={}-{}",
+            state.scroll_region_top, state.scroll_region_bottom
+        ));
+    }
+    if !modes.is_empty() {
+        let _ = writeln!(out, "modes: {}", modes.join(" "));
+    }
+    if let Some(title) = &state.title {
+        let _ = writeln!(out, "title: {}", title);
+    }
+
+    let mut runs = String::new();
+    for row in 0..state.rows {
+        let line = state.main_buffer.get(screen_start + row);
+        let cell = |col: usize| {
+            line.and_then(|line| line.get(col))
+                .copied()
+                .unwrap_or_default()
+        };
+
+        let mut text = String::new();
+        let mut covered = false; // Right half of the previous wide character
+        for col in 0..state.cols {
+            let ch = cell(col).ch;
+            if ch == '\u{0000}' && covered {
+                covered = false;
+                continue;
+            }
+            covered = ch.width() == Some(2);
+            text.push(if ch.is_control() { ' ' } else { ch });
+        }
+        let _ = writeln!(out, "|{}|", text);
+
+        let mut col = 0;
+        while col < state.cols {
+            let color = cell(col).color;
+            let start = col;
+            while col < state.cols && cell(col).color == color {
+                col += 1;
+            }
+            if color != AnsiColor::default() {
+                let _ = writeln!(runs, "{}:{}-{} {}", row, start, col - 1, describe(&color));
+            }
+        }
+    }
+    out.push_str(&runs);
+    out
+}
+
+// "bold underline fg=1 bg=#102030"
+fn describe(color: &AnsiColor) -> String {
+    let mut parts = Vec::new();
+    for (set, name) in [
+        (color.bold, "bold"),
+        (color.italic, "italic"),
+        (color.underline, "underline"),
+        (color.reverse, "reverse"),
+    ] {
+        if set {
+            parts.push(name.to_string());
+        }
+    }
+    if color.foreground != TermColor::Foreground {
+        parts.push(format!("fg={}", describe_color(color.foreground)));
+    }
+    if color.background != TermColor::Background {
+        parts.push(format!("bg={}", describe_color(color.background)));
+    }
+    parts.join(" ")
+}
+
+fn describe_color(color: TermColor) -> String {
+    match color {
+        TermColor::Foreground => "default".to_string(),
+        TermColor::Background => "default".to_string(),
+        TermColor::Indexed(idx) => idx.to_string(),
+        TermColor::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+    }
+}
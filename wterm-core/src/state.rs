@@ -0,0 +1,1159 @@
+use crate::color::TermColor;
+use crate::selection::{Selection, DEFAULT_WORD_SEPARATORS};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use unicode_width::UnicodeWidthChar;
+
+pub const MAX_HISTORY_LINES: usize = 10000;
+pub const MAX_MAIN_BUFFER_COLS: usize = 1000; // Fixed width for main_buffer to preserve original data
+
+// A synchronized update (DEC 2026) that never ends is shown after this long
+pub const SYNCHRONIZED_UPDATE_TIMEOUT: Duration = Duration::from_millis(150);
+
+// ANSI 색상 정보를 저장하는 구조체
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnsiColor {
+    pub foreground: TermColor,
+    pub background: TermColor,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub reverse: bool,
+}
+
+impl Default for AnsiColor {
+    fn default() -> Self {
+        Self {
+            foreground: TermColor::Foreground,
+            background: TermColor::Background, // Window background shows through
+            bold: false,
+            italic: false,
+            underline: false,
+            reverse: false,
+        }
+    }
+}
+
+// 터미널 셀 정보 (문자 + 색상)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TerminalCell {
+    pub ch: char,
+    pub color: AnsiColor,
+}
+
+impl Default for TerminalCell {
+    fn default() -> Self {
+        Self {
+            ch: ' ',
+            color: AnsiColor::default(),
+        }
+    }
+}
+
+// A laid-out render_buffer row: cells, wrapped, padded and generation
+type RenderRow = (Vec<TerminalCell>, bool, bool, u64);
+
+// Terminal state structure with separated buffers
+#[derive(Clone)]
+pub struct TerminalState {
+    // Main buffer: stores the logical lines of the terminal history.
+    pub main_buffer: VecDeque<Vec<TerminalCell>>,
+    // Per main_buffer line: the generation of its last change. Lines newer than
+    // render_generation are the dirty ones.
+    pub line_generations: VecDeque<u64>,
+    pub generation: u64, // Bumped on every line change and every laid-out row
+    pub render_generation: u64, // generation when render_buffer was last rebuilt
+    // How each line was laid out by the last rebuild: (line generation, render
+    // rows), in main_buffer order, and the (cols, incremental_update) it was for
+    render_lines: Vec<(u64, usize)>,
+    render_layout: Option<(usize, bool)>,
+    // Lines dropped from the front of main_buffer since the last rebuild: the
+    // next one to go is render_lines[lines_dropped]. Their render rows leave
+    // the front of render_buffer at the next rebuild.
+    lines_dropped: usize,
+    render_rows_dropped: usize,
+
+    // Render buffer: stores the visual lines after reflow.
+    // This is what is actually displayed.
+    pub render_buffer: Vec<Vec<TerminalCell>>,
+    // Soft-wrap flag per render_buffer row: true if the row continues on the next one
+    pub render_row_wrapped: Vec<bool>,
+    // Per render_buffer row: true if its last cell is left blank because the
+    // wide character after it didn't fit and starts the next row
+    pub render_row_padded: Vec<bool>,
+    // Per render_buffer row: the generation it was laid out at. A row keeps its
+    // generation until its cells change, so the UI can reuse what it painted.
+    pub render_row_generations: Vec<u64>,
+    pub render_buffer_dirty: bool,
+    pub incremental_update: bool, // true = only process changed rows, false = full reflow
+
+    // Logical cursor position in the main_buffer.
+    pub cursor_row: usize,
+    pub cursor_col: usize,
+
+    // Visual cursor position in the render_buffer.
+    // This is calculated by update_render_buffer.
+    pub render_cursor_row: usize,
+    pub render_cursor_col: usize,
+
+    // Terminal dimensions (in characters).
+    pub rows: usize,
+    pub cols: usize,
+
+    pub current_color: AnsiColor,
+    pub arrow_key_pressed: bool,
+    pub arrow_key_time: Option<Instant>,
+
+    // Alternative screen mode (uses main_buffer but with screen size limits)
+    pub is_alt_screen: bool,
+    pub saved_cursor_main: (usize, usize),
+    pub saved_cursor_alt: (usize, usize),
+    pub cursor_visible: bool,
+    pub bracketed_paste: bool, // DECSET 2004: wrap pasted text in ESC[200~ ... ESC[201~
+
+    // Backup for main buffer when switching to alt screen
+    pub main_buffer_backup: Option<VecDeque<Vec<TerminalCell>>>,
+
+    // Scrolling region (DECSTBM)
+    pub scroll_region_top: usize, // Top line of scrolling region (0-based)
+    pub scroll_region_bottom: usize, // Bottom line of scrolling region (0-based)
+
+    // Viewport tracking for optimized render_buffer updates
+    pub visible_start_row: usize, // First visible row in render_buffer
+    pub visible_end_row: usize,   // Last visible row in render_buffer (exclusive)
+
+    // Scrollback position: render_buffer rows the viewport is scrolled up from
+    // the bottom (0 = following new output)
+    pub display_offset: usize,
+
+    // DEC mode 2026: when the current synchronized update began (None = not in one)
+    pub synchronized_update: Option<Instant>,
+
+    // Mouse selection in render_buffer coordinates
+    pub selection: Option<Selection>,
+    pub word_separators: String, // Characters that delimit words for double-click selection
+
+    // Lines kept in main_buffer before the oldest are dropped (scrollback.lines);
+    // never fewer than the screen has rows
+    pub history_lines: usize,
+
+    // Shell working directory reported via OSC 7, used to resolve relative file links
+    pub working_directory: Option<PathBuf>,
+
+    // Window/tab title from OSC 0 / OSC 2
+    pub title: Option<String>,
+    // BEL received and not yet shown by the UI
+    pub bell: bool,
+}
+
+impl TerminalState {
+    // Find the actual end of text in a row (excluding trailing spaces)
+    fn find_row_text_end(&self, row: &[TerminalCell]) -> usize {
+        row.iter()
+            .rposition(|cell| cell.ch != ' ' && cell.ch != '\u{0000}')
+            .map_or(0, |i| i + 1)
+    }
+
+    // Fast bulk copy for terminal cells using unsafe operations
+    unsafe fn fast_copy_cells(&self, src: &[TerminalCell], dst: &mut [TerminalCell], count: usize) {
+        debug_assert!(count <= src.len());
+        debug_assert!(count <= dst.len());
+        debug_assert!(count > 0);
+        std::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr(), count);
+    }
+
+    // Safe bulk copy wrapper with optional unsafe optimization
+    fn bulk_copy_cells(&self, src: &[TerminalCell], dst: &mut [TerminalCell]) -> usize {
+        let copy_len = src.len().min(dst.len());
+        if copy_len > 0 {
+            // Use optimized copy strategy based on size
+            if (100..=1000).contains(&copy_len) {
+                // Sweet spot for unsafe optimization
+                unsafe {
+                    self.fast_copy_cells(src, dst, copy_len);
+                }
+            } else {
+                // Use safe copy for very small or very large chunks
+                dst[..copy_len].copy_from_slice(&src[..copy_len]);
+            }
+        }
+        copy_len
+    }
+
+    fn next_generation(&mut self) -> u64 {
+        self.generation += 1;
+        self.generation
+    }
+
+    // Stamp main_buffer line `row` as changed. A line that already changed
+    // since the last rebuild keeps its stamp: no laid-out rows carry it.
+    pub fn touch_line(&mut self, row: usize) {
+        if self.is_line_dirty(row) {
+            return;
+        }
+        let generation = self.next_generation();
+        if let Some(stamp) = self.line_generations.get_mut(row) {
+            *stamp = generation;
+        }
+    }
+
+    // main_buffer line `row` for writing, stamped as changed
+    pub fn line_mut(&mut self, row: usize) -> &mut Vec<TerminalCell> {
+        self.touch_line(row);
+        &mut self.main_buffer[row]
+    }
+
+    // Whether main_buffer line `row` changed since render_buffer was rebuilt
+    pub fn is_line_dirty(&self, row: usize) -> bool {
+        self.line_generations
+            .get(row)
+            .is_none_or(|&generation| generation > self.render_generation)
+    }
+
+    // Lines are added and removed through these so line_generations stays
+    // parallel to main_buffer
+    pub fn push_line(&mut self) {
+        let generation = self.next_generation();
+        self.main_buffer
+            .push_back(vec![TerminalCell::default(); MAX_MAIN_BUFFER_COLS]);
+        self.line_generations.push_back(generation);
+    }
+
+    pub fn insert_line(&mut self, row: usize) {
+        let generation = self.next_generation();
+        self.main_buffer
+            .insert(row, vec![TerminalCell::default(); MAX_MAIN_BUFFER_COLS]);
+        self.line_generations.insert(row, generation);
+    }
+
+    pub fn remove_line(&mut self, row: usize) {
+        self.main_buffer.remove(row);
+        self.line_generations.remove(row);
+    }
+
+    pub fn pop_front_line(&mut self) {
+        self.main_buffer.pop_front();
+        self.line_generations.pop_front();
+    }
+
+    pub fn clear_lines(&mut self) {
+        self.main_buffer.clear();
+        self.line_generations.clear();
+    }
+
+    // Forget how lines were laid out: the next rebuild lays out every line
+    pub fn invalidate_render_buffer(&mut self) {
+        self.render_layout = None;
+        self.render_buffer_dirty = true;
+    }
+
+    // Every line changed (main_buffer was replaced wholesale)
+    fn restamp_lines(&mut self) {
+        self.line_generations.clear();
+        for _ in 0..self.main_buffer.len() {
+            let generation = self.next_generation();
+            self.line_generations.push_back(generation);
+        }
+    }
+
+    // Mark render_buffer as dirty for batch update
+    pub fn mark_render_dirty(&mut self) {
+        self.render_buffer_dirty = true;
+    }
+
+    // Update render_buffer from main_buffer's visible area, called by the UI
+    // once per frame. Held back while a synchronized update is in progress, so
+    // a half-drawn frame is never shown. Returns true if render_buffer was rebuilt.
+    pub fn update_render_buffer_if_dirty(&mut self) -> bool {
+        if self.synchronized_update_remaining().is_some() {
+            return false;
+        }
+        self.synchronized_update = None; // Over, or timed out
+        if !self.render_buffer_dirty {
+            return false;
+        }
+        self.force_update_render_buffer();
+        true
+    }
+
+    // Rebuild render_buffer now, synchronized update or not. For resize,
+    // tests and benchmarks; the UI goes through update_render_buffer_if_dirty.
+    pub fn force_update_render_buffer(&mut self) {
+        self.rebuild_render_buffer();
+        self.render_buffer_dirty = false;
+    }
+
+    // Time left before a synchronized update is shown anyway; None when there
+    // is nothing to hold back
+    pub fn synchronized_update_remaining(&self) -> Option<Duration> {
+        let started = self.synchronized_update?;
+        SYNCHRONIZED_UPDATE_TIMEOUT
+            .checked_sub(started.elapsed())
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    // Rebuild render_buffer; a viewport scrolled into history stays on the same
+    // text. Rows come in at the bottom and, once history is full, leave at the
+    // top, so the offset from the bottom grows by the rows appended.
+    fn rebuild_render_buffer(&mut self) {
+        let kept = self
+            .render_buffer
+            .len()
+            .saturating_sub(self.render_rows_dropped);
+        self.update_render_buffer();
+        if self.display_offset > 0 {
+            self.display_offset += self.render_buffer.len().saturating_sub(kept);
+        }
+        self.display_offset = self.display_offset.min(self.max_display_offset());
+    }
+
+    // main_buffer row at the top of the screen; the rows above are scrollback
+    pub fn screen_start(&self) -> usize {
+        self.main_buffer.len().saturating_sub(self.rows)
+    }
+
+    // Largest display_offset: the first render_buffer row at the top of the viewport
+    pub fn max_display_offset(&self) -> usize {
+        self.render_buffer.len().saturating_sub(self.rows)
+    }
+
+    pub fn scroll_display_up(&mut self, lines: usize) {
+        self.display_offset = (self.display_offset + lines).min(self.max_display_offset());
+    }
+
+    pub fn scroll_display_down(&mut self, lines: usize) {
+        self.display_offset = self.display_offset.saturating_sub(lines);
+    }
+
+    pub fn scroll_display_to_top(&mut self) {
+        self.display_offset = self.max_display_offset();
+    }
+
+    pub fn scroll_display_to_bottom(&mut self) {
+        self.display_offset = 0;
+    }
+
+    // Scroll so that a render_buffer row sits in the middle of the viewport
+    pub fn scroll_display_to_row(&mut self, row: usize) {
+        let top = row.saturating_sub(self.rows / 2);
+        self.display_offset = self.max_display_offset().saturating_sub(top);
+    }
+
+    // Scroll the least amount needed to bring a render_buffer row into view
+    pub fn scroll_display_to_include(&mut self, row: usize) {
+        let max = self.max_display_offset();
+        let top = max - self.display_offset.min(max);
+        if row < top {
+            self.display_offset = max - row.min(max);
+        } else if row >= top + self.rows {
+            let new_top = (row + 1).saturating_sub(self.rows).min(max);
+            self.display_offset = max - new_top;
+        }
+    }
+
+    // Update viewport information for optimized rendering
+    pub fn update_viewport(&mut self, visible_start: usize, visible_end: usize) {
+        self.visible_start_row = visible_start;
+        self.visible_end_row = visible_end;
+    }
+
+    // Bring render_buffer up to date with main_buffer. Lines that haven't
+    // changed since the last rebuild keep their render rows; only dirty lines
+    // (and the cursor line, for the cursor position) are laid out again.
+    pub fn update_render_buffer(&mut self) {
+        if self.line_generations.len() != self.main_buffer.len() {
+            self.restamp_lines();
+        }
+
+        // Rows can only be reused when they were laid out the same way
+        let layout = (self.cols, self.incremental_update);
+        let mut old_rows: Vec<Option<RenderRow>> = std::mem::take(&mut self.render_buffer)
+            .into_iter()
+            .zip(std::mem::take(&mut self.render_row_wrapped))
+            .zip(std::mem::take(&mut self.render_row_padded))
+            .zip(std::mem::take(&mut self.render_row_generations))
+            .map(|(((cells, wrapped), padded), generation)| {
+                Some((cells, wrapped, padded, generation))
+            })
+            .collect();
+        let mut old_lines = HashMap::new();
+        let laid_out: usize = self.render_lines.iter().map(|&(_, count)| count).sum();
+        if self.render_layout == Some(layout) && laid_out == old_rows.len() {
+            let mut first = 0;
+            for &(generation, count) in &self.render_lines {
+                old_lines.insert(generation, (first, count));
+                first += count;
+            }
+        }
+        self.render_lines.clear();
+        self.lines_dropped = 0;
+        self.render_rows_dropped = 0;
+
+        for row in 0..self.main_buffer.len() {
+            let generation = self.line_generations[row];
+            let start = self.render_buffer.len();
+            // The old rows of an unchanged line; a line copied from another
+            // shares its generation, and the copy is laid out afresh
+            let old = old_lines
+                .get(&generation)
+                .filter(|_| row != self.cursor_row)
+                .and_then(|&(first, count)| {
+                    old_rows[first..first + count]
+                        .iter_mut()
+                        .map(Option::take)
+                        .collect::<Option<Vec<_>>>()
+                });
+            if let Some(old) = old {
+                for (cells, wrapped, padded, row_generation) in old {
+                    self.render_buffer.push(cells);
+                    self.render_row_wrapped.push(wrapped);
+                    self.render_row_padded.push(padded);
+                    self.render_row_generations.push(row_generation);
+                }
+            } else {
+                if self.incremental_update {
+                    self.copy_line_without_reflow(row);
+                } else {
+                    self.reflow_line(row);
+                }
+                while self.render_row_generations.len() < self.render_buffer.len() {
+                    let generation = self.next_generation();
+                    self.render_row_generations.push(generation);
+                }
+            }
+            self.render_lines
+                .push((generation, self.render_buffer.len() - start));
+        }
+
+        self.render_layout = Some(layout);
+        self.render_generation = self.generation;
+        self.render_buffer_dirty = false;
+    }
+
+    // Lay out main_buffer line `row`, splitting it across as many render rows
+    // as the width needs
+    fn reflow_line(&mut self, row: usize) {
+        let source_row = &self.main_buffer[row];
+        let is_cursor_row = row == self.cursor_row;
+
+        // Find the actual end of text in this row
+        let text_end = self.find_row_text_end(source_row);
+
+        // Check if this row needs reflow based on actual text length
+        let needs_reflow = text_end > self.cols;
+
+        if !needs_reflow {
+            // Simple copy without reflow - only copy up to text end or cols
+            let mut render_row = vec![TerminalCell::default(); self.cols];
+            let copy_length = text_end.min(self.cols);
+            if copy_length > 0 {
+                self.bulk_copy_cells(&source_row[..copy_length], &mut render_row[..copy_length]);
+            }
+            self.render_buffer.push(render_row);
+            self.render_row_wrapped.push(false);
+            self.render_row_padded.push(false);
+
+            // If this is cursor row, record the render row
+            if is_cursor_row {
+                self.render_cursor_row = self.render_buffer.len() - 1;
+                self.render_cursor_col = self.cursor_col.min(self.cols);
+            }
+        } else {
+            // Reflow: split long row across multiple render rows
+            let mut source_col = 0;
+            let cursor_render_start = self.render_buffer.len(); // Remember where this row starts
+
+            while source_col < text_end {
+                let mut render_row = vec![TerminalCell::default(); self.cols];
+                let mut render_col = 0;
+
+                // Fill current render row up to cols width
+                while render_col < self.cols && source_col < text_end {
+                    // Skip null characters (wide char continuations)
+                    if source_row[source_col].ch == '\u{0000}' {
+                        source_col += 1;
+                        continue;
+                    }
+
+                    // Optimization: Try to bulk copy consecutive normal-width characters
+                    let start_source_col = source_col;
+                    let start_render_col = render_col;
+                    let mut consecutive_normal_chars = 0;
+
+                    // Count consecutive normal-width characters
+                    while render_col < self.cols && source_col < text_end {
+                        if source_row[source_col].ch == '\u{0000}' {
+                            break;
+                        }
+                        let char_width = source_row[source_col].ch.width().unwrap_or(1);
+                        if char_width != 1 || render_col + 1 > self.cols {
+                            break;
+                        }
+                        consecutive_normal_chars += 1;
+                        render_col += 1;
+                        source_col += 1;
+                    }
+
+                    // Bulk copy if we found consecutive normal characters
+                    if consecutive_normal_chars > 0 {
+                        self.bulk_copy_cells(
+                            &source_row
+                                [start_source_col..start_source_col + consecutive_normal_chars],
+                            &mut render_row
+                                [start_render_col..start_render_col + consecutive_normal_chars],
+                        );
+
+                        // Check cursor position in the bulk copied range
+                        if is_cursor_row
+                            && self.cursor_col >= start_source_col
+                            && self.cursor_col < start_source_col + consecutive_normal_chars
+                        {
+                            self.render_cursor_row = self.render_buffer.len();
+                            self.render_cursor_col =
+                                start_render_col + (self.cursor_col - start_source_col);
+                        }
+                    }
+
+                    // Handle remaining character (wide character or end condition)
+                    if render_col < self.cols
+                        && source_col < text_end
+                        && source_row[source_col].ch != '\u{0000}'
+                    {
+                        let char_width = source_row[source_col].ch.width().unwrap_or(1);
+                        if render_col + char_width <= self.cols {
+                            render_row[render_col] = source_row[source_col];
+
+                            // For wide characters, mark the second cell as continuation
+                            if char_width == 2 && render_col + 1 < self.cols {
+                                render_row[render_col + 1] = TerminalCell {
+                                    ch: '\u{0000}',
+                                    color: source_row[source_col].color,
+                                };
+                            }
+
+                            // Check if this is where cursor should be (for cursor row)
+                            if is_cursor_row && source_col == self.cursor_col {
+                                self.render_cursor_row = self.render_buffer.len();
+                                self.render_cursor_col = render_col;
+                            }
+
+                            render_col += char_width;
+                            source_col += 1;
+                        } else if render_col == 0 {
+                            // Wider than the terminal (a wide character at 1 column):
+                            // it gets a row of its own instead of looping forever
+                            render_row[0] = source_row[source_col];
+                            if is_cursor_row && source_col == self.cursor_col {
+                                self.render_cursor_row = self.render_buffer.len();
+                                self.render_cursor_col = 0;
+                            }
+                            render_col = self.cols;
+                            source_col += 1;
+                        } else {
+                            break; // Can't fit this character
+                        }
+                    }
+                }
+
+                self.render_buffer.push(render_row);
+                // Rows that still have text left over continue on the next row
+                self.render_row_wrapped.push(source_col < text_end);
+                self.render_row_padded
+                    .push(source_col < text_end && render_col < self.cols);
+            }
+
+            // If cursor was in this row but not found yet (at end of line or beyond),
+            // place it at the last render row for this main_buffer row
+            if is_cursor_row && self.cursor_col >= text_end {
+                self.render_cursor_row = (self.render_buffer.len() - 1).max(cursor_render_start);
+                self.render_cursor_col = self.cols.saturating_sub(1);
+            }
+        }
+    }
+
+    // Lay out main_buffer line `row` for PTY data. Output runs past the width
+    // on the same line, so a longer line is wrapped as reflow does it and its
+    // rows are marked soft-wrapped for search, links and selection.
+    fn copy_line_without_reflow(&mut self, row: usize) {
+        let source_row = &self.main_buffer[row];
+
+        // Find actual text end in this row
+        let text_end = self.find_row_text_end(source_row);
+        if text_end > self.cols {
+            self.reflow_line(row);
+            return;
+        }
+
+        // Create render row with terminal width
+        let mut render_row = vec![TerminalCell::default(); self.cols];
+        let copy_length = text_end.min(self.cols);
+
+        // Copy content up to terminal width or text end
+        if copy_length > 0 {
+            self.bulk_copy_cells(&source_row[..copy_length], &mut render_row[..copy_length]);
+        }
+
+        self.render_buffer.push(render_row);
+        self.render_row_wrapped.push(false);
+        self.render_row_padded.push(false);
+
+        // Track cursor position in render buffer
+        if row == self.cursor_row {
+            self.render_cursor_row = self.render_buffer.len() - 1;
+            self.render_cursor_col = self.cursor_col.min(self.cols);
+        }
+    }
+
+    pub fn new(rows: usize, cols: usize) -> Self {
+        // A terminal has at least one cell; 0 comes from a window that has no room
+        let (rows, cols) = (rows.max(1), cols.max(1));
+        let mut main_buffer = VecDeque::with_capacity(MAX_HISTORY_LINES + rows);
+        main_buffer.push_back(vec![TerminalCell::default(); MAX_MAIN_BUFFER_COLS]);
+
+        let mut state = Self {
+            main_buffer,
+            line_generations: VecDeque::from([1]),
+            generation: 1,
+            render_generation: 0,
+            render_lines: Vec::new(),
+            render_layout: None,
+            lines_dropped: 0,
+            render_rows_dropped: 0,
+            render_buffer: Vec::new(),
+            render_row_wrapped: Vec::new(),
+            render_row_padded: Vec::new(),
+            render_row_generations: Vec::new(),
+            render_buffer_dirty: true,
+            incremental_update: false, // Start with full reflow
+            cursor_row: 0,
+            cursor_col: 0,
+            render_cursor_row: 0,
+            render_cursor_col: 0,
+            rows,
+            cols,
+            current_color: AnsiColor::default(),
+            arrow_key_pressed: false,
+            arrow_key_time: None,
+            is_alt_screen: false,
+            saved_cursor_main: (0, 0),
+            saved_cursor_alt: (0, 0),
+            cursor_visible: true,
+            bracketed_paste: false,
+            main_buffer_backup: None,
+            scroll_region_top: 0,
+            scroll_region_bottom: rows - 1,
+            visible_start_row: 0,
+            visible_end_row: rows, // Initially show first 'rows' lines
+            display_offset: 0,
+            synchronized_update: None,
+            selection: None,
+            word_separators: DEFAULT_WORD_SEPARATORS.to_string(),
+            history_lines: MAX_HISTORY_LINES,
+            working_directory: None,
+            title: None,
+            bell: false,
+        };
+        state.update_render_buffer();
+        state
+    }
+
+    // Whether a render_buffer row is soft-wrapped into the next row
+    pub fn is_render_row_wrapped(&self, row: usize) -> bool {
+        self.render_row_wrapped.get(row).copied().unwrap_or(false)
+    }
+
+    // The blank reflow leaves at the end of a wrapped row when the wide
+    // character after it didn't fit and moved to the next row; not part of
+    // the text
+    pub fn is_wrap_padding(&self, row: usize, col: usize) -> bool {
+        self.render_row_padded.get(row).copied().unwrap_or(false)
+            && self
+                .render_buffer
+                .get(row)
+                .is_some_and(|cells| col + 1 == cells.len())
+    }
+
+    // Text of the current selection, if any
+    pub fn selection_to_string(&self) -> Option<String> {
+        let range = self.selection.as_ref()?.to_range(self)?;
+        let text = range.text(self);
+        (!text.is_empty()).then_some(text)
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+    }
+
+    pub fn clear_screen(&mut self) {
+        self.clear_selection();
+        self.clear_lines();
+        self.push_line();
+        self.cursor_row = 0;
+        self.cursor_col = 0;
+        self.incremental_update = false; // Full reflow required for clear
+        self.mark_render_dirty(); // Rebuilt on the next frame
+    }
+
+    pub fn resize(&mut self, new_rows: usize, new_cols: usize) {
+        let (new_rows, new_cols) = (new_rows.max(1), new_cols.max(1));
+        if self.rows == new_rows && self.cols == new_cols {
+            return;
+        }
+
+        let _old_rows = self.rows;
+        self.rows = new_rows;
+        self.cols = new_cols;
+
+        // Reflow invalidates render_buffer coordinates
+        self.clear_selection();
+
+        // In alt screen mode, don't force buffer size changes
+        // Let the application (top, vim, etc.) handle resize by itself
+
+        // When resizing, the entire buffer needs to be reflowed.
+        self.incremental_update = false; // Full reflow required for resize
+        self.force_update_render_buffer(); // Resize needs immediate update
+
+        // Ensure cursor is within new bounds
+        self.cursor_col = self.cursor_col.min(new_cols.saturating_sub(1));
+
+        // Ensure main_buffer has at least one row
+        if self.main_buffer.is_empty() {
+            self.push_line();
+        }
+
+        self.cursor_row = self.cursor_row.min(self.main_buffer.len() - 1);
+
+        // Update scroll region to match new terminal size
+        self.scroll_region_top = 0;
+        self.scroll_region_bottom = new_rows - 1;
+
+        // Update viewport to match new terminal size
+        self.visible_end_row = new_rows;
+    }
+
+    pub fn put_char(&mut self, ch: char) {
+        // Skip frequent arrow key protection clearing for performance
+        // self.clear_arrow_key_protection();
+        let char_width = ch.width().unwrap_or(1);
+
+        // Ensure row exists in main_buffer
+        while self.cursor_row >= self.main_buffer.len() {
+            self.push_line();
+        }
+
+        // Skip frequent auto-scroll checks for performance - let newline handle it
+        // self.auto_scroll_if_needed();
+
+        // Additional safety check
+        if self.cursor_row >= self.main_buffer.len() {
+            return; // Early return to prevent panic
+        }
+
+        self.touch_line(self.cursor_row);
+        let buffer = &mut self.main_buffer[self.cursor_row];
+
+        // Ensure row has enough capacity (a zero-width character still takes the cell)
+        let needed = self.cursor_col + char_width.max(1);
+        if needed > buffer.len() {
+            buffer.resize(needed, TerminalCell::default());
+        }
+
+        buffer[self.cursor_col] = TerminalCell {
+            ch,
+            color: self.current_color,
+        };
+
+        if char_width == 2 && self.cursor_col + 1 < buffer.len() {
+            buffer[self.cursor_col + 1] = TerminalCell {
+                ch: '\u{0000}', // Continuation marker
+                color: self.current_color,
+            };
+        }
+
+        self.cursor_col += char_width;
+        self.incremental_update = true; // Only current row needs reflow
+        self.mark_render_dirty();
+    }
+
+    pub fn newline(&mut self) {
+        self.clear_arrow_key_protection();
+        self.cursor_col = 0;
+
+        // Full-screen programs: LF at the bottom margin scrolls the scroll region
+        if self.is_alt_screen {
+            self.index_down();
+            return;
+        }
+
+        self.cursor_row += 1;
+
+        // Always add new line to main_buffer when cursor moves to new row
+        while self.cursor_row >= self.main_buffer.len() {
+            self.push_line();
+        }
+
+        // Only auto-scroll every 10 lines for better performance during heavy output
+        if self.cursor_row.is_multiple_of(10) {
+            self.auto_scroll_if_needed();
+        }
+
+        // History management: trim old lines if exceeds maximum
+        while self.main_buffer.len() > self.history_lines.max(self.rows) {
+            let dropped = self.line_generations.front().copied();
+            self.pop_front_line();
+            // Adjust cursor_row if it's affected by the removal
+            if self.cursor_row > 0 {
+                self.cursor_row -= 1;
+            }
+            // How the dropped line was laid out, if it hasn't changed since
+            let laid_out = self.render_lines.get(self.lines_dropped).copied();
+            self.lines_dropped += 1;
+            self.render_rows_dropped += laid_out.map_or(0, |(_, count)| count);
+            let render_rows = laid_out
+                .filter(|&(generation, _)| Some(generation) == dropped)
+                .map(|(_, count)| count);
+            // Keep the selection on the same text: it is in render_buffer rows,
+            // and the dropped line may have been laid out over several
+            if let Some(selection) = &mut self.selection {
+                if !render_rows.is_some_and(|count| selection.rotate_up(count)) {
+                    self.selection = None;
+                }
+            }
+        }
+
+        self.incremental_update = true; // Only affected rows need reflow
+        self.mark_render_dirty();
+    }
+
+    pub fn carriage_return(&mut self) {
+        self.cursor_col = 0;
+        self.mark_render_dirty();
+    }
+
+    pub fn backspace(&mut self) {
+        if self.cursor_col > 0 {
+            // Move cursor back to find the character to delete
+            let mut delete_col = self.cursor_col - 1;
+
+            // If we're on a continuation marker (\u{0000}), move back to the actual character
+            if self.cursor_row < self.main_buffer.len() {
+                let row = &self.main_buffer[self.cursor_row];
+                while delete_col > 0 && delete_col < row.len() && row[delete_col].ch == '\u{0000}' {
+                    delete_col -= 1;
+                }
+            }
+
+            if self.cursor_row < self.main_buffer.len() {
+                self.touch_line(self.cursor_row);
+                let row = &mut self.main_buffer[self.cursor_row];
+                if delete_col < row.len() {
+                    // Get the character we're about to delete
+                    let ch_to_delete = row[delete_col].ch;
+                    let char_width = ch_to_delete.width().unwrap_or(1);
+
+                    // Clear the character and any continuation markers
+                    for i in 0..char_width {
+                        if delete_col + i < row.len() {
+                            row[delete_col + i] = TerminalCell::default();
+                        }
+                    }
+
+                    // Move cursor to the position of the deleted character
+                    self.cursor_col = delete_col;
+                }
+            }
+        }
+        self.mark_render_dirty();
+    }
+
+    pub fn move_cursor_to(&mut self, row: usize, col: usize) {
+        if self.is_alt_screen {
+            // In alt screen mode, limit to screen bounds
+            self.cursor_row = row.min(self.rows.saturating_sub(1));
+            self.cursor_col = col.min(self.cols.saturating_sub(1));
+        } else {
+            // In main screen mode, limit to buffer bounds
+            self.cursor_row = row.min(self.main_buffer.len().saturating_sub(1));
+            self.cursor_col = col.min(MAX_MAIN_BUFFER_COLS - 1);
+        }
+        self.mark_render_dirty();
+    }
+
+    // CUP/HVP: `row` is a screen row, clamped to the last one. A main screen
+    // that doesn't fill the window yet gets the rows down to the cursor.
+    pub fn move_cursor_on_screen(&mut self, row: usize, col: usize) {
+        let row = self.screen_start() + row.min(self.rows - 1);
+        while self.main_buffer.len() <= row {
+            self.push_line();
+        }
+        self.move_cursor_to(row, col);
+    }
+
+    // Check if arrow key protection should still be active (within 300ms)
+    pub fn should_protect_from_arrow_key(&self) -> bool {
+        if !self.arrow_key_pressed {
+            return false;
+        }
+
+        if let Some(arrow_time) = self.arrow_key_time {
+            let elapsed = arrow_time.elapsed();
+            elapsed.as_millis() < 300 // 300ms protection window to catch delayed backspaces
+        } else {
+            false
+        }
+    }
+
+    // Set arrow key protection with current timestamp
+    pub fn set_arrow_key_protection(&mut self) {
+        self.arrow_key_pressed = true;
+        self.arrow_key_time = Some(Instant::now());
+    }
+
+    // Clear arrow key protection
+    pub fn clear_arrow_key_protection(&mut self) {
+        self.arrow_key_pressed = false;
+        self.arrow_key_time = None;
+    }
+
+    // Switch to alternative screen buffer
+    pub fn switch_to_alt_screen(&mut self) {
+        if !self.is_alt_screen {
+            // Save current main buffer state
+            self.main_buffer_backup = Some(self.main_buffer.clone());
+            self.saved_cursor_main = (self.cursor_row, self.cursor_col);
+
+            // Switch to alternative screen - initialize main_buffer as clean screen
+            self.clear_lines();
+            // Create initial rows to match screen size
+            for _ in 0..self.rows {
+                self.push_line();
+            }
+            self.is_alt_screen = true;
+            self.cursor_row = 0;
+            self.cursor_col = 0;
+            self.clear_selection();
+
+            self.mark_render_dirty();
+        }
+    }
+
+    // Switch back to main screen buffer
+    pub fn switch_to_main_screen(&mut self) {
+        if self.is_alt_screen {
+            // Don't save alt screen state - each app gets a clean alt screen
+            // Just restore main screen
+            if let Some(backup) = self.main_buffer_backup.take() {
+                self.main_buffer = backup;
+                self.restamp_lines(); // The generations went with the alt screen
+            }
+            self.cursor_row = self.saved_cursor_main.0;
+            self.cursor_col = self.saved_cursor_main.1;
+            self.is_alt_screen = false;
+            self.clear_selection();
+
+            self.mark_render_dirty();
+        }
+    }
+
+    // Undo the modes a program that exited may have left behind (alt screen,
+    // scroll region, hidden cursor, ...) before writing below its output
+    pub fn reset_after_child_exit(&mut self) {
+        self.switch_to_main_screen();
+        self.scroll_region_top = 0;
+        self.scroll_region_bottom = self.rows.saturating_sub(1);
+        self.current_color = AnsiColor::default();
+        self.cursor_visible = true;
+        self.bracketed_paste = false;
+        self.mark_render_dirty();
+    }
+
+    // Set scrolling region (DECSTBM - DEC Set Top and Bottom Margins)
+    pub fn set_scroll_region(&mut self, top: usize, bottom: usize) {
+        // Convert from 1-based to 0-based indexing
+        let top = if top > 0 { top - 1 } else { 0 };
+        let bottom = if bottom > 0 {
+            bottom - 1
+        } else {
+            self.rows - 1
+        };
+
+        // Validate bounds
+        if top < self.rows && bottom < self.rows && top <= bottom {
+            self.scroll_region_top = top;
+            self.scroll_region_bottom = bottom;
+
+            // Move cursor home (as per VT100 spec; the region top only with DECOM)
+            self.cursor_row = self.screen_start();
+            self.cursor_col = 0;
+        } else {
+            // Reset to full screen if invalid parameters
+            self.scroll_region_top = 0;
+            self.scroll_region_bottom = self.rows - 1;
+            self.cursor_row = self.screen_start();
+            self.cursor_col = 0;
+        }
+        self.mark_render_dirty();
+    }
+
+    // Scroll up within the scrolling region (SU - Scroll Up)
+    pub fn scroll_up_in_region(&mut self, lines: usize) {
+        let lines = if lines == 0 { 1 } else { lines };
+
+        for _ in 0..lines {
+            // Calculate absolute positions in main_buffer
+            let buffer_offset = self.main_buffer.len().saturating_sub(self.rows);
+            let top_abs = buffer_offset + self.scroll_region_top;
+            let bottom_abs = buffer_offset + self.scroll_region_bottom;
+
+            // Ensure we have enough buffer space
+            while self.main_buffer.len() <= bottom_abs {
+                self.push_line();
+            }
+
+            // Remove the top line of the scrolling region
+            if top_abs < self.main_buffer.len() {
+                self.remove_line(top_abs);
+            }
+
+            // Add a new blank line at the bottom of the scrolling region
+            if bottom_abs < self.main_buffer.len() {
+                self.insert_line(bottom_abs);
+            } else {
+                self.push_line();
+            }
+        }
+
+        // println!("📜 Scrolled up {} lines in region", lines); // Every LF at the bottom margin
+        self.mark_render_dirty();
+    }
+
+    // Scroll down within the scrolling region (SD - Scroll Down)
+    pub fn scroll_down_in_region(&mut self, lines: usize) {
+        let lines = if lines == 0 { 1 } else { lines };
+
+        for _ in 0..lines {
+            // Calculate absolute positions in main_buffer
+            let buffer_offset = self.main_buffer.len().saturating_sub(self.rows);
+            let top_abs = buffer_offset + self.scroll_region_top;
+            let bottom_abs = buffer_offset + self.scroll_region_bottom;
+
+            // Ensure we have enough buffer space
+            while self.main_buffer.len() <= bottom_abs {
+                self.push_line();
+            }
+
+            // Remove the bottom line of the scrolling region
+            if bottom_abs < self.main_buffer.len() {
+                self.remove_line(bottom_abs);
+            }
+
+            // Add a new blank line at the top of the scrolling region
+            self.insert_line(top_abs);
+        }
+
+        // println!("📜 Scrolled down {} lines in region", lines);
+        self.mark_render_dirty();
+    }
+
+    // Auto-scroll to keep cursor in visible area
+    pub fn auto_scroll_if_needed(&mut self) {
+        if self.is_alt_screen {
+            // In alt-screen mode (vi, less, etc.), use scrolling region
+            let screen_start = self.main_buffer.len().saturating_sub(self.rows);
+            let cursor_screen_row = self.cursor_row.saturating_sub(screen_start);
+
+            // If cursor is beyond the bottom of the scrolling region, scroll up
+            if cursor_screen_row > self.scroll_region_bottom {
+                let lines_to_scroll = cursor_screen_row - self.scroll_region_bottom;
+                for _ in 0..lines_to_scroll {
+                    self.scroll_up_in_region(1);
+                }
+                // Move cursor back to bottom of scrolling region
+                self.cursor_row = screen_start + self.scroll_region_bottom;
+            }
+        } else {
+            // In normal mode, simply ensure buffer grows as needed
+            // The render system will automatically show the bottom part of the buffer
+            // Debug output disabled for performance during large outputs
+            // println!(
+            //     "🔄 Normal mode: cursor at row {}, buffer size {}",
+            //     self.cursor_row,
+            //     self.main_buffer.len()
+            // );
+        }
+    }
+
+    // IND (Index) - Move cursor down one line, scroll if at bottom of scrolling region
+    pub fn index_down(&mut self) {
+        self.clear_arrow_key_protection();
+
+        if self.is_alt_screen {
+            // In alt-screen mode, check if at bottom of scrolling region
+            let screen_start = self.main_buffer.len().saturating_sub(self.rows);
+            let cursor_screen_row = self.cursor_row.saturating_sub(screen_start);
+
+            if cursor_screen_row == self.scroll_region_bottom {
+                // At bottom of scrolling region - scroll up
+                self.scroll_up_in_region(1);
+            } else if cursor_screen_row + 1 < self.rows {
+                // Move cursor down normally; below the region it stops at the last row
+                self.cursor_row += 1;
+                while self.cursor_row >= self.main_buffer.len() {
+                    self.push_line();
+                }
+            }
+        } else {
+            // In normal mode, just move cursor down and grow buffer as needed
+            self.cursor_row += 1;
+            while self.cursor_row >= self.main_buffer.len() {
+                self.push_line();
+            }
+            // println!(
+            //     "🔄 IND: Normal mode, moved cursor to row {}",
+            //     self.cursor_row
+            // );
+        }
+
+        self.mark_render_dirty();
+    }
+
+    // NEL (Next Line) - Move to beginning of next line, scroll if at bottom
+    pub fn next_line(&mut self) {
+        self.index_down(); // Move down one line with scroll
+        self.cursor_col = 0; // Move to beginning of line
+    }
+
+    // RI (Reverse Index) - Move cursor up one line, scroll if at top of scrolling region
+    pub fn reverse_index(&mut self) {
+        self.clear_arrow_key_protection();
+
+        if self.is_alt_screen {
+            // In alt-screen mode, check if at top of scrolling region
+            let screen_start = self.main_buffer.len().saturating_sub(self.rows);
+            let cursor_screen_row = self.cursor_row.saturating_sub(screen_start);
+
+            if cursor_screen_row <= self.scroll_region_top {
+                // At top of scrolling region - scroll down
+                self.scroll_down_in_region(1);
+            } else {
+                // Move cursor up normally
+                if self.cursor_row > 0 {
+                    self.cursor_row -= 1;
+                }
+            }
+        } else {
+            // In normal mode, just move cursor up
+            if self.cursor_row > 0 {
+                self.cursor_row -= 1;
+            }
+            // println!(
+            //     "🔄 RI: Normal mode, moved cursor to row {}",
+            //     self.cursor_row
+            // );
+        }
+
+        self.mark_render_dirty();
+    }
+}
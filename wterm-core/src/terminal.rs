@@ -0,0 +1,147 @@
+use crate::event::{EventListener, NoopListener};
+use crate::performer::TerminalPerformer;
+use crate::state::{TerminalCell, TerminalState};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use vte::Parser;
+
+// A terminal without a window: bytes from the program go in through
+// advance(), the screen is read back through the query methods
+pub struct Terminal {
+    state: Arc<Mutex<TerminalState>>,
+    parser: Parser,
+    events: Arc<dyn EventListener>,
+}
+
+impl Terminal {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self::with_listener(rows, cols, Arc::new(NoopListener))
+    }
+
+    pub fn with_listener(rows: usize, cols: usize, listener: Arc<dyn EventListener>) -> Self {
+        let state = Arc::new(Mutex::new(TerminalState::new(rows, cols)));
+        Self::from_state(state, listener)
+    }
+
+    // Feed a grid that is also read elsewhere (the UI thread)
+    pub fn from_state(state: Arc<Mutex<TerminalState>>, listener: Arc<dyn EventListener>) -> Self {
+        Self {
+            state,
+            parser: Parser::new(),
+            events: listener,
+        }
+    }
+
+    // Process program output; escape sequences may span calls. The whole
+    // chunk is parsed under one lock, the listener hears about it afterwards.
+    pub fn advance(&mut self, bytes: &[u8]) {
+        let (damage, wake) = {
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            let was_dirty = state.render_buffer_dirty;
+            let was_synchronized = state.synchronized_update.is_some();
+            let mut performer = TerminalPerformer::new(&mut state);
+            self.parser.advance(&mut performer, bytes);
+            let damage = performer.finish();
+            // The UI repaints until it has caught up with the damage, so it only
+            // needs waking when the buffer goes dirty, or when a synchronized
+            // update it is holding back ends
+            let wake = (damage.changed && !was_dirty)
+                || (was_synchronized && state.synchronized_update.is_none());
+            (damage, wake)
+        }; // Drop state lock before notifying the listener
+
+        if let Some(title) = &damage.title {
+            self.events.title_changed(title.as_deref());
+        }
+        if damage.bell {
+            self.events.bell();
+        }
+        if !damage.replies.is_empty() {
+            self.events.pty_write(&damage.replies);
+        }
+        if wake {
+            self.events.repaint();
+        }
+    }
+
+    pub fn resize(&mut self, rows: usize, cols: usize) {
+        self.lock().resize(rows, cols);
+    }
+
+    pub fn state(&self) -> &Arc<Mutex<TerminalState>> {
+        &self.state
+    }
+
+    // Run `f` with the whole state locked, for anything the queries don't cover
+    pub fn with_state<R>(&self, f: impl FnOnce(&TerminalState) -> R) -> R {
+        f(&self.lock())
+    }
+
+    // (rows, cols)
+    pub fn size(&self) -> (usize, usize) {
+        let state = self.lock();
+        (state.rows, state.cols)
+    }
+
+    // Cursor (row, col) on the screen, 0-based
+    pub fn cursor(&self) -> (usize, usize) {
+        let state = self.lock();
+        (
+            state.cursor_row.saturating_sub(state.screen_start()),
+            state.cursor_col,
+        )
+    }
+
+    // Cell at (row, col) of the screen; None outside of it. The right half of
+    // a wide character is a cell holding '\0'.
+    pub fn cell(&self, row: usize, col: usize) -> Option<TerminalCell> {
+        let state = self.lock();
+        if row >= state.rows || col >= state.cols {
+            return None;
+        }
+        let cell = state
+            .main_buffer
+            .get(state.screen_start() + row)
+            .and_then(|line| line.get(col))
+            .copied();
+        Some(cell.unwrap_or_default())
+    }
+
+    // Text of a screen row without trailing blanks
+    pub fn row_text(&self, row: usize) -> String {
+        let state = self.lock();
+        row_text(&state, row)
+    }
+
+    // Text of every screen row, top to bottom
+    pub fn screen_text(&self) -> Vec<String> {
+        let state = self.lock();
+        (0..state.rows).map(|row| row_text(&state, row)).collect()
+    }
+
+    // Screen text plus attributes, see snapshot::snapshot
+    pub fn snapshot(&self) -> String {
+        crate::snapshot::snapshot(&self.lock())
+    }
+
+    // Title set with OSC 0/2
+    pub fn title(&self) -> Option<String> {
+        self.lock().title.clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TerminalState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn row_text(state: &TerminalState, row: usize) -> String {
+    let Some(line) = state.main_buffer.get(state.screen_start() + row) else {
+        return String::new();
+    };
+    let text: String = line
+        .iter()
+        .take(state.cols)
+        .filter(|cell| cell.ch != '\u{0000}')
+        .map(|cell| cell.ch)
+        .collect();
+    text.trim_end().to_string()
+}
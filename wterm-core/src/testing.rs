@@ -0,0 +1,15 @@
+// Fixtures for tests of code built on the emulator, here and in wterm
+
+use crate::state::TerminalState;
+use crate::Terminal;
+
+// Feed `text` and lay the lines out again at the terminal width, so long
+// lines become soft-wrapped rows as they do after a resize
+pub fn laid_out(rows: usize, cols: usize, text: &str) -> TerminalState {
+    let mut terminal = Terminal::new(rows, cols);
+    terminal.advance(text.as_bytes());
+    let mut state = terminal.state().lock().unwrap().clone();
+    state.incremental_update = false;
+    state.force_update_render_buffer();
+    state
+}
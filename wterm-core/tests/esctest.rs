@@ -0,0 +1,711 @@
+// Conformance harness after esctest (https://github.com/ThomasDickey/esctest):
+// its expectations ported to cases that drive a Terminal directly, without a
+// PTY, and read the result back from the grid.
+//
+// cargo test -p wterm-core --test esctest prints the score per sequence family
+// and what fails. Cases listed in tests/esctest/known_failures.txt are gaps
+// in the emulator and don't fail the run; any other failure does, and so
+// does a listed case that passes (take it off the list).
+
+use std::collections::BTreeSet;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use wterm_core::color::TermColor;
+use wterm_core::state::SYNCHRONIZED_UPDATE_TIMEOUT;
+use wterm_core::{EventListener, Terminal};
+
+const ROWS: usize = 24;
+const COLS: usize = 80;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Family {
+    C0,
+    Csi,
+    Esc,
+    Dcs,
+    Osc,
+    Modes,
+}
+
+impl Family {
+    fn name(self) -> &'static str {
+        match self {
+            Family::C0 => "C0",
+            Family::Csi => "CSI",
+            Family::Esc => "ESC",
+            Family::Dcs => "DCS",
+            Family::Osc => "OSC",
+            Family::Modes => "modes",
+        }
+    }
+}
+
+// Events the emulator reported while a case ran
+#[derive(Default)]
+struct Recorder {
+    titles: Mutex<Vec<Option<String>>>,
+    bells: Mutex<usize>,
+    replies: Mutex<Vec<u8>>,
+}
+
+impl EventListener for Recorder {
+    fn title_changed(&self, title: Option<&str>) {
+        self.titles.lock().unwrap().push(title.map(str::to_string));
+    }
+
+    fn bell(&self) {
+        *self.bells.lock().unwrap() += 1;
+    }
+
+    fn pty_write(&self, bytes: &[u8]) {
+        self.replies.lock().unwrap().extend_from_slice(bytes);
+    }
+}
+
+type Check = Result<(), String>;
+
+// What a case talks to: a 24x80 terminal, positions 1-based like esctest
+struct Driver {
+    terminal: Terminal,
+    events: Arc<Recorder>,
+}
+
+impl Driver {
+    fn new() -> Self {
+        let events = Arc::new(Recorder::default());
+        Self {
+            terminal: Terminal::with_listener(ROWS, COLS, events.clone()),
+            events,
+        }
+    }
+
+    fn send(&mut self, text: &str) {
+        self.terminal.advance(text.as_bytes());
+    }
+
+    // Cursor as esctest's (x, y), 1-based
+    fn cursor(&self) -> (usize, usize) {
+        let (row, col) = self.terminal.cursor();
+        (col + 1, row + 1)
+    }
+
+    fn expect_cursor(&self, x: usize, y: usize) -> Check {
+        let actual = self.cursor();
+        expect("cursor (x, y)", (x, y), actual)
+    }
+
+    fn char_at(&self, x: usize, y: usize) -> char {
+        self.terminal.cell(y - 1, x - 1).map_or(' ', |cell| cell.ch)
+    }
+
+    // Characters from (x, y) to the right, blanks as ' '
+    fn expect_text(&self, x: usize, y: usize, text: &str) -> Check {
+        let actual: String = (0..text.chars().count())
+            .map(|i| self.char_at(x + i, y))
+            .collect();
+        expect(&format!("text at ({}, {})", x, y), text.to_string(), actual)
+    }
+
+    // Paint rows 1..=rows with "a", "b", ... across the whole width
+    fn fill(&mut self, rows: usize) {
+        for row in 0..rows {
+            let letter = (b'a' + row as u8) as char;
+            self.send(&format!(
+                "\x1b[{};1H{}",
+                row + 1,
+                letter.to_string().repeat(COLS)
+            ));
+        }
+    }
+
+    // What the terminal wrote back for `query`. Replies that vary between
+    // terminals (DA) only have to start with `reply`.
+    fn expect_reply(&mut self, query: &str, reply: &str) -> Check {
+        self.events.replies.lock().unwrap().clear();
+        self.send(query);
+        let actual = String::from_utf8_lossy(&self.events.replies.lock().unwrap()).into_owned();
+        if actual.starts_with(reply) {
+            Ok(())
+        } else {
+            Err(format!("reply: expected {:?}, got {:?}", reply, actual))
+        }
+    }
+}
+
+fn expect<T: PartialEq + std::fmt::Debug>(what: &str, expected: T, actual: T) -> Check {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(format!(
+            "{}: expected {:?}, got {:?}",
+            what, expected, actual
+        ))
+    }
+}
+
+struct Case {
+    family: Family,
+    name: &'static str,
+    run: fn(&mut Driver) -> Check,
+}
+
+const fn case(family: Family, name: &'static str, run: fn(&mut Driver) -> Check) -> Case {
+    Case { family, name, run }
+}
+
+use Family::*;
+
+const CASES: &[Case] = &[
+    // C0 controls
+    case(C0, "BS_MovesLeft", |t| {
+        t.send("\x1b[1;5H\x08");
+        t.expect_cursor(4, 1)
+    }),
+    case(C0, "BS_StopsAtLeftMargin", |t| {
+        t.send("\x1b[1;1H\x08");
+        t.expect_cursor(1, 1)
+    }),
+    case(C0, "BS_DoesNotErase", |t| {
+        t.send("\x1b[1;1Hab\x08");
+        t.expect_text(1, 1, "ab")
+    }),
+    case(C0, "HT_DefaultStops", |t| {
+        t.send("\x1b[1;1H\tx\tx");
+        t.expect_text(9, 1, "x")?;
+        t.expect_text(17, 1, "x")
+    }),
+    case(C0, "HT_StopsAtRightMargin", |t| {
+        t.send(&"\t".repeat(20));
+        t.expect_cursor(COLS, 1)
+    }),
+    case(C0, "CR_MovesToLeftMargin", |t| {
+        t.send("\x1b[3;10H\r");
+        t.expect_cursor(1, 3)
+    }),
+    case(C0, "LF_KeepsColumn", |t| {
+        t.send("\x1b[3;10H\n");
+        t.expect_cursor(10, 4)
+    }),
+    case(C0, "LF_ScrollsAtBottom", |t| {
+        t.fill(ROWS);
+        t.send(&format!("\x1b[{};1H\n", ROWS));
+        t.expect_text(1, 1, "b")?;
+        t.expect_text(1, ROWS, " ")
+    }),
+    case(C0, "VT_FF_LikeLF", |t| {
+        t.send("\x1b[3;10H\x0b\x0c");
+        t.expect_cursor(10, 5)
+    }),
+    case(C0, "BEL_RaisesBell", |t| {
+        t.send("\x07");
+        expect("bells", 1, *t.events.bells.lock().unwrap())
+    }),
+    // CSI
+    case(Csi, "CUP_DefaultParams", |t| {
+        t.send("\x1b[6;6H\x1b[H");
+        t.expect_cursor(1, 1)
+    }),
+    case(Csi, "CUP_RowOnly", |t| {
+        t.send("\x1b[6;6H\x1b[2H");
+        t.expect_cursor(1, 2)
+    }),
+    case(Csi, "CUP_ColumnOnly", |t| {
+        t.send("\x1b[6;6H\x1b[;2H");
+        t.expect_cursor(2, 1)
+    }),
+    case(Csi, "CUP_ZeroIsTreatedAsOne", |t| {
+        t.send("\x1b[6;6H\x1b[0;0H");
+        t.expect_cursor(1, 1)
+    }),
+    case(Csi, "CUP_OutOfBoundsParams", |t| {
+        t.send("\x1b[999;999H");
+        t.expect_cursor(COLS, ROWS)
+    }),
+    case(Csi, "HVP_DefaultParams", |t| {
+        t.send("\x1b[6;6H\x1b[f");
+        t.expect_cursor(1, 1)
+    }),
+    case(Csi, "CUU_DefaultParam", |t| {
+        t.send("\x1b[5;3H\x1b[A");
+        t.expect_cursor(3, 4)
+    }),
+    case(Csi, "CUU_StopsAtTopLine", |t| {
+        t.send("\x1b[5;3H\x1b[99A");
+        t.expect_cursor(3, 1)
+    }),
+    case(Csi, "CUD_DefaultParam", |t| {
+        t.send("\x1b[5;3H\x1b[B");
+        t.expect_cursor(3, 6)
+    }),
+    case(Csi, "CUD_StopsAtBottomLine", |t| {
+        t.send("\x1b[5;3H\x1b[99B");
+        t.expect_cursor(3, ROWS)
+    }),
+    case(Csi, "CUF_DefaultParam", |t| {
+        t.send("\x1b[5;3H\x1b[C");
+        t.expect_cursor(4, 5)
+    }),
+    case(Csi, "CUF_StopsAtRightEdge", |t| {
+        t.send("\x1b[5;3H\x1b[999C");
+        t.expect_cursor(COLS, 5)
+    }),
+    case(Csi, "CUB_DefaultParam", |t| {
+        t.send("\x1b[5;3H\x1b[D");
+        t.expect_cursor(2, 5)
+    }),
+    case(Csi, "CUB_StopsAtLeftEdge", |t| {
+        t.send("\x1b[5;3H\x1b[99D");
+        t.expect_cursor(1, 5)
+    }),
+    case(Csi, "CNL_DefaultParam", |t| {
+        t.send("\x1b[5;3H\x1b[E");
+        t.expect_cursor(1, 6)
+    }),
+    case(Csi, "CPL_DefaultParam", |t| {
+        t.send("\x1b[5;3H\x1b[F");
+        t.expect_cursor(1, 4)
+    }),
+    case(Csi, "CHA_DefaultParam", |t| {
+        t.send("\x1b[5;3H\x1b[G");
+        t.expect_cursor(1, 5)
+    }),
+    case(Csi, "CHA_OutOfBounds", |t| {
+        t.send("\x1b[5;3H\x1b[999G");
+        t.expect_cursor(COLS, 5)
+    }),
+    case(Csi, "HPA_DefaultParam", |t| {
+        t.send("\x1b[5;3H\x1b[`");
+        t.expect_cursor(1, 5)
+    }),
+    case(Csi, "VPA_DefaultParam", |t| {
+        t.send("\x1b[5;3H\x1b[d");
+        t.expect_cursor(3, 1)
+    }),
+    case(Csi, "VPA_OutOfBounds", |t| {
+        t.send("\x1b[5;3H\x1b[999d");
+        t.expect_cursor(3, ROWS)
+    }),
+    case(Csi, "ED_0_ClearsBelow", |t| {
+        t.fill(3);
+        t.send("\x1b[2;5H\x1b[J");
+        t.expect_text(1, 1, "aaaa")?;
+        t.expect_text(1, 2, "bbbb ")?;
+        t.expect_text(1, 3, " ")?;
+        t.expect_cursor(5, 2)
+    }),
+    case(Csi, "ED_1_ClearsAbove", |t| {
+        t.fill(3);
+        t.send("\x1b[2;5H\x1b[1J");
+        t.expect_text(1, 1, " ")?;
+        t.expect_text(1, 2, "     b")?;
+        t.expect_text(1, 3, "c")
+    }),
+    case(Csi, "ED_2_ClearsScreen", |t| {
+        t.fill(3);
+        t.send("\x1b[2;5H\x1b[2J");
+        t.expect_text(1, 1, " ")?;
+        t.expect_text(1, 3, " ")
+    }),
+    case(Csi, "ED_2_DoesNotMoveCursor", |t| {
+        t.send("\x1b[2;5H\x1b[2J");
+        t.expect_cursor(5, 2)
+    }),
+    case(Csi, "EL_0_ClearsRight", |t| {
+        t.fill(1);
+        t.send("\x1b[1;5H\x1b[K");
+        t.expect_text(1, 1, "aaaa ")
+    }),
+    case(Csi, "EL_1_ClearsLeft", |t| {
+        t.fill(1);
+        t.send("\x1b[1;5H\x1b[1K");
+        t.expect_text(1, 1, "     a")
+    }),
+    case(Csi, "EL_2_ClearsLine", |t| {
+        t.fill(1);
+        t.send("\x1b[1;5H\x1b[2K");
+        t.expect_text(1, 1, " ")?;
+        t.expect_text(COLS, 1, " ")
+    }),
+    case(Csi, "ECH_DefaultParam", |t| {
+        t.fill(1);
+        t.send("\x1b[1;2H\x1b[X");
+        t.expect_text(1, 1, "a a")
+    }),
+    case(Csi, "ECH_ExplicitParam", |t| {
+        t.fill(1);
+        t.send("\x1b[1;2H\x1b[3X");
+        t.expect_text(1, 1, "a   a")?;
+        t.expect_cursor(2, 1)
+    }),
+    case(Csi, "ICH_DefaultParam", |t| {
+        t.send("\x1b[1;1Habcd\x1b[1;2H\x1b[@");
+        t.expect_text(1, 1, "a bcd")
+    }),
+    case(Csi, "DCH_DefaultParam", |t| {
+        t.send("\x1b[1;1Habcd\x1b[1;2H\x1b[P");
+        t.expect_text(1, 1, "acd ")
+    }),
+    case(Csi, "IL_DefaultParam", |t| {
+        t.fill(3);
+        t.send("\x1b[2;1H\x1b[L");
+        t.expect_text(1, 2, " ")?;
+        t.expect_text(1, 3, "b")
+    }),
+    case(Csi, "DL_DefaultParam", |t| {
+        t.fill(3);
+        t.send("\x1b[2;1H\x1b[M");
+        t.expect_text(1, 2, "c")
+    }),
+    case(Csi, "SU_DefaultParam", |t| {
+        t.fill(ROWS);
+        t.send("\x1b[S");
+        t.expect_text(1, 1, "b")?;
+        t.expect_text(1, ROWS, " ")
+    }),
+    case(Csi, "SD_DefaultParam", |t| {
+        t.fill(ROWS);
+        t.send("\x1b[T");
+        t.expect_text(1, 1, " ")?;
+        t.expect_text(1, 2, "a")
+    }),
+    case(Csi, "REP_DefaultParam", |t| {
+        t.send("\x1b[1;1Ha\x1b[b");
+        t.expect_text(1, 1, "aa ")
+    }),
+    case(Csi, "CBT_DefaultParam", |t| {
+        t.send("\x1b[1;12H\x1b[Z");
+        t.expect_cursor(9, 1)
+    }),
+    case(Csi, "SGR_Bold", |t| {
+        t.send("\x1b[1mx");
+        expect("bold", true, t.terminal.cell(0, 0).unwrap().color.bold)
+    }),
+    case(Csi, "SGR_ResetAll", |t| {
+        t.send("\x1b[1;3;4;7;31;42m\x1b[mx");
+        let cell = t.terminal.cell(0, 0).unwrap();
+        expect("attributes", Default::default(), cell.color)
+    }),
+    case(Csi, "SGR_256Color", |t| {
+        t.send("\x1b[38;5;208;48;5;22mx");
+        let color = t.terminal.cell(0, 0).unwrap().color;
+        expect("fg", TermColor::Indexed(208), color.foreground)?;
+        expect("bg", TermColor::Indexed(22), color.background)
+    }),
+    case(Csi, "SGR_TrueColor", |t| {
+        t.send("\x1b[38;2;1;2;3mx");
+        let color = t.terminal.cell(0, 0).unwrap().color;
+        expect("fg", TermColor::Rgb(1, 2, 3), color.foreground)
+    }),
+    case(Csi, "SGR_TrueColorColons", |t| {
+        t.send("\x1b[38:2::1:2:3mx");
+        let color = t.terminal.cell(0, 0).unwrap().color;
+        expect("fg", TermColor::Rgb(1, 2, 3), color.foreground)
+    }),
+    case(Csi, "SGR_NormalIntensity", |t| {
+        t.send("\x1b[1m\x1b[22mx");
+        expect("bold", false, t.terminal.cell(0, 0).unwrap().color.bold)
+    }),
+    case(Csi, "DECSTBM_LFScrollsRegion", |t| {
+        t.fill(5);
+        t.send("\x1b[2;4r\x1b[4;1H\n");
+        t.expect_text(1, 1, "a")?;
+        t.expect_text(1, 2, "c")?;
+        t.expect_text(1, 4, " ")?;
+        t.expect_text(1, 5, "e")
+    }),
+    case(Csi, "DECSTBM_MovesCursorHome", |t| {
+        t.send("\x1b[5;5H\x1b[2;4r");
+        t.expect_cursor(1, 1)
+    }),
+    case(Csi, "DECSTBM_InvalidRegionIgnored", |t| {
+        t.fill(3);
+        t.send("\x1b[3;2r\x1b[3;1H\n");
+        t.expect_text(1, 1, "a")
+    }),
+    case(Csi, "DSR_CursorPosition", |t| {
+        t.send("\x1b[3;5H");
+        t.expect_reply("\x1b[6n", "\x1b[3;5R")
+    }),
+    case(Csi, "DA_Primary", |t| t.expect_reply("\x1b[c", "\x1b[?")),
+    // ESC
+    case(Esc, "DECSC_DECRC", |t| {
+        t.send("\x1b[5;6H\x1b7\x1b[1;1H\x1b8");
+        t.expect_cursor(6, 5)
+    }),
+    case(Esc, "DECRC_RestoresAttributes", |t| {
+        t.send("\x1b[1m\x1b7\x1b[m\x1b8x");
+        expect("bold", true, t.terminal.cell(0, 0).unwrap().color.bold)
+    }),
+    case(Esc, "IND_MovesDown", |t| {
+        t.send("\x1b[3;5H\x1bD");
+        t.expect_cursor(5, 4)
+    }),
+    case(Esc, "IND_ScrollsAtBottom", |t| {
+        t.fill(ROWS);
+        t.send(&format!("\x1b[{};5H\x1bD", ROWS));
+        t.expect_text(1, 1, "b")?;
+        t.expect_cursor(5, ROWS)
+    }),
+    case(Esc, "RI_MovesUp", |t| {
+        t.send("\x1b[3;5H\x1bM");
+        t.expect_cursor(5, 2)
+    }),
+    case(Esc, "RI_ScrollsAtTop", |t| {
+        t.fill(2);
+        t.send("\x1b[1;5H\x1bM");
+        t.expect_text(1, 1, " ")?;
+        t.expect_text(1, 2, "a")?;
+        t.expect_cursor(5, 1)
+    }),
+    case(Esc, "NEL_MovesToNextLine", |t| {
+        t.send("\x1b[3;5H\x1bE");
+        t.expect_cursor(1, 4)
+    }),
+    case(Esc, "HTS_SetsTabStop", |t| {
+        t.send("\x1b[1;4H\x1bH\x1b[1;1H\t");
+        t.expect_cursor(4, 1)
+    }),
+    case(Esc, "RIS_ResetsScreen", |t| {
+        t.fill(2);
+        t.send("\x1b[1m\x1b[5;5H\x1bc");
+        t.expect_text(1, 1, " ")?;
+        t.expect_cursor(1, 1)
+    }),
+    case(Esc, "DECALN_FillsScreen", |t| {
+        t.send("\x1b#8");
+        t.expect_text(1, 1, "EEE")?;
+        t.expect_text(1, ROWS, "EEE")
+    }),
+    // DCS
+    case(Dcs, "DCS_Swallowed", |t| {
+        t.send("\x1bPq#0;2;0;0;0#0~~\x1b\\x");
+        t.expect_text(1, 1, "x ")
+    }),
+    case(Dcs, "DCS_C1Terminated", |t| {
+        t.send("\x1bP1$rfoo\x1b\\x");
+        t.expect_text(1, 1, "x ")
+    }),
+    case(Dcs, "DECRQSS_SGR", |t| {
+        t.send("\x1b[1m");
+        t.expect_reply("\x1bP$qm\x1b\\", "\x1bP1$r0;1m\x1b\\")
+    }),
+    // OSC
+    case(Osc, "OSC0_SetsTitle", |t| {
+        t.send("\x1b]0;hello\x07");
+        expect("title", Some("hello".to_string()), t.terminal.title())
+    }),
+    case(Osc, "OSC2_SetsTitle", |t| {
+        t.send("\x1b]2;hello\x1b\\");
+        expect("title", Some("hello".to_string()), t.terminal.title())
+    }),
+    case(Osc, "OSC2_ReportsTitleChange", |t| {
+        t.send("\x1b]2;hello\x07");
+        let titles = t.events.titles.lock().unwrap().clone();
+        expect("title events", vec![Some("hello".to_string())], titles)
+    }),
+    case(Osc, "OSC2_TitleWithSemicolon", |t| {
+        t.send("\x1b]2;a;b\x07");
+        expect("title", Some("a;b".to_string()), t.terminal.title())
+    }),
+    case(Osc, "OSC1_IconNameKeepsTitle", |t| {
+        t.send("\x1b]2;title\x07\x1b]1;icon\x07");
+        expect("title", Some("title".to_string()), t.terminal.title())
+    }),
+    case(Osc, "OSC7_WorkingDirectory", |t| {
+        t.send("\x1b]7;file://host/tmp/dir\x1b\\");
+        let dir = t
+            .terminal
+            .with_state(|state| state.working_directory.clone());
+        expect("working directory", Some("/tmp/dir".into()), dir)
+    }),
+    case(Osc, "OSC8_HyperlinkTextOnly", |t| {
+        t.send("\x1b]8;;http://example.com\x1b\\link\x1b]8;;\x1b\\");
+        t.expect_text(1, 1, "link ")
+    }),
+    case(Osc, "OSC_UnknownSwallowed", |t| {
+        t.send("\x1b]1337;whatever\x07x");
+        t.expect_text(1, 1, "x ")
+    }),
+    // Modes
+    case(Modes, "DECTCEM_HidesCursor", |t| {
+        t.send("\x1b[?25l");
+        let visible = t.terminal.with_state(|state| state.cursor_visible);
+        expect("cursor visible", false, visible)?;
+        t.send("\x1b[?25h");
+        let visible = t.terminal.with_state(|state| state.cursor_visible);
+        expect("cursor visible", true, visible)
+    }),
+    case(Modes, "BracketedPaste_2004", |t| {
+        t.send("\x1b[?2004h");
+        let on = t.terminal.with_state(|state| state.bracketed_paste);
+        expect("bracketed paste", true, on)
+    }),
+    case(Modes, "AltScreen_1049_SavesAndRestores", |t| {
+        t.send("\x1b[1;1Hmain\x1b[2;3H\x1b[?1049h");
+        t.expect_text(1, 1, " ")?;
+        t.send("alt\x1b[?1049l");
+        t.expect_text(1, 1, "main")?;
+        t.expect_cursor(3, 2)
+    }),
+    case(Modes, "AltScreen_1049_StartsBlank", |t| {
+        t.send("\x1b[?1049h\x1b[1;1Hx\x1b[?1049l\x1b[?1049h");
+        t.expect_text(1, 1, " ")
+    }),
+    case(Modes, "AltScreen_47", |t| {
+        t.send("\x1b[1;1Hmain\x1b[?47h");
+        t.expect_text(1, 1, " ")?;
+        t.send("\x1b[?47l");
+        t.expect_text(1, 1, "main")
+    }),
+    case(Modes, "AltScreen_1047", |t| {
+        t.send("\x1b[1;1Hmain\x1b[?1047h");
+        t.expect_text(1, 1, " ")?;
+        t.send("\x1b[?1047l");
+        t.expect_text(1, 1, "main")
+    }),
+    case(Modes, "DECAWM_WrapsAtRightEdge", |t| {
+        t.send(&format!("\x1b[1;{}Hxy", COLS));
+        t.expect_text(1, 2, "y")
+    }),
+    case(Modes, "DECAWM_Off", |t| {
+        t.send(&format!("\x1b[?7l\x1b[1;{}Hxy", COLS - 1));
+        t.expect_text(COLS - 1, 1, "xy")?;
+        t.expect_text(1, 2, " ")
+    }),
+    case(Modes, "DECOM_CursorRelativeToRegion", |t| {
+        t.send("\x1b[5;10r\x1b[?6h\x1b[1;1Hx");
+        t.expect_text(1, 5, "x")
+    }),
+    case(Modes, "IRM_InsertsCharacters", |t| {
+        t.send("\x1b[1;1Habc\x1b[1;1H\x1b[4hx");
+        t.expect_text(1, 1, "xabc")
+    }),
+    case(Modes, "DECRQM_Reports", |t| {
+        t.send("\x1b[?25l");
+        t.expect_reply("\x1b[?25$p", "\x1b[?25;2$y")
+    }),
+    case(Modes, "DECRQM_UnknownMode", |t| {
+        t.expect_reply("\x1b[?12345$p", "\x1b[?12345;0$y")
+    }),
+    case(Modes, "SynchronizedOutput_2026", |t| {
+        t.expect_reply("\x1b[?2026$p", "\x1b[?2026;2$y")?;
+        t.send("\x1b[?2026hx");
+        t.expect_reply("\x1b[?2026$p", "\x1b[?2026;1$y")?;
+        t.send("\x1b[?2026l");
+        t.expect_text(1, 1, "x")?;
+        t.expect_reply("\x1b[?2026$p", "\x1b[?2026;2$y")
+    }),
+    case(Modes, "SynchronizedOutput_2026_AfterTimeout", |t| {
+        t.send("\x1b[?2026hx");
+        std::thread::sleep(SYNCHRONIZED_UPDATE_TIMEOUT + Duration::from_millis(20));
+        // Shown anyway, and no longer reported as set
+        let shown = t
+            .terminal
+            .state()
+            .lock()
+            .unwrap()
+            .update_render_buffer_if_dirty();
+        expect("rebuilt after the timeout", true, shown)?;
+        t.expect_reply("\x1b[?2026$p", "\x1b[?2026;2$y")?;
+
+        // The next update is held back for a full timeout again
+        t.send("\x1b[?2026hy");
+        let shown = t
+            .terminal
+            .state()
+            .lock()
+            .unwrap()
+            .update_render_buffer_if_dirty();
+        expect("rebuilt inside the next update", false, shown)?;
+        t.expect_reply("\x1b[?2026$p", "\x1b[?2026;1$y")
+    }),
+];
+
+// Run one case; a panic counts as a failure
+fn run(case: &Case) -> Check {
+    let mut driver = Driver::new();
+    match panic::catch_unwind(AssertUnwindSafe(|| (case.run)(&mut driver))) {
+        Ok(result) => result,
+        Err(payload) => Err(format!(
+            "panicked: {}",
+            payload
+                .downcast_ref::<String>()
+                .map(String::as_str)
+                .or_else(|| payload.downcast_ref::<&str>().copied())
+                .unwrap_or("?")
+        )),
+    }
+}
+
+fn known_failures() -> BTreeSet<String> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/esctest/known_failures.txt");
+    std::fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+fn main() {
+    let known = known_failures();
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {})); // Reported per case instead
+    let results: Vec<(&Case, Check)> = CASES.iter().map(|case| (case, run(case))).collect();
+    panic::set_hook(default_hook);
+
+    println!("esctest conformance ({} cases)\n", CASES.len());
+    let mut families: Vec<Family> = CASES.iter().map(|case| case.family).collect();
+    families.sort();
+    families.dedup();
+    for family in &families {
+        let cases: Vec<_> = results
+            .iter()
+            .filter(|(case, _)| case.family == *family)
+            .collect();
+        let passed = cases.iter().filter(|(_, result)| result.is_ok()).count();
+        println!(
+            "{:<6} {:>3}/{:<3} passed",
+            family.name(),
+            passed,
+            cases.len()
+        );
+    }
+    let passed = results.iter().filter(|(_, result)| result.is_ok()).count();
+    println!("{:<6} {:>3}/{:<3} passed", "total", passed, results.len());
+
+    let mut regressions = Vec::new();
+    let mut fixed = Vec::new();
+    println!("\nFailing:");
+    for (case, result) in &results {
+        match result {
+            Err(message) => {
+                println!("  [{}] {}: {}", case.family.name(), case.name, message);
+                if !known.contains(case.name) {
+                    regressions.push(case.name);
+                }
+            }
+            Ok(()) if known.contains(case.name) => fixed.push(case.name),
+            Ok(()) => {}
+        }
+    }
+
+    let mut ok = true;
+    if !regressions.is_empty() {
+        println!("\nNot in known_failures.txt: {}", regressions.join(", "));
+        ok = false;
+    }
+    if !fixed.is_empty() {
+        println!(
+            "\nPassing now, remove from known_failures.txt: {}",
+            fixed.join(", ")
+        );
+        ok = false;
+    }
+    if !ok {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,157 @@
+// Golden-file tests: every tests/golden/NAME.in is fed into a Terminal and
+// the screen snapshot is compared with NAME.snap.
+//
+// NAME.in is line based:
+//   # comment
+//   size 5 20            rows and cols, before any output (default 24 80)
+//   resize 4 10
+//   snapshot             also record the screen at this point
+//   "\e[1mbold\r\n"      bytes to feed, Rust-style escapes
+//
+// The quoted lines use the same escapes as the "PTY Read" lines of a debug
+// log (\u{1b}, \r, \n, \t, \\, \", \'), plus \e and \xNN for raw bytes, so
+// recorded sessions can be pasted in as they are.
+//
+// WTERM_BLESS=1 cargo test -p wterm-core --test golden rewrites the .snap
+// files from the current output.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use wterm_core::Terminal;
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+// "\e[1mhi\u{1b}[0m" -> bytes
+fn unescape(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('e') => bytes.push(0x1b),
+            Some('r') => bytes.push(b'\r'),
+            Some('n') => bytes.push(b'\n'),
+            Some('t') => bytes.push(b'\t'),
+            Some('0') => bytes.push(0),
+            Some(c @ ('\\' | '"' | '\'')) => bytes.push(c as u8),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let byte = u8::from_str_radix(&hex, 16).map_err(|_| format!("bad \\x{}", hex))?;
+                bytes.push(byte);
+            }
+            Some('u') => {
+                if chars.next() != Some('{') {
+                    return Err("expected \\u{...}".to_string());
+                }
+                let hex: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let ch = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("bad \\u{{{}}}", hex))?;
+                let mut buf = [0; 4];
+                bytes.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+            }
+            other => return Err(format!("unknown escape \\{:?}", other)),
+        }
+    }
+    Ok(bytes)
+}
+
+fn parse_size(args: &str) -> Result<(usize, usize), String> {
+    let mut numbers = args.split_whitespace().map(str::parse::<usize>);
+    match (numbers.next(), numbers.next(), numbers.next()) {
+        (Some(Ok(rows)), Some(Ok(cols)), None) => Ok((rows, cols)),
+        _ => Err(format!("expected `ROWS COLS`, got {:?}", args)),
+    }
+}
+
+// Run a fixture and return the snapshots it records
+fn run(input: &str) -> Result<String, String> {
+    let mut terminal: Option<Terminal> = None;
+    let mut snapshots = Vec::new();
+
+    for (number, line) in input.lines().enumerate() {
+        let line = line.trim();
+        let error = |message: String| format!("line {}: {}", number + 1, message);
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(args) = line.strip_prefix("size ") {
+            if terminal.is_some() {
+                return Err(error("size must come before any output".to_string()));
+            }
+            let (rows, cols) = parse_size(args).map_err(error)?;
+            terminal = Some(Terminal::new(rows, cols));
+            continue;
+        }
+
+        let terminal = terminal.get_or_insert_with(|| Terminal::new(24, 80));
+        if let Some(args) = line.strip_prefix("resize ") {
+            let (rows, cols) = parse_size(args).map_err(error)?;
+            terminal.resize(rows, cols);
+        } else if line == "snapshot" {
+            snapshots.push(terminal.snapshot());
+        } else if let Some(quoted) = line.strip_prefix('"').and_then(|l| l.strip_suffix('"')) {
+            terminal.advance(&unescape(quoted).map_err(error)?);
+        } else {
+            return Err(error(format!("can't parse {:?}", line)));
+        }
+    }
+
+    let terminal = terminal.get_or_insert_with(|| Terminal::new(24, 80));
+    snapshots.push(terminal.snapshot());
+    Ok(snapshots.join("---\n"))
+}
+
+#[test]
+fn golden() {
+    let bless = std::env::var_os("WTERM_BLESS").is_some();
+    let mut fixtures: Vec<PathBuf> = fs::read_dir(golden_dir())
+        .expect("tests/golden")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "in"))
+        .collect();
+    fixtures.sort();
+    assert!(!fixtures.is_empty(), "no fixtures in tests/golden");
+
+    let mut failures = Vec::new();
+    for input_path in &fixtures {
+        let name = input_path.file_stem().unwrap().to_string_lossy();
+        let input = fs::read_to_string(input_path).unwrap();
+        let actual = match run(&input) {
+            Ok(actual) => actual,
+            Err(e) => {
+                failures.push(format!("{}.in {}", name, e));
+                continue;
+            }
+        };
+
+        let snap_path = input_path.with_extension("snap");
+        if bless {
+            fs::write(&snap_path, &actual).unwrap();
+            continue;
+        }
+        match fs::read_to_string(&snap_path) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{} differs\n--- expected\n{}--- actual\n{}",
+                name, expected, actual
+            )),
+            Err(_) => failures.push(format!("{}.snap is missing\n{}", name, actual)),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} golden files failed (WTERM_BLESS=1 to update):\n\n{}",
+        failures.len(),
+        fixtures.len(),
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,171 @@
+// Incremental render_buffer rebuilds against full ones: after every frame the
+// rows reused from the last rebuild must match laying out every line again.
+// The deterministic counterpart of the fuzz targets' check_render_buffer.
+
+use wterm_core::state::TerminalState;
+use wterm_core::Terminal;
+
+enum Step<'a> {
+    Output(&'a [u8]),
+    Resize(usize, usize),
+}
+
+// Run `steps` on a rows x cols terminal, rebuilding and checking after each
+// one as if a frame was painted in between
+fn check_frames(rows: usize, cols: usize, steps: &[Step]) -> TerminalState {
+    let mut terminal = Terminal::new(rows, cols);
+    terminal
+        .state()
+        .lock()
+        .unwrap()
+        .force_update_render_buffer();
+    for (i, step) in steps.iter().enumerate() {
+        match step {
+            Step::Output(bytes) => terminal.advance(bytes),
+            Step::Resize(rows, cols) => terminal.resize(*rows, *cols),
+        }
+        let mut state = terminal.state().lock().unwrap();
+        state.force_update_render_buffer();
+        check_render_buffer(&state, i);
+    }
+    let state = terminal.state().lock().unwrap().clone();
+    state
+}
+
+fn check_render_buffer(state: &TerminalState, step: usize) {
+    let mut fresh = state.clone();
+    fresh.invalidate_render_buffer();
+    fresh.force_update_render_buffer();
+    assert_eq!(
+        rows_text(&fresh),
+        rows_text(state),
+        "step {}: reused render rows differ from a full rebuild",
+        step
+    );
+    assert!(
+        fresh.render_buffer == state.render_buffer,
+        "step {}: cell attributes differ from a full rebuild",
+        step
+    );
+    assert_eq!(
+        fresh.render_row_wrapped, state.render_row_wrapped,
+        "step {}",
+        step
+    );
+    assert_eq!(
+        fresh.render_row_padded, state.render_row_padded,
+        "step {}",
+        step
+    );
+    assert_eq!(
+        (fresh.render_cursor_row, fresh.render_cursor_col),
+        (state.render_cursor_row, state.render_cursor_col),
+        "step {}: cursor",
+        step
+    );
+}
+
+// The rows as text, for a readable diff when they differ
+fn rows_text(state: &TerminalState) -> Vec<String> {
+    state
+        .render_buffer
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| cell.ch)
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn carriage_return_progress_line() {
+    let mut steps = vec![Step::Output(b"$ cargo build\r\n   Compiling wterm\r\n")];
+    let frames: Vec<String> = (0..=10)
+        .map(|i| {
+            format!(
+                "\r[{:<10}] {:>3}% downloading a long file name",
+                "#".repeat(i),
+                i * 10
+            )
+        })
+        .collect();
+    steps.extend(frames.iter().map(|frame| Step::Output(frame.as_bytes())));
+    steps.push(Step::Output(b"\r\n$ "));
+
+    let state = check_frames(6, 20, &steps);
+    // The line above the progress bar was laid out once and reused since
+    let before = check_frames(6, 20, &steps[..1]);
+    assert_eq!(
+        state.render_row_generations[1],
+        before.render_row_generations[1]
+    );
+}
+
+#[test]
+fn scroll_region_scrolls() {
+    let mut steps = vec![Step::Output(b"\x1b[2J\x1b[Hheader\r\n")];
+    let lines: Vec<String> = (0..12)
+        .map(|i| format!("\x1b[2;5r\x1b[5;1Hline {}\n", i))
+        .collect();
+    steps.extend(lines.iter().map(|line| Step::Output(line.as_bytes())));
+    // Reverse index at the top of the region scrolls it down
+    steps.push(Step::Output(b"\x1b[2;1H\x1bM\x1bMback"));
+    steps.push(Step::Output(b"\x1b[r\x1b[6;1Hfooter"));
+    check_frames(6, 20, &steps);
+}
+
+#[test]
+fn erase_display_and_scrollback() {
+    let history: String = (0..20).map(|i| format!("history {}\r\n", i)).collect();
+    check_frames(
+        5,
+        20,
+        &[
+            Step::Output(history.as_bytes()),
+            Step::Output(b"\x1b[2J"),
+            Step::Output(b"\x1b[Hafter clear"),
+            Step::Output(b"\x1b[3J"),
+            Step::Output(b"\r\nmore\r\nlines\r\nto\r\nscroll\r\nagain"),
+            Step::Output(b"\x1b[H\x1b[2J\x1b[3Jempty"),
+        ],
+    );
+}
+
+#[test]
+fn alt_screen_enter_and_exit() {
+    let history: String = (0..10).map(|i| format!("shell {}\r\n", i)).collect();
+    check_frames(
+        5,
+        20,
+        &[
+            Step::Output(history.as_bytes()),
+            Step::Output(b"$ vim"),
+            Step::Output(b"\x1b[?1049h\x1b[H\x1b[2J"),
+            Step::Output(b"~\r\n~\r\n~\r\n\x1b[5;1H-- INSERT --"),
+            Step::Output(b"\x1b[1;1Htyped text"),
+            Step::Output(b"\x1b[?1049l"),
+            Step::Output(b"\r\n$ "),
+        ],
+    );
+}
+
+#[test]
+fn resize_rewraps() {
+    let long = "a line long enough to wrap at every width used here";
+    let text = format!("{}\r\nshort\r\n{}\r\n$ ", long, long);
+    check_frames(
+        6,
+        30,
+        &[
+            Step::Output(text.as_bytes()),
+            Step::Resize(6, 12),
+            Step::Output(b"typed after the resize"),
+            Step::Resize(8, 50),
+            Step::Resize(4, 20),
+            Step::Output(b"\r\n$ "),
+        ],
+    );
+}
@@ -0,0 +1,61 @@
+// The viewport scrolled back into history (display_offset) while output
+// keeps arriving.
+
+use wterm_core::state::TerminalState;
+use wterm_core::Terminal;
+
+fn feed(terminal: &mut Terminal, lines: std::ops::Range<usize>) {
+    for line in lines {
+        terminal.advance(format!("line {}\r\n", line).as_bytes());
+    }
+    terminal
+        .state()
+        .lock()
+        .unwrap()
+        .update_render_buffer_if_dirty();
+}
+
+fn viewport(state: &TerminalState) -> Vec<String> {
+    let top = state.max_display_offset() - state.display_offset;
+    state.render_buffer[top..top + state.rows]
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| cell.ch)
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn scrolled_back_view_stays_put_while_history_grows() {
+    let mut terminal = Terminal::new(4, 10);
+    terminal.state().lock().unwrap().history_lines = 50;
+    feed(&mut terminal, 0..20);
+    terminal.state().lock().unwrap().scroll_display_up(5);
+    let before = viewport(&terminal.state().lock().unwrap());
+
+    feed(&mut terminal, 20..25);
+    assert_eq!(viewport(&terminal.state().lock().unwrap()), before);
+}
+
+#[test]
+fn scrolled_back_view_stays_put_with_a_full_history() {
+    let mut terminal = Terminal::new(4, 10);
+    terminal.state().lock().unwrap().history_lines = 12;
+    feed(&mut terminal, 0..30);
+    terminal.state().lock().unwrap().scroll_display_up(5);
+    let before = viewport(&terminal.state().lock().unwrap());
+    assert_eq!(before[0], "line 22");
+
+    // Lines drop off the top as many as come in at the bottom
+    feed(&mut terminal, 30..33);
+    assert_eq!(viewport(&terminal.state().lock().unwrap()), before);
+
+    // Until the text in view itself leaves history: then the view is the top
+    feed(&mut terminal, 33..50);
+    let state = terminal.state().lock().unwrap();
+    assert_eq!(state.display_offset, state.max_display_offset());
+}
@@ -0,0 +1,179 @@
+// Selection text and highlight spans over render_buffer rows: soft-wrapped
+// lines, wide characters and word/line granularity.
+
+use wterm_core::selection::{Selection, SelectionPoint, SelectionType};
+use wterm_core::state::TerminalState;
+use wterm_core::testing::laid_out;
+use wterm_core::Terminal;
+
+fn select(
+    state: &TerminalState,
+    ty: SelectionType,
+    anchor: (usize, usize),
+    head: (usize, usize),
+) -> String {
+    let mut selection = Selection::new(ty, SelectionPoint::new(anchor.0, anchor.1));
+    selection.update(SelectionPoint::new(head.0, head.1));
+    selection.to_range(state).unwrap().text(state)
+}
+
+#[test]
+fn soft_wrapped_rows_join_without_newline() {
+    // "hello worl|d foo" - the line wraps inside "world"
+    let state = laid_out(4, 10, "hello world foo\r\nnext");
+    assert!(state.is_render_row_wrapped(0));
+    assert!(!state.is_render_row_wrapped(1));
+
+    let text = select(&state, SelectionType::Simple, (0, 6), (1, 4));
+    assert_eq!(text, "world foo");
+}
+
+#[test]
+fn hard_line_ends_become_newlines() {
+    let state = laid_out(4, 10, "one  \r\ntwo");
+    let text = select(&state, SelectionType::Simple, (0, 0), (1, 9));
+    assert_eq!(text, "one\ntwo");
+}
+
+#[test]
+fn wide_characters_are_selected_whole() {
+    // 한 takes columns 2-3, 글 columns 4-5
+    let state = laid_out(4, 10, "ab한글cd");
+
+    // Starting on the right half of 한 and ending on the left half of 글
+    let mut selection = Selection::new(SelectionType::Simple, SelectionPoint::new(0, 3));
+    selection.update(SelectionPoint::new(0, 4));
+    let range = selection.to_range(&state).unwrap();
+    assert_eq!(range.columns_in_row(0, 10), Some((2, 5)));
+    assert_eq!(range.text(&state), "한글");
+}
+
+#[test]
+fn wide_characters_split_by_a_wrap_keep_their_halves() {
+    // "12345678한|글" - 한 still fits on the first row, 글 goes to the next
+    let state = laid_out(4, 10, "12345678한글");
+    assert!(state.is_render_row_wrapped(0));
+    assert_eq!(state.render_buffer[0][9].ch, '\u{0000}');
+
+    let text = select(&state, SelectionType::Simple, (0, 8), (1, 1));
+    assert_eq!(text, "한글");
+}
+
+#[test]
+fn padding_before_a_wrapped_wide_character_is_not_copied() {
+    // "12345678 |한글" at 9 columns - 한 doesn't fit in the last cell
+    let state = laid_out(4, 9, "12345678한글");
+    assert!(state.is_render_row_wrapped(0));
+    assert_eq!(state.render_buffer[0][8].ch, ' ');
+    assert!(state.is_wrap_padding(0, 8));
+    assert!(!state.is_wrap_padding(0, 7));
+
+    let text = select(&state, SelectionType::Simple, (0, 0), (1, 3));
+    assert_eq!(text, "12345678한글");
+    let text = select(&state, SelectionType::Line, (1, 0), (1, 0));
+    assert_eq!(text, "12345678한글");
+
+    // A real space that fills the row before a wide character is kept
+    let state = laid_out(4, 9, "12345678 한글");
+    assert!(!state.is_wrap_padding(0, 8));
+    let text = select(&state, SelectionType::Simple, (0, 0), (1, 3));
+    assert_eq!(text, "12345678 한글");
+}
+
+#[test]
+fn word_selection_follows_soft_wraps() {
+    let state = laid_out(4, 10, "hello world foo");
+    assert_eq!(select(&state, SelectionType::Word, (1, 0), (1, 0)), "world");
+    assert_eq!(select(&state, SelectionType::Word, (0, 1), (0, 1)), "hello");
+}
+
+#[test]
+fn word_selection_stops_at_custom_separators() {
+    let mut state = laid_out(4, 40, "cargo run --bin=wterm-core/tests");
+    assert_eq!(
+        select(&state, SelectionType::Word, (0, 20), (0, 20)),
+        "--bin=wterm-core/tests"
+    );
+
+    state.word_separators = " =/".to_string();
+    assert_eq!(
+        select(&state, SelectionType::Word, (0, 20), (0, 20)),
+        "wterm-core"
+    );
+
+    // Dragging a word selection extends it word by word
+    assert_eq!(
+        select(&state, SelectionType::Word, (0, 2), (0, 20)),
+        "cargo run --bin=wterm-core"
+    );
+}
+
+#[test]
+fn word_selection_includes_wide_characters() {
+    let state = laid_out(4, 20, "echo 안녕하세요 done");
+    assert_eq!(
+        select(&state, SelectionType::Word, (0, 8), (0, 8)),
+        "안녕하세요"
+    );
+}
+
+#[test]
+fn line_selection_covers_the_logical_line() {
+    let state = laid_out(5, 10, "first\r\nhello world foo\r\nlast");
+    // Clicking on the wrapped continuation selects the whole logical line
+    assert_eq!(
+        select(&state, SelectionType::Line, (2, 2), (2, 2)),
+        "hello world foo"
+    );
+    assert_eq!(
+        select(&state, SelectionType::Line, (0, 0), (1, 0)),
+        "first\nhello world foo"
+    );
+}
+
+#[test]
+fn rows_outside_the_selection_have_no_columns() {
+    let state = laid_out(4, 10, "hello world foo");
+    let mut selection = Selection::new(SelectionType::Simple, SelectionPoint::new(0, 6));
+    selection.update(SelectionPoint::new(1, 2));
+    let range = selection.to_range(&state).unwrap();
+
+    assert_eq!(range.columns_in_row(0, 10), Some((6, 9)));
+    assert_eq!(range.columns_in_row(1, 10), Some((0, 2)));
+    assert_eq!(range.columns_in_row(2, 10), None);
+}
+
+#[test]
+fn block_selection_spans_the_corners_in_any_drag_direction() {
+    let state = laid_out(4, 10, "abcdef\r\nghijkl\r\nmnopqr");
+    let mut selection = Selection::new(SelectionType::Block, SelectionPoint::new(2, 1));
+    selection.update(SelectionPoint::new(0, 3));
+    let range = selection.to_range(&state).unwrap();
+
+    assert!(range.is_block);
+    assert_eq!(range.start, SelectionPoint::new(0, 1));
+    assert_eq!(range.end, SelectionPoint::new(2, 3));
+    assert_eq!(range.text(&state), "bcd\nhij\nnop");
+}
+
+#[test]
+fn selection_stays_on_its_text_when_a_wrapped_line_leaves_history() {
+    // Written at 20 columns, so after narrowing "hello world foo" is one
+    // main_buffer line over two render rows
+    let mut terminal = Terminal::new(3, 20);
+    terminal.advance(b"hello world foo\r\nsecond\r\nthird");
+    terminal.resize(3, 10);
+    let mut state = terminal.state().lock().unwrap().clone();
+    state.history_lines = 3;
+    assert_eq!(state.render_buffer.len(), 4);
+
+    let mut selection = Selection::new(SelectionType::Simple, SelectionPoint::new(2, 0));
+    selection.update(SelectionPoint::new(2, 5));
+    state.selection = Some(selection);
+
+    state.newline(); // Drops "hello world foo"
+    state.incremental_update = false;
+    state.force_update_render_buffer();
+    let range = state.selection.as_ref().unwrap().to_range(&state).unwrap();
+    assert_eq!(range.text(&state), "second");
+}